@@ -0,0 +1,74 @@
+use crate::core::{
+    presets::{self, Preset},
+    Layer,
+};
+
+/// Applies built-in remap presets to a layer of a model's factory default profiles and prints what changed and what
+/// was skipped. Presets are separated by `+` (`dvorak+caps-as-ctrl`), a later one wins for the keys both remap.
+/// `--layer` picks the layer (`Layer2` or `2`, base by default), `--fn` its Fn keys and `--list` prints the presets.
+pub fn run(args: &[String]) -> i32 {
+    let mut positional = vec![];
+    let mut layer = Layer::Base;
+    let mut fn_layer = false;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fn" => fn_layer = true,
+            "--list" => {
+                for preset in presets::PRESETS {
+                    println!("{:<14} {}", preset.name, preset.description);
+                }
+                return 0;
+            }
+            "--layer" => {
+                let Some(value) = args.next() else {
+                    eprintln!("Missing value after `{arg}`");
+                    return 1;
+                };
+                match Layer::from_name(value) {
+                    Some(value) => layer = value,
+                    None => {
+                        eprintln!(
+                            "Unknown layer `{value}` (Base, Layer1, Layer2, Layer3, Driver or 0-3)"
+                        );
+                        return 1;
+                    }
+                }
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let [model_id, names] = positional[..] else {
+        eprintln!("Usage: gk6xui apply-preset <model id> <preset>[+<preset>...] [--layer <layer>] [--fn] | --list");
+        return 1;
+    };
+    let Ok(model_id) = model_id.parse() else {
+        eprintln!("Invalid model id `{model_id}`");
+        return 1;
+    };
+    let mut selected = vec![];
+    for name in names.split('+') {
+        match Preset::find(name) {
+            Some(preset) => selected.push(preset),
+            None => {
+                eprintln!("Unknown preset `{name}`, see `apply-preset --list`");
+                return 1;
+            }
+        }
+    }
+
+    let report = match presets::apply_to_factory_default(model_id, layer, fn_layer, &selected) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("Failed to apply {names}: {err}");
+            return 1;
+        }
+    };
+    for change in &report.summary {
+        println!("{change}");
+    }
+    for skipped in &report.skipped {
+        println!("Skipped: {skipped}");
+    }
+    0
+}
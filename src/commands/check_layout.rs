@@ -0,0 +1,75 @@
+use crate::core::{
+    presets::Preset,
+    sanity::{self, Severity},
+    Layer,
+};
+
+/// Checks the factory default profiles of a model for layers without a way back to the base layer, shadowed Fn
+/// shortcuts and lost essential keys. `--preset <preset>[+<preset>...]` applies presets first (see `apply-preset`),
+/// to the layer given by `--layer` (`Layer2` or `2`) or every layer, and to their Fn keys with `--fn`. Fails if there
+/// are errors, or warnings with `--strict`.
+pub fn run(args: &[String]) -> i32 {
+    let mut model_id = None;
+    let mut strict = false;
+    let mut fn_layer = false;
+    let mut layers = Layer::ALL.to_vec();
+    let mut presets = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--strict" => strict = true,
+            "--fn" => fn_layer = true,
+            "--layer" | "--preset" => {
+                let Some(value) = args.next() else {
+                    eprintln!("Missing value after `{arg}`");
+                    return 1;
+                };
+                if arg == "--layer" {
+                    match Layer::from_name(value) {
+                        Some(value) => layers = vec![value],
+                        None => {
+                            eprintln!(
+                                "Unknown layer `{value}` (Base, Layer1, Layer2, Layer3, Driver or 0-3)"
+                            );
+                            return 1;
+                        }
+                    }
+                    continue;
+                }
+                for name in value.split('+') {
+                    match Preset::find(name) {
+                        Some(preset) => presets.push(preset),
+                        None => {
+                            eprintln!("Unknown preset `{name}`, see `apply-preset --list`");
+                            return 1;
+                        }
+                    }
+                }
+            }
+            _ => model_id = Some(arg),
+        }
+    }
+    let Some(model_id) = model_id else {
+        eprintln!("Usage: gk6xui check-layout <model id> [--preset <preset>[+<preset>...]] [--layer <layer>] [--fn] [--strict]");
+        return 1;
+    };
+    let Ok(model_id) = model_id.parse() else {
+        eprintln!("Invalid model id `{model_id}`");
+        return 1;
+    };
+
+    let issues = match sanity::check_factory_default(model_id, &presets, &layers, fn_layer) {
+        Ok(issues) => issues,
+        Err(err) => {
+            eprintln!("Failed to check model {model_id}: {err}");
+            return 1;
+        }
+    };
+    for issue in &issues {
+        println!("{issue}");
+    }
+    let fails = issues
+        .iter()
+        .any(|issue| strict || issue.severity == Severity::Error);
+    i32::from(fails)
+}
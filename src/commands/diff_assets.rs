@@ -0,0 +1,24 @@
+use crate::core::asset_diff;
+
+/// Compares two asset trees and prints a changelog of everything which changed, or the changes as JSON with `--json`
+pub fn run(args: &[String]) -> i32 {
+    let json = args.iter().any(|arg| arg == "--json");
+    let paths = args
+        .iter()
+        .filter(|arg| !arg.starts_with("--"))
+        .collect::<Vec<_>>();
+    let [old_path, new_path] = paths[..] else {
+        eprintln!("Usage: gk6xui diff-assets <old assets> <new assets> [--json]");
+        return 1;
+    };
+
+    let changes = asset_diff::diff_assets(old_path, new_path);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes).unwrap());
+    } else if changes.is_empty() {
+        println!("No changes");
+    } else {
+        print!("{}", asset_diff::format_changelog(&changes));
+    }
+    0
+}
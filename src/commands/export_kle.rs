@@ -0,0 +1,63 @@
+use std::fs;
+
+use crate::core::{kle, Layer};
+
+/// Prints a layer of a model's factory default profiles as Keyboard Layout Editor raw JSON, with the bound actions
+/// as legends (or writes it to `--out <path>`). `--layer` picks the layer (`Layer2` or `2`, base by default) and
+/// `--fn` its Fn keys.
+pub fn run(args: &[String]) -> i32 {
+    let mut model_id = None;
+    let mut layer = Layer::Base;
+    let mut fn_layer = false;
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--fn" => fn_layer = true,
+            "--layer" | "--out" => {
+                let Some(value) = args.next() else {
+                    eprintln!("Missing value after `{arg}`");
+                    return 1;
+                };
+                if arg == "--out" {
+                    out = Some(value.clone());
+                    continue;
+                }
+                match Layer::from_name(value) {
+                    Some(value) => layer = value,
+                    None => {
+                        eprintln!(
+                            "Unknown layer `{value}` (Base, Layer1, Layer2, Layer3, Driver or 0-3)"
+                        );
+                        return 1;
+                    }
+                }
+            }
+            _ => model_id = Some(arg),
+        }
+    }
+    let Some(model_id) = model_id else {
+        eprintln!("Usage: gk6xui export-kle <model id> [--layer <layer>] [--fn] [--out <path>]");
+        return 1;
+    };
+    let Ok(model_id) = model_id.parse() else {
+        eprintln!("Invalid model id `{model_id}`");
+        return 1;
+    };
+
+    let Some(kle) = kle::export_factory_default(model_id, layer, fn_layer) else {
+        eprintln!("Unknown model {model_id}");
+        return 1;
+    };
+    let json = serde_json::to_string_pretty(&kle).unwrap();
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, json) {
+                eprintln!("Failed to write {path}: {err}");
+                return 1;
+            }
+        }
+        None => println!("{json}"),
+    }
+    0
+}
@@ -0,0 +1,51 @@
+use std::fs;
+
+use crate::core::qmk;
+
+/// Prints the factory default keymap of a model's onboard layers as a QMK `keymap.json` (or writes it to
+/// `--out <path>`). Actions QMK can't express are exported as `KC_NO` and listed on stderr.
+pub fn run(args: &[String]) -> i32 {
+    let mut model_id = None;
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => match args.next() {
+                Some(path) => out = Some(path.clone()),
+                None => {
+                    eprintln!("Missing path after `--out`");
+                    return 1;
+                }
+            },
+            _ => model_id = Some(arg),
+        }
+    }
+    let Some(model_id) = model_id else {
+        eprintln!("Usage: gk6xui export-qmk <model id> [--out <path>]");
+        return 1;
+    };
+    let Ok(model_id) = model_id.parse() else {
+        eprintln!("Invalid model id `{model_id}`");
+        return 1;
+    };
+
+    let Some(export) = qmk::export_factory_default(model_id) else {
+        eprintln!("Unknown model {model_id}");
+        return 1;
+    };
+    for action in &export.unsupported {
+        eprintln!("Not supported by QMK: {action}");
+    }
+
+    let json = serde_json::to_string_pretty(&export.keymap).unwrap();
+    match out {
+        Some(path) => {
+            if let Err(err) = fs::write(&path, json) {
+                eprintln!("Failed to write {path}: {err}");
+                return 1;
+            }
+        }
+        None => println!("{json}"),
+    }
+    0
+}
@@ -0,0 +1,53 @@
+use std::fs;
+
+use serde_json::Value;
+
+use crate::core::kle;
+
+/// Turns a Keyboard Layout Editor file into a vendor style `keymap.json` for a board without data files (printed,
+/// or written to `--out <path>`). The logic codes are left at -1 and have to be filled in.
+pub fn run(args: &[String]) -> i32 {
+    let mut path = None;
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => match args.next() {
+                Some(value) => out = Some(value.clone()),
+                None => {
+                    eprintln!("Missing path after `--out`");
+                    return 1;
+                }
+            },
+            _ => path = Some(arg),
+        }
+    }
+    let Some(path) = path else {
+        eprintln!("Usage: gk6xui import-kle <layout.json> [--out <keymap.json>]");
+        return 1;
+    };
+
+    let keys = match fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| serde_json::from_slice::<Value>(&bytes).map_err(|err| err.to_string()))
+        .and_then(|kle| kle::parse(&kle))
+    {
+        Ok(keys) => keys,
+        Err(err) => {
+            eprintln!("Failed to read {path}: {err}");
+            return 1;
+        }
+    };
+    let json = serde_json::to_string_pretty(&kle::to_keymap(&keys)).unwrap();
+    match out {
+        Some(out) => {
+            if let Err(err) = fs::write(&out, json) {
+                eprintln!("Failed to write {out}: {err}");
+                return 1;
+            }
+            println!("{} keys written to {out}", keys.len());
+        }
+        None => println!("{json}"),
+    }
+    0
+}
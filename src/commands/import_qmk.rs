@@ -0,0 +1,84 @@
+use std::fs;
+
+use serde_json::Value;
+
+use crate::core::qmk;
+
+/// Imports a QMK / VIA `keymap.json` onto layers 1 - 3 of a model's factory default profiles and prints what changed
+/// and what couldn't be placed. `--layout <info.json>` places the keys by the geometry of a QMK `info.json`
+/// (`--layout-name` picks one of its layouts) or a KLE file, otherwise the keymap has to follow the model's layout
+/// (see `export-qmk`).
+pub fn run(args: &[String]) -> i32 {
+    let mut positional = vec![];
+    let mut layout_path = None;
+    let mut layout_name = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--layout" => &mut layout_path,
+            "--layout-name" => &mut layout_name,
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        };
+        match args.next() {
+            Some(arg) => *value = Some(arg.clone()),
+            None => {
+                eprintln!("Missing value after `{arg}`");
+                return 1;
+            }
+        }
+    }
+    let [model_id, keymap_path] = positional[..] else {
+        eprintln!("Usage: gk6xui import-qmk <model id> <keymap.json> [--layout <info.json / KLE>] [--layout-name <name>]");
+        return 1;
+    };
+    let Ok(model_id) = model_id.parse() else {
+        eprintln!("Invalid model id `{model_id}`");
+        return 1;
+    };
+
+    let keymap = match read_json(keymap_path) {
+        Ok(keymap) => keymap,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+    let geometry = match layout_path {
+        Some(path) => match read_json(&path)
+            .and_then(|info| qmk::parse_geometry(&info, layout_name.as_deref()))
+        {
+            Ok(geometry) => geometry,
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                return 1;
+            }
+        },
+        None => vec![],
+    };
+
+    let report = match qmk::import_onto_factory_default(model_id, &keymap, &geometry) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("Failed to import {keymap_path}: {err}");
+            return 1;
+        }
+    };
+    for (qmk_layer, layer) in &report.layers {
+        println!("QMK layer {qmk_layer} -> {layer}");
+    }
+    for change in &report.summary {
+        println!("{change}");
+    }
+    for issue in &report.issues {
+        println!("Not placed: {issue}");
+    }
+    0
+}
+
+fn read_json(path: &str) -> Result<Value, String> {
+    let bytes = fs::read(path).map_err(|err| format!("Failed to read {path}: {err}"))?;
+    serde_json::from_slice(&bytes).map_err(|err| format!("Failed to parse {path}: {err}"))
+}
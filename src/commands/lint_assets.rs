@@ -0,0 +1,43 @@
+use std::{collections::HashSet, fs};
+
+use crate::core::lint;
+
+const ASSETS_PATH: &str = "assets";
+const REPORT_PATH: &str = "assets/DuplicateKeys.txt";
+
+/// Prints every issue as JSON and regenerates the human readable report. With `--check` the report is compared
+/// against the one on disk instead, failing if the assets have changed without the report being regenerated.
+pub fn run(args: &[String]) -> i32 {
+    let check = args.iter().any(|arg| arg == "--check");
+
+    let issues = lint::lint_assets(ASSETS_PATH);
+    let report = lint::format_report(&issues);
+
+    if check {
+        let existing = fs::read_to_string(REPORT_PATH).unwrap_or_default();
+        if existing == report {
+            return 0;
+        }
+        // New lines are regressions in the assets (or the checks), missing ones need the report regenerating
+        let existing_lines = existing.lines().collect::<HashSet<_>>();
+        let report_lines = report.lines().collect::<HashSet<_>>();
+        for line in report.lines().filter(|line| !existing_lines.contains(line)) {
+            eprintln!("+ {line}");
+        }
+        for line in existing.lines().filter(|line| !report_lines.contains(line)) {
+            eprintln!("- {line}");
+        }
+        eprintln!(
+            "{REPORT_PATH} is out of date ({} issues), run `gk6xui lint-assets` to regenerate it",
+            issues.len()
+        );
+        return 1;
+    }
+
+    println!("{}", serde_json::to_string_pretty(&issues).unwrap());
+    if let Err(err) = fs::write(REPORT_PATH, report) {
+        eprintln!("Failed to write {REPORT_PATH}: {err}");
+        return 1;
+    }
+    0
+}
@@ -0,0 +1,84 @@
+mod apply_preset;
+mod check_layout;
+mod diff_assets;
+mod export_kle;
+mod export_qmk;
+mod import_kle;
+mod import_qmk;
+mod lint_assets;
+mod update_data;
+
+/// A command line command. Returns the process exit code.
+type Command = fn(&[String]) -> i32;
+
+const COMMANDS: &[(&str, &str, Command)] = &[
+    (
+        "apply-preset",
+        "<model id> <preset>[+<preset>...] [--layer <layer>] [--fn] | --list  Apply remap presets (dvorak, caps-as-ctrl, ...) to a factory default layer",
+        apply_preset::run,
+    ),
+    (
+        "check-layout",
+        "<model id> [--preset <presets>] [--layer <layer>] [--fn] [--strict]  Check layers for ways back to the base layer, shadowed Fn shortcuts and lost keys",
+        check_layout::run,
+    ),
+    (
+        "diff-assets",
+        "<old> <new> [--json]  List what changed between two asset trees (models, keymaps, profiles, keys.json)",
+        diff_assets::run,
+    ),
+    (
+        "export-kle",
+        "<model id> [--layer <layer>] [--fn] [--out <path>]  Export a factory default layer as Keyboard Layout Editor JSON",
+        export_kle::run,
+    ),
+    (
+        "export-qmk",
+        "<model id> [--out <path>]  Export the factory default layers of a model as a QMK keymap.json",
+        export_qmk::run,
+    ),
+    (
+        "import-kle",
+        "<layout.json> [--out <keymap.json>]  Turn a Keyboard Layout Editor file into a keymap.json for a custom board",
+        import_kle::run,
+    ),
+    (
+        "import-qmk",
+        "<model id> <keymap.json> [--layout <info.json / KLE>] [--layout-name <name>]  Import a QMK keymap onto layers 1 - 3",
+        import_qmk::run,
+    ),
+    (
+        "lint-assets",
+        "[--check]  Check the vendor data files and regenerate assets/DuplicateKeys.txt",
+        lint_assets::run,
+    ),
+    (
+        "update_data",
+        "<folder> [--out <path>]  Import the data files of an unpacked vendor driver",
+        update_data::run,
+    ),
+];
+
+pub fn run(args: &[String]) -> i32 {
+    let Some(name) = args.first() else {
+        print_usage();
+        return 0;
+    };
+
+    match COMMANDS.iter().find(|(command, _, _)| command == name) {
+        Some((_, _, command)) => command(&args[1..]),
+        None => {
+            eprintln!("Unknown command `{name}`");
+            print_usage();
+            1
+        }
+    }
+}
+
+fn print_usage() {
+    println!("Usage: gk6xui <command> [args]");
+    println!();
+    for (name, help, _) in COMMANDS {
+        println!("  {name} {help}");
+    }
+}
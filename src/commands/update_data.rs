@@ -0,0 +1,53 @@
+use crate::core::vendor_data;
+
+const ASSETS_PATH: &str = "assets";
+
+/// Imports the data files of an unpacked vendor driver (see `assets/Updating.txt`). The destination defaults to
+/// `assets`, `--out <path>` writes them somewhere else.
+pub fn run(args: &[String]) -> i32 {
+    let mut source = None;
+    let mut assets_path = ASSETS_PATH.to_string();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => match args.next() {
+                Some(path) => assets_path = path.clone(),
+                None => {
+                    eprintln!("Missing path after `--out`");
+                    return 1;
+                }
+            },
+            _ => source = Some(arg),
+        }
+    }
+    let Some(source) = source else {
+        eprintln!("Usage: gk6xui update_data <folder> [--out <assets path>]");
+        return 1;
+    };
+
+    let summary = match vendor_data::update_data(source, &assets_path) {
+        Ok(summary) => summary,
+        Err(err) => {
+            eprintln!("Failed to update the data files: {err}");
+            return 1;
+        }
+    };
+
+    for driver_path in &summary.driver_folders {
+        println!("Driver: {}", driver_path.display());
+    }
+    if let Some((file, group_count)) = &summary.keys {
+        println!("Keys: {group_count} groups from {}", file.display());
+    }
+    if !summary.languages.is_empty() {
+        println!("Languages: {}", summary.languages.join(", "));
+    }
+    println!("Device files: {}", summary.device_files);
+    for path in &summary.copied_as_is {
+        println!("Copied without normalizing: {}", path.display());
+    }
+    for warning in &summary.warnings {
+        eprintln!("Warning: {warning}");
+    }
+    0
+}
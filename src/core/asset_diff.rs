@@ -0,0 +1,640 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt, fs,
+    path::Path,
+};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{js_value::JsValue, profile};
+
+/// A difference between two asset trees, see [`diff_assets`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    ModelAdded {
+        model_id: u32,
+        name: String,
+    },
+    ModelRemoved {
+        model_id: u32,
+        name: String,
+    },
+    /// A field of the model's `models.json` entry (name, firmwareID, LEType)
+    ModelInfoChanged {
+        model_id: u32,
+        field: String,
+        old: Value,
+        new: Value,
+    },
+    /// A data file (keymap / profile) which only exists in one of the trees
+    FileAdded {
+        model_id: u32,
+        file: String,
+    },
+    FileRemoved {
+        model_id: u32,
+        file: String,
+    },
+    KeyAdded {
+        model_id: u32,
+        file: String,
+        location_code: Value,
+        key_name: String,
+    },
+    KeyRemoved {
+        model_id: u32,
+        file: String,
+        location_code: Value,
+        key_name: String,
+    },
+    /// The name, logic code or position of a keymap key (keys are matched by location code)
+    KeyChanged {
+        model_id: u32,
+        file: String,
+        location_code: Value,
+        key_name: String,
+        field: String,
+        old: Value,
+        new: Value,
+    },
+    /// The default DriverValue of a profile's KeySet / FnKeySet entry. `None` when there's no entry for the index.
+    KeySetChanged {
+        model_id: u32,
+        file: String,
+        key_set: String,
+        index: i64,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// A top level field of `config.json` (FuncTable, QuickSelect, IsFnKeySet, etc)
+    ConfigChanged {
+        model_id: u32,
+        field: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+    /// A group of `keys.json`, by keytype
+    GroupAdded {
+        group: String,
+        title: String,
+    },
+    GroupRemoved {
+        group: String,
+        title: String,
+    },
+    GroupChanged {
+        group: String,
+        field: String,
+        old: Value,
+        new: Value,
+    },
+    /// A key of a `keys.json` group, matched by DriverValue
+    CatalogKeyAdded {
+        group: String,
+        driver_value: String,
+        name: String,
+    },
+    CatalogKeyRemoved {
+        group: String,
+        driver_value: String,
+        name: String,
+    },
+    CatalogKeyChanged {
+        group: String,
+        driver_value: String,
+        name: String,
+        field: String,
+        old: Option<Value>,
+        new: Option<Value>,
+    },
+}
+
+impl Change {
+    /// The model the change belongs to, `None` for `keys.json` changes
+    pub fn model_id(&self) -> Option<u32> {
+        match self {
+            Change::ModelAdded { model_id, .. }
+            | Change::ModelRemoved { model_id, .. }
+            | Change::ModelInfoChanged { model_id, .. }
+            | Change::FileAdded { model_id, .. }
+            | Change::FileRemoved { model_id, .. }
+            | Change::KeyAdded { model_id, .. }
+            | Change::KeyRemoved { model_id, .. }
+            | Change::KeyChanged { model_id, .. }
+            | Change::KeySetChanged { model_id, .. }
+            | Change::ConfigChanged { model_id, .. } => Some(*model_id),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::ModelAdded { model_id, name } => write!(f, "Added model {model_id} ({name})"),
+            Change::ModelRemoved { model_id, name } => {
+                write!(f, "Removed model {model_id} ({name})")
+            }
+            Change::ModelInfoChanged {
+                field, old, new, ..
+            } => write!(f, "{field}: {old} -> {new}"),
+            Change::FileAdded { file, .. } => write!(f, "Added {file}"),
+            Change::FileRemoved { file, .. } => write!(f, "Removed {file}"),
+            Change::KeyAdded {
+                file,
+                location_code,
+                key_name,
+                ..
+            } => write!(f, "{file}: added key {key_name} (location {location_code})"),
+            Change::KeyRemoved {
+                file,
+                location_code,
+                key_name,
+                ..
+            } => write!(
+                f,
+                "{file}: removed key {key_name} (location {location_code})"
+            ),
+            Change::KeyChanged {
+                file,
+                location_code,
+                key_name,
+                field,
+                old,
+                new,
+                ..
+            } => write!(
+                f,
+                "{file}: key {key_name} (location {location_code}) {field} {old} -> {new}"
+            ),
+            Change::KeySetChanged {
+                file,
+                key_set,
+                index,
+                old,
+                new,
+                ..
+            } => write!(
+                f,
+                "{file}: {key_set}[{index}] {} -> {}",
+                old.as_deref().unwrap_or("(none)"),
+                new.as_deref().unwrap_or("(none)")
+            ),
+            Change::ConfigChanged {
+                field, old, new, ..
+            } => write!(
+                f,
+                "config.json: {field} {} -> {}",
+                optional_value(old),
+                optional_value(new)
+            ),
+            Change::GroupAdded { group, title } => write!(f, "Added group {group} ({title})"),
+            Change::GroupRemoved { group, title } => {
+                write!(f, "Removed group {group} ({title})")
+            }
+            Change::GroupChanged {
+                group,
+                field,
+                old,
+                new,
+            } => write!(f, "Group {group}: {field} {old} -> {new}"),
+            Change::CatalogKeyAdded {
+                group,
+                driver_value,
+                name,
+            } => write!(f, "{group}: added {name} ({driver_value})"),
+            Change::CatalogKeyRemoved {
+                group,
+                driver_value,
+                name,
+            } => write!(f, "{group}: removed {name} ({driver_value})"),
+            Change::CatalogKeyChanged {
+                group,
+                driver_value,
+                name,
+                field,
+                old,
+                new,
+            } => write!(
+                f,
+                "{group}: {name} ({driver_value}) {field} {} -> {}",
+                optional_value(old),
+                optional_value(new)
+            ),
+        }
+    }
+}
+
+fn optional_value(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => "(none)".to_string(),
+    }
+}
+
+/// Compares two asset trees (e.g. the current `assets` and the output of `update_data`)
+pub fn diff_assets(old_path: impl AsRef<Path>, new_path: impl AsRef<Path>) -> Vec<Change> {
+    let old_path = old_path.as_ref();
+    let new_path = new_path.as_ref();
+    let mut changes = Vec::new();
+
+    diff_models(old_path, new_path, &mut changes);
+    diff_catalog(
+        &read_json(old_path.join("keys.json")),
+        &read_json(new_path.join("keys.json")),
+        &mut changes,
+    );
+    changes
+}
+
+/// Formats the changes as a changelog, grouped by model
+pub fn format_changelog(changes: &[Change]) -> String {
+    let mut result = String::new();
+
+    let catalog = changes
+        .iter()
+        .filter(|change| change.model_id().is_none())
+        .collect::<Vec<_>>();
+    if !catalog.is_empty() {
+        result += "## keys.json\n\n";
+        for change in catalog {
+            result += &format!("- {}\n", change);
+        }
+        result += "\n";
+    }
+
+    let mut by_model = BTreeMap::<u32, Vec<&Change>>::new();
+    for change in changes {
+        if let Some(model_id) = change.model_id() {
+            by_model.entry(model_id).or_default().push(change);
+        }
+    }
+    for (model_id, changes) in by_model {
+        result += &format!("## {}\n\n", model_id);
+        for change in changes {
+            result += &format!("- {}\n", change);
+        }
+        result += "\n";
+    }
+    result
+}
+
+/// Reads a data file, accepting the usual vendor quirks (BOM, JS declarations, trailing garbage)
+fn read_json(path: impl AsRef<Path>) -> Option<Value> {
+    let bytes = fs::read(path).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    JsValue::parse_at(&text, 0)
+        .ok()
+        .map(|(value, _)| Value::from(&value))
+}
+
+fn model_list(assets_path: &Path) -> BTreeMap<u32, Value> {
+    read_json(assets_path.join("device").join("models.json"))
+        .and_then(|models| models.as_array().cloned())
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|model| Some((model.get("modelID")?.as_u64()? as u32, model)))
+        .collect()
+}
+
+/// The numeric model folders of `device`
+fn model_folders(assets_path: &Path) -> BTreeSet<u32> {
+    fs::read_dir(assets_path.join("device"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+        .collect()
+}
+
+fn diff_models(old_path: &Path, new_path: &Path, changes: &mut Vec<Change>) {
+    let old_models = model_list(old_path);
+    let new_models = model_list(new_path);
+    let model_name = |model: &Value| {
+        model
+            .get("name")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let mut model_ids = model_folders(old_path);
+    model_ids.extend(model_folders(new_path));
+    model_ids.extend(old_models.keys());
+    model_ids.extend(new_models.keys());
+
+    for model_id in model_ids {
+        match (old_models.get(&model_id), new_models.get(&model_id)) {
+            (None, Some(model)) => changes.push(Change::ModelAdded {
+                model_id,
+                name: model_name(model),
+            }),
+            (Some(model), None) => changes.push(Change::ModelRemoved {
+                model_id,
+                name: model_name(model),
+            }),
+            (Some(old), Some(new)) => {
+                for (field, old_value, new_value) in diff_fields(old, new) {
+                    changes.push(Change::ModelInfoChanged {
+                        model_id,
+                        field,
+                        old: old_value.unwrap_or(Value::Null),
+                        new: new_value.unwrap_or(Value::Null),
+                    });
+                }
+            }
+            (None, None) => {}
+        }
+
+        let old_model_path = old_path.join("device").join(model_id.to_string());
+        let new_model_path = new_path.join("device").join(model_id.to_string());
+        diff_config(
+            model_id,
+            &read_json(old_model_path.join("config.json")),
+            &read_json(new_model_path.join("config.json")),
+            changes,
+        );
+
+        let mut file_names = data_files(&old_model_path.join("data"));
+        file_names.extend(data_files(&new_model_path.join("data")));
+        for file in file_names {
+            let old = read_json(old_model_path.join("data").join(&file));
+            let new = read_json(new_model_path.join("data").join(&file));
+            match (&old, &new) {
+                (None, Some(_)) => changes.push(Change::FileAdded { model_id, file }),
+                (Some(_), None) => changes.push(Change::FileRemoved { model_id, file }),
+                (Some(old), Some(new)) if file.starts_with("keymap") => {
+                    diff_keymap(model_id, &file, old, new, changes)
+                }
+                (Some(old), Some(new)) => diff_profile(model_id, &file, old, new, changes),
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+/// The keymap / profile files of a model's data folder
+fn data_files(data_path: &Path) -> BTreeSet<String> {
+    fs::read_dir(data_path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|file| {
+            file.ends_with(".json") && (file.starts_with("keymap") || file.starts_with("profile"))
+        })
+        .collect()
+}
+
+/// Compares the top level fields of two objects
+fn diff_fields(old: &Value, new: &Value) -> Vec<(String, Option<Value>, Option<Value>)> {
+    let empty = serde_json::Map::new();
+    let old = old.as_object().unwrap_or(&empty);
+    let new = new.as_object().unwrap_or(&empty);
+
+    let fields = old.keys().chain(new.keys()).collect::<BTreeSet<_>>();
+    fields
+        .into_iter()
+        .filter(|field| old.get(*field) != new.get(*field))
+        .map(|field| {
+            (
+                field.clone(),
+                old.get(field).cloned(),
+                new.get(field).cloned(),
+            )
+        })
+        .collect()
+}
+
+fn diff_config(model_id: u32, old: &Option<Value>, new: &Option<Value>, changes: &mut Vec<Change>) {
+    let null = Value::Null;
+    for (field, old, new) in
+        diff_fields(old.as_ref().unwrap_or(&null), new.as_ref().unwrap_or(&null))
+    {
+        changes.push(Change::ConfigChanged {
+            model_id,
+            field,
+            old,
+            new,
+        });
+    }
+}
+
+/// Groups the entries of an array by an identity, numbering duplicates so they're matched up in order
+fn keyed_entries(
+    entries: Option<&Value>,
+    identity: impl Fn(&Value) -> String,
+) -> BTreeMap<(String, usize), &Value> {
+    let mut result = BTreeMap::new();
+    for entry in entries.and_then(Value::as_array).into_iter().flatten() {
+        let identity = identity(entry);
+        let occurrence = result
+            .keys()
+            .filter(|(existing, _)| *existing == identity)
+            .count();
+        result.insert((identity, occurrence), entry);
+    }
+    result
+}
+
+fn diff_keymap(model_id: u32, file: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    let location_code = |key: &Value| key.get("LocationCode").cloned().unwrap_or_default();
+    let key_name = |key: &Value| {
+        key.get("KeyName")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    // Sort numerically rather than by the text of the location code
+    let identity = |key: &Value| format!("{:>12}", location_code(key).to_string());
+
+    let old_keys = keyed_entries(Some(old), identity);
+    let new_keys = keyed_entries(Some(new), identity);
+    let identities = old_keys
+        .keys()
+        .chain(new_keys.keys())
+        .collect::<BTreeSet<_>>();
+    for identity in identities {
+        match (old_keys.get(identity), new_keys.get(identity)) {
+            (None, Some(key)) => changes.push(Change::KeyAdded {
+                model_id,
+                file: file.to_string(),
+                location_code: location_code(key),
+                key_name: key_name(key),
+            }),
+            (Some(key), None) => changes.push(Change::KeyRemoved {
+                model_id,
+                file: file.to_string(),
+                location_code: location_code(key),
+                key_name: key_name(key),
+            }),
+            (Some(old_key), Some(new_key)) => {
+                for field in ["KeyName", "LogicCode", "Position"] {
+                    let old_value = old_key.get(field).cloned().unwrap_or_default();
+                    let new_value = new_key.get(field).cloned().unwrap_or_default();
+                    if old_value != new_value {
+                        changes.push(Change::KeyChanged {
+                            model_id,
+                            file: file.to_string(),
+                            location_code: location_code(new_key),
+                            key_name: key_name(new_key),
+                            field: field.to_string(),
+                            old: old_value,
+                            new: new_value,
+                        });
+                    }
+                }
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn diff_profile(model_id: u32, file: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    for key_set in ["KeySet", "FnKeySet"] {
+        let driver_values = |profile: &Value| {
+            profile
+                .get(key_set)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| {
+                    let index = entry.get("Index")?.as_i64()?;
+                    let driver_value = entry.get("DriverValue")?.as_str()?;
+                    // Compare the values rather than how they're written ("0x0200E000" vs "0x0200e000")
+                    let driver_value = profile::parse_driver_value(driver_value)
+                        .map(profile::format_driver_value)
+                        .unwrap_or_else(|| driver_value.to_string());
+                    Some((index, driver_value))
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+        let old_values = driver_values(old);
+        let new_values = driver_values(new);
+
+        let indices = old_values
+            .keys()
+            .chain(new_values.keys())
+            .collect::<BTreeSet<_>>();
+        for index in indices {
+            let old_value = old_values.get(index);
+            let new_value = new_values.get(index);
+            if old_value != new_value {
+                changes.push(Change::KeySetChanged {
+                    model_id,
+                    file: file.to_string(),
+                    key_set: key_set.to_string(),
+                    index: *index,
+                    old: old_value.cloned(),
+                    new: new_value.cloned(),
+                });
+            }
+        }
+    }
+}
+
+fn diff_catalog(old: &Option<Value>, new: &Option<Value>, changes: &mut Vec<Change>) {
+    let string_field = |value: &Value, field: &str| {
+        value
+            .get(field)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+    let group_identity = |group: &Value| string_field(group, "keytype");
+
+    let old_groups = keyed_entries(old.as_ref(), group_identity);
+    let new_groups = keyed_entries(new.as_ref(), group_identity);
+    let identities = old_groups
+        .keys()
+        .chain(new_groups.keys())
+        .collect::<BTreeSet<_>>();
+    for identity in identities {
+        let group_name = identity.0.clone();
+        let (old_group, new_group) = match (old_groups.get(identity), new_groups.get(identity)) {
+            (None, Some(group)) => {
+                changes.push(Change::GroupAdded {
+                    group: group_name,
+                    title: string_field(group, "title"),
+                });
+                continue;
+            }
+            (Some(group), None) => {
+                changes.push(Change::GroupRemoved {
+                    group: group_name,
+                    title: string_field(group, "title"),
+                });
+                continue;
+            }
+            (Some(old_group), Some(new_group)) => (*old_group, *new_group),
+            (None, None) => continue,
+        };
+
+        for (field, old_value, new_value) in diff_fields(old_group, new_group) {
+            if field != "keys" {
+                changes.push(Change::GroupChanged {
+                    group: group_name.clone(),
+                    field,
+                    old: old_value.unwrap_or_default(),
+                    new: new_value.unwrap_or_default(),
+                });
+            }
+        }
+
+        // The rows ("keys") only affect how the keys are laid out in the vendor UI
+        let line_keys = |group: &Value| {
+            Value::Array(
+                group
+                    .get("keys")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|row| row.get("linekeys")?.as_array())
+                    .flatten()
+                    .cloned()
+                    .collect(),
+            )
+        };
+        let old_keys = line_keys(old_group);
+        let new_keys = line_keys(new_group);
+        let key_identity = |key: &Value| string_field(key, "DriverValue");
+        let old_keys = keyed_entries(Some(&old_keys), key_identity);
+        let new_keys = keyed_entries(Some(&new_keys), key_identity);
+
+        let key_identities = old_keys
+            .keys()
+            .chain(new_keys.keys())
+            .collect::<BTreeSet<_>>();
+        for key_identity in key_identities {
+            let driver_value = key_identity.0.clone();
+            match (old_keys.get(key_identity), new_keys.get(key_identity)) {
+                (None, Some(key)) => changes.push(Change::CatalogKeyAdded {
+                    group: group_name.clone(),
+                    driver_value,
+                    name: string_field(key, "Name"),
+                }),
+                (Some(key), None) => changes.push(Change::CatalogKeyRemoved {
+                    group: group_name.clone(),
+                    driver_value,
+                    name: string_field(key, "Name"),
+                }),
+                (Some(old_key), Some(new_key)) => {
+                    for (field, old_value, new_value) in diff_fields(old_key, new_key) {
+                        changes.push(Change::CatalogKeyChanged {
+                            group: group_name.clone(),
+                            driver_value: driver_value.clone(),
+                            name: string_field(new_key, "Name"),
+                            field,
+                            old: old_value,
+                            new: new_value,
+                        });
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
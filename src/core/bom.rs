@@ -0,0 +1,25 @@
+/// The UTF-8 byte order mark some of the vendor files start with
+pub const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// Removes the UTF-8 BOM from the start of a file, `None` if it doesn't have one
+pub fn strip_bom(bytes: &[u8]) -> Option<&[u8]> {
+    bytes.strip_prefix(UTF8_BOM)
+}
+
+/// [`strip_bom`] for text which has already been decoded
+pub fn strip_bom_str(text: &str) -> Option<&str> {
+    strip_bom(text.as_bytes()).map(|rest| &text[text.len() - rest.len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_leading_bom_is_removed() {
+        assert_eq!(strip_bom(b"\xEF\xBB\xBF{}"), Some(&b"{}"[..]));
+        assert_eq!(strip_bom(b"{}\xEF\xBB\xBF"), None);
+        assert_eq!(strip_bom_str("\u{feff}[1]"), Some("[1]"));
+        assert_eq!(strip_bom_str("[1]"), None);
+    }
+}
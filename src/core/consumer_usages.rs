@@ -0,0 +1,435 @@
+/// The usages of the HID consumer page (0x0C), which is what [`DriverValueType::System`] values contain
+/// (`0x030000CD` = play / pause).
+///
+/// Collections and named arrays (`ConsumerControl`, `MediaSelection`, `ApplicationLaunchButtons`, ...) only group
+/// other usages and can't be sent by a key, so they aren't listed. "AL" (application launch) and "AC" (application
+/// control) usages keep their prefix. See "HID Usage Tables", section 15.
+///
+/// [`DriverValueType::System`]: super::key_values::DriverValueType::System
+pub const CONSUMER_USAGES: &[(u16, &str)] = &[
+    (0x0020, "Plus10"),
+    (0x0021, "Plus100"),
+    (0x0022, "AmPm"),
+    (0x0030, "Power"),
+    (0x0031, "Reset"),
+    (0x0032, "Sleep"),
+    (0x0033, "SleepAfter"),
+    (0x0034, "SleepMode"),
+    (0x0035, "Illumination"),
+    (0x0040, "Menu"),
+    (0x0041, "MenuPick"),
+    (0x0042, "MenuUp"),
+    (0x0043, "MenuDown"),
+    (0x0044, "MenuLeft"),
+    (0x0045, "MenuRight"),
+    (0x0046, "MenuEscape"),
+    (0x0047, "MenuValueIncrease"),
+    (0x0048, "MenuValueDecrease"),
+    (0x0060, "DataOnScreen"),
+    (0x0061, "ClosedCaption"),
+    (0x0062, "ClosedCaptionSelect"),
+    (0x0063, "VcrTv"),
+    (0x0064, "BroadcastMode"),
+    (0x0065, "Snapshot"),
+    (0x0066, "Still"),
+    (0x0067, "PictureInPictureToggle"),
+    (0x0068, "PictureInPictureSwap"),
+    (0x0069, "RedMenuButton"),
+    (0x006A, "GreenMenuButton"),
+    (0x006B, "BlueMenuButton"),
+    (0x006C, "YellowMenuButton"),
+    (0x006D, "Aspect"),
+    (0x006E, "ThreeDModeSelect"),
+    (0x006F, "DisplayBrightnessIncrement"),
+    (0x0070, "DisplayBrightnessDecrement"),
+    (0x0071, "DisplayBrightness"),
+    (0x0072, "DisplayBacklightToggle"),
+    (0x0073, "DisplaySetBrightnessToMinimum"),
+    (0x0074, "DisplaySetBrightnessToMaximum"),
+    (0x0075, "DisplaySetAutoBrightness"),
+    (0x0076, "CameraAccessEnabled"),
+    (0x0077, "CameraAccessDisabled"),
+    (0x0078, "CameraAccessToggle"),
+    (0x0079, "KeyboardBrightnessIncrement"),
+    (0x007A, "KeyboardBrightnessDecrement"),
+    (0x007B, "KeyboardBacklightSetLevel"),
+    (0x007C, "KeyboardBacklightOnOff"),
+    (0x007D, "KeyboardBacklightSetMinimum"),
+    (0x007E, "KeyboardBacklightSetMaximum"),
+    (0x007F, "KeyboardBacklightAuto"),
+    (0x0081, "AssignSelection"),
+    (0x0082, "ModeStep"),
+    (0x0083, "RecallLast"),
+    (0x0084, "EnterChannel"),
+    (0x0085, "OrderMovie"),
+    (0x0086, "Channel"),
+    (0x0088, "MediaSelectComputer"),
+    (0x0089, "MediaSelectTv"),
+    (0x008A, "MediaSelectWww"),
+    (0x008B, "MediaSelectDvd"),
+    (0x008C, "MediaSelectTelephone"),
+    (0x008D, "MediaSelectProgramGuide"),
+    (0x008E, "MediaSelectVideoPhone"),
+    (0x008F, "MediaSelectGames"),
+    (0x0090, "MediaSelectMessages"),
+    (0x0091, "MediaSelectCd"),
+    (0x0092, "MediaSelectVcr"),
+    (0x0093, "MediaSelectTuner"),
+    (0x0094, "Quit"),
+    (0x0095, "Help"),
+    (0x0096, "MediaSelectTape"),
+    (0x0097, "MediaSelectCable"),
+    (0x0098, "MediaSelectSatellite"),
+    (0x0099, "MediaSelectSecurity"),
+    (0x009A, "MediaSelectHome"),
+    (0x009B, "MediaSelectCall"),
+    (0x009C, "ChannelIncrement"),
+    (0x009D, "ChannelDecrement"),
+    (0x009E, "MediaSelectSap"),
+    (0x00A0, "VcrPlus"),
+    (0x00A1, "Once"),
+    (0x00A2, "Daily"),
+    (0x00A3, "Weekly"),
+    (0x00A4, "Monthly"),
+    (0x00B0, "Play"),
+    (0x00B1, "Pause"),
+    (0x00B2, "Record"),
+    (0x00B3, "FastForward"),
+    (0x00B4, "Rewind"),
+    (0x00B5, "ScanNextTrack"),
+    (0x00B6, "ScanPreviousTrack"),
+    (0x00B7, "Stop"),
+    (0x00B8, "Eject"),
+    (0x00B9, "RandomPlay"),
+    (0x00BB, "EnterDisc"),
+    (0x00BC, "Repeat"),
+    (0x00BD, "Tracking"),
+    (0x00BE, "TrackNormal"),
+    (0x00BF, "SlowTracking"),
+    (0x00C0, "FrameForward"),
+    (0x00C1, "FrameBack"),
+    (0x00C2, "Mark"),
+    (0x00C3, "ClearMark"),
+    (0x00C4, "RepeatFromMark"),
+    (0x00C5, "ReturnToMark"),
+    (0x00C6, "SearchMarkForward"),
+    (0x00C7, "SearchMarkBackwards"),
+    (0x00C8, "CounterReset"),
+    (0x00C9, "ShowCounter"),
+    (0x00CA, "TrackingIncrement"),
+    (0x00CB, "TrackingDecrement"),
+    (0x00CC, "StopEject"),
+    (0x00CD, "PlayPause"),
+    (0x00CE, "PlaySkip"),
+    (0x00CF, "VoiceCommand"),
+    (0x00D0, "InvokeCaptureInterface"),
+    (0x00D1, "StartOrStopGameRecording"),
+    (0x00D2, "HistoricalGameCapture"),
+    (0x00D3, "CaptureGameScreenshot"),
+    (0x00D4, "ShowOrHideRecordingIndicator"),
+    (0x00D5, "StartOrStopMicrophoneCapture"),
+    (0x00D6, "StartOrStopCameraCapture"),
+    (0x00D7, "StartOrStopGameBroadcast"),
+    (0x00D8, "StartOrStopVoiceDictationSession"),
+    (0x00D9, "InvokeDismissEmojiPicker"),
+    (0x00E0, "Volume"),
+    (0x00E1, "Balance"),
+    (0x00E2, "Mute"),
+    (0x00E3, "Bass"),
+    (0x00E4, "Treble"),
+    (0x00E5, "BassBoost"),
+    (0x00E6, "SurroundMode"),
+    (0x00E7, "Loudness"),
+    (0x00E8, "Mpx"),
+    (0x00E9, "VolumeIncrement"),
+    (0x00EA, "VolumeDecrement"),
+    (0x00F0, "SpeedSelect"),
+    (0x00F2, "StandardPlay"),
+    (0x00F3, "LongPlay"),
+    (0x00F4, "ExtendedPlay"),
+    (0x00F5, "Slow"),
+    (0x0100, "FanEnable"),
+    (0x0101, "FanSpeed"),
+    (0x0102, "LightEnable"),
+    (0x0103, "LightIlluminationLevel"),
+    (0x0104, "ClimateControlEnable"),
+    (0x0105, "RoomTemperature"),
+    (0x0106, "SecurityEnable"),
+    (0x0107, "FireAlarm"),
+    (0x0108, "PoliceAlarm"),
+    (0x0109, "Proximity"),
+    (0x010A, "Motion"),
+    (0x010B, "DuressAlarm"),
+    (0x010C, "HoldupAlarm"),
+    (0x010D, "MedicalAlarm"),
+    (0x0150, "BalanceRight"),
+    (0x0151, "BalanceLeft"),
+    (0x0152, "BassIncrement"),
+    (0x0153, "BassDecrement"),
+    (0x0154, "TrebleIncrement"),
+    (0x0155, "TrebleDecrement"),
+    (0x0170, "SubChannel"),
+    (0x0171, "SubChannelIncrement"),
+    (0x0172, "SubChannelDecrement"),
+    (0x0173, "AlternateAudioIncrement"),
+    (0x0174, "AlternateAudioDecrement"),
+    (0x0181, "AlLaunchButtonConfigurationTool"),
+    (0x0182, "AlProgrammableButtonConfiguration"),
+    (0x0183, "AlConsumerControlConfiguration"),
+    (0x0184, "AlWordProcessor"),
+    (0x0185, "AlTextEditor"),
+    (0x0186, "AlSpreadsheet"),
+    (0x0187, "AlGraphicsEditor"),
+    (0x0188, "AlPresentationApp"),
+    (0x0189, "AlDatabaseApp"),
+    (0x018A, "AlEmailReader"),
+    (0x018B, "AlNewsreader"),
+    (0x018C, "AlVoicemail"),
+    (0x018D, "AlContactsAddressBook"),
+    (0x018E, "AlCalendarSchedule"),
+    (0x018F, "AlTaskProjectManager"),
+    (0x0190, "AlLogJournalTimecard"),
+    (0x0191, "AlCheckbookFinance"),
+    (0x0192, "AlCalculator"),
+    (0x0193, "AlAvCapturePlayback"),
+    (0x0194, "AlLocalMachineBrowser"),
+    (0x0195, "AlLanWanBrowser"),
+    (0x0196, "AlInternetBrowser"),
+    (0x0197, "AlRemoteNetworkingIspConnect"),
+    (0x0198, "AlNetworkConference"),
+    (0x0199, "AlNetworkChat"),
+    (0x019A, "AlTelephonyDialer"),
+    (0x019B, "AlLogon"),
+    (0x019C, "AlLogoff"),
+    (0x019D, "AlLogonLogoff"),
+    (0x019E, "AlTerminalLockScreensaver"),
+    (0x019F, "AlControlPanel"),
+    (0x01A0, "AlCommandLineProcessorRun"),
+    (0x01A1, "AlProcessTaskManager"),
+    (0x01A2, "AlSelectTaskApplication"),
+    (0x01A3, "AlNextTaskApplication"),
+    (0x01A4, "AlPreviousTaskApplication"),
+    (0x01A5, "AlPreemptiveHaltTaskApplication"),
+    (0x01A6, "AlIntegratedHelpCenter"),
+    (0x01A7, "AlDocuments"),
+    (0x01A8, "AlThesaurus"),
+    (0x01A9, "AlDictionary"),
+    (0x01AA, "AlDesktop"),
+    (0x01AB, "AlSpellCheck"),
+    (0x01AC, "AlGrammarCheck"),
+    (0x01AD, "AlWirelessStatus"),
+    (0x01AE, "AlKeyboardLayout"),
+    (0x01AF, "AlVirusProtection"),
+    (0x01B0, "AlEncryption"),
+    (0x01B1, "AlScreenSaver"),
+    (0x01B2, "AlAlarms"),
+    (0x01B3, "AlClock"),
+    (0x01B4, "AlFileBrowser"),
+    (0x01B5, "AlPowerStatus"),
+    (0x01B6, "AlImageBrowser"),
+    (0x01B7, "AlAudioBrowser"),
+    (0x01B8, "AlMovieBrowser"),
+    (0x01B9, "AlDigitalRightsManager"),
+    (0x01BA, "AlDigitalWallet"),
+    (0x01BC, "AlInstantMessaging"),
+    (0x01BD, "AlOemFeaturesTipsTutorialBrowser"),
+    (0x01BE, "AlOemHelp"),
+    (0x01BF, "AlOnlineCommunity"),
+    (0x01C0, "AlEntertainmentContentBrowser"),
+    (0x01C1, "AlOnlineShoppingBrowser"),
+    (0x01C2, "AlSmartCardInformationHelp"),
+    (0x01C3, "AlMarketMonitorFinanceBrowser"),
+    (0x01C4, "AlCustomizedCorporateNewsBrowser"),
+    (0x01C5, "AlOnlineActivityBrowser"),
+    (0x01C6, "AlResearchSearchBrowser"),
+    (0x01C7, "AlAudioPlayer"),
+    (0x01C8, "AlMessageStatus"),
+    (0x01C9, "AlContactSync"),
+    (0x01CA, "AlNavigation"),
+    (0x01CB, "AlContextAwareDesktopAssistant"),
+    (0x0201, "AcNew"),
+    (0x0202, "AcOpen"),
+    (0x0203, "AcClose"),
+    (0x0204, "AcExit"),
+    (0x0205, "AcMaximize"),
+    (0x0206, "AcMinimize"),
+    (0x0207, "AcSave"),
+    (0x0208, "AcPrint"),
+    (0x0209, "AcProperties"),
+    (0x021A, "AcUndo"),
+    (0x021B, "AcCopy"),
+    (0x021C, "AcCut"),
+    (0x021D, "AcPaste"),
+    (0x021E, "AcSelectAll"),
+    (0x021F, "AcFind"),
+    (0x0220, "AcFindAndReplace"),
+    (0x0221, "AcSearch"),
+    (0x0222, "AcGoTo"),
+    (0x0223, "AcHome"),
+    (0x0224, "AcBack"),
+    (0x0225, "AcForward"),
+    (0x0226, "AcStop"),
+    (0x0227, "AcRefresh"),
+    (0x0228, "AcPreviousLink"),
+    (0x0229, "AcNextLink"),
+    (0x022A, "AcBookmarks"),
+    (0x022B, "AcHistory"),
+    (0x022C, "AcSubscriptions"),
+    (0x022D, "AcZoomIn"),
+    (0x022E, "AcZoomOut"),
+    (0x022F, "AcZoom"),
+    (0x0230, "AcFullScreenView"),
+    (0x0231, "AcNormalView"),
+    (0x0232, "AcViewToggle"),
+    (0x0233, "AcScrollUp"),
+    (0x0234, "AcScrollDown"),
+    (0x0235, "AcScroll"),
+    (0x0236, "AcPanLeft"),
+    (0x0237, "AcPanRight"),
+    (0x0238, "AcPan"),
+    (0x0239, "AcNewWindow"),
+    (0x023A, "AcTileHorizontally"),
+    (0x023B, "AcTileVertically"),
+    (0x023C, "AcFormat"),
+    (0x023D, "AcEdit"),
+    (0x023E, "AcBold"),
+    (0x023F, "AcItalics"),
+    (0x0240, "AcUnderline"),
+    (0x0241, "AcStrikethrough"),
+    (0x0242, "AcSubscript"),
+    (0x0243, "AcSuperscript"),
+    (0x0244, "AcAllCaps"),
+    (0x0245, "AcRotate"),
+    (0x0246, "AcResize"),
+    (0x0247, "AcFlipHorizontal"),
+    (0x0248, "AcFlipVertical"),
+    (0x0249, "AcMirrorHorizontal"),
+    (0x024A, "AcMirrorVertical"),
+    (0x024B, "AcFontSelect"),
+    (0x024C, "AcFontColor"),
+    (0x024D, "AcFontSize"),
+    (0x024E, "AcJustifyLeft"),
+    (0x024F, "AcJustifyCenterH"),
+    (0x0250, "AcJustifyRight"),
+    (0x0251, "AcJustifyBlockH"),
+    (0x0252, "AcJustifyTop"),
+    (0x0253, "AcJustifyCenterV"),
+    (0x0254, "AcJustifyBottom"),
+    (0x0255, "AcJustifyBlockV"),
+    (0x0256, "AcIndentDecrease"),
+    (0x0257, "AcIndentIncrease"),
+    (0x0258, "AcNumberedList"),
+    (0x0259, "AcRestartNumbering"),
+    (0x025A, "AcBulletedList"),
+    (0x025B, "AcPromote"),
+    (0x025C, "AcDemote"),
+    (0x025D, "AcYes"),
+    (0x025E, "AcNo"),
+    (0x025F, "AcCancel"),
+    (0x0260, "AcCatalog"),
+    (0x0261, "AcBuyCheckout"),
+    (0x0262, "AcAddToCart"),
+    (0x0263, "AcExpand"),
+    (0x0264, "AcExpandAll"),
+    (0x0265, "AcCollapse"),
+    (0x0266, "AcCollapseAll"),
+    (0x0267, "AcPrintPreview"),
+    (0x0268, "AcPasteSpecial"),
+    (0x0269, "AcInsertMode"),
+    (0x026A, "AcDelete"),
+    (0x026B, "AcLock"),
+    (0x026C, "AcUnlock"),
+    (0x026D, "AcProtect"),
+    (0x026E, "AcUnprotect"),
+    (0x026F, "AcAttachComment"),
+    (0x0270, "AcDeleteComment"),
+    (0x0271, "AcViewComment"),
+    (0x0272, "AcSelectWord"),
+    (0x0273, "AcSelectSentence"),
+    (0x0274, "AcSelectParagraph"),
+    (0x0275, "AcSelectColumn"),
+    (0x0276, "AcSelectRow"),
+    (0x0277, "AcSelectTable"),
+    (0x0278, "AcSelectObject"),
+    (0x0279, "AcRedoRepeat"),
+    (0x027A, "AcSort"),
+    (0x027B, "AcSortAscending"),
+    (0x027C, "AcSortDescending"),
+    (0x027D, "AcFilter"),
+    (0x027E, "AcSetClock"),
+    (0x027F, "AcViewClock"),
+    (0x0280, "AcSelectTimeZone"),
+    (0x0281, "AcEditTimeZones"),
+    (0x0282, "AcSetAlarm"),
+    (0x0283, "AcClearAlarm"),
+    (0x0284, "AcSnoozeAlarm"),
+    (0x0285, "AcResetAlarm"),
+    (0x0286, "AcSynchronize"),
+    (0x0287, "AcSendReceive"),
+    (0x0288, "AcSendTo"),
+    (0x0289, "AcReply"),
+    (0x028A, "AcReplyAll"),
+    (0x028B, "AcForwardMsg"),
+    (0x028C, "AcSend"),
+    (0x028D, "AcAttachFile"),
+    (0x028E, "AcUpload"),
+    (0x028F, "AcDownloadSaveTargetAs"),
+    (0x0290, "AcSetBorders"),
+    (0x0291, "AcInsertRow"),
+    (0x0292, "AcInsertColumn"),
+    (0x0293, "AcInsertFile"),
+    (0x0294, "AcInsertPicture"),
+    (0x0295, "AcInsertObject"),
+    (0x0296, "AcInsertSymbol"),
+    (0x0297, "AcSaveAndClose"),
+    (0x0298, "AcRename"),
+    (0x0299, "AcMerge"),
+    (0x029A, "AcSplit"),
+    (0x029B, "AcDistributeHorizontally"),
+    (0x029C, "AcDistributeVertically"),
+    (0x029D, "AcNextKeyboardLayoutSelect"),
+    (0x029E, "AcNavigationGuidance"),
+    (0x029F, "AcDesktopShowAllWindows"),
+    (0x02A0, "AcSoftKeyLeft"),
+    (0x02A1, "AcSoftKeyRight"),
+    (0x02A2, "AcDesktopShowAllApplications"),
+    (0x02B0, "AcIdleKeepAlive"),
+    (0x02C7, "KeyboardInputAssistPrevious"),
+    (0x02C8, "KeyboardInputAssistNext"),
+    (0x02C9, "KeyboardInputAssistPreviousGroup"),
+    (0x02CA, "KeyboardInputAssistNextGroup"),
+    (0x02CB, "KeyboardInputAssistAccept"),
+    (0x02CC, "KeyboardInputAssistCancel"),
+    (0x02D0, "PrivacyScreenToggle"),
+    (0x02D1, "PrivacyScreenLevelDecrement"),
+    (0x02D2, "PrivacyScreenLevelIncrement"),
+    (0x02D3, "PrivacyScreenLevelMinimum"),
+    (0x02D4, "PrivacyScreenLevelMaximum"),
+];
+
+pub fn usage_name(usage: u16) -> Option<&'static str> {
+    CONSUMER_USAGES
+        .binary_search_by_key(&usage, |(value, _)| *value)
+        .ok()
+        .map(|index| CONSUMER_USAGES[index].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn usages_are_sorted_for_the_binary_search() {
+        assert!(CONSUMER_USAGES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn usage_names() {
+        assert_eq!(usage_name(0x00CD), Some("PlayPause"));
+        assert_eq!(usage_name(0x00E9), Some("VolumeIncrement"));
+        assert_eq!(usage_name(0xFFFF), None);
+        // Collections
+        assert_eq!(usage_name(0x0001), None);
+        assert_eq!(usage_name(0x0087), None);
+    }
+}
@@ -1,5 +1,4 @@
-pub static mut TABLE: Vec<u16> = Vec::new();
-
+/// The CRC-16/CCITT table, stored as little endian u16 values
 pub static BYTE_TABLE: [u8; 512] = [
     0x00, 0x00, 0x21, 0x10, 0x42, 0x20, 0x63, 0x30, 0x84, 0x40, 0xA5, 0x50, 0xC6, 0x60, 0xE7, 0x70,
     0x08, 0x81, 0x29, 0x91, 0x4A, 0xA1, 0x6B, 0xB1, 0x8C, 0xC1, 0xAD, 0xD1, 0xCE, 0xE1, 0xEF, 0xF1,
@@ -35,38 +34,74 @@ pub static BYTE_TABLE: [u8; 512] = [
     0x17, 0x6E, 0x36, 0x7E, 0x55, 0x4E, 0x74, 0x5E, 0x93, 0x2E, 0xB2, 0x3E, 0xD1, 0x0E, 0xF0, 0x1E,
 ];
 
-unsafe fn crc16() {
-    TABLE = vec![0; BYTE_TABLE.len() / 2];
-    for i in 0..TABLE.len() {
-        TABLE[i] = (BYTE_TABLE[i * 2] as u16) << 8 | BYTE_TABLE[i * 2 + 1] as u16;
-    }
+fn table(index: u8) -> u16 {
+    let index = index as usize * 2;
+    u16::from_le_bytes([BYTE_TABLE[index], BYTE_TABLE[index + 1]])
 }
 
 pub fn get_crc(data: &[u8], index: usize, iv: u16) -> u16 {
     let mut crc = iv;
-    for i in index..data.len() {
-        let table_offset = data[i] ^ (crc >> 8) as u8;
-        crc = unsafe { TABLE[table_offset as usize] ^ (crc << 8) };
+    for &byte in &data[index..] {
+        let table_offset = byte ^ (crc >> 8) as u8;
+        crc = table(table_offset) ^ (crc << 8);
     }
     crc
 }
 
 pub fn insert_crc(data: &mut [u8], data_offset: usize, crc_offset: usize) {
-    data[crc_offset + 0] = 0;
+    data[crc_offset] = 0;
     data[crc_offset + 1] = 0;
     let data_crc = get_crc(data, data_offset, 0xFFFF);
-    data[crc_offset + 0] = data_crc as u8;
+    data[crc_offset] = data_crc as u8;
     data[crc_offset + 1] = (data_crc >> 8) as u8;
 }
 
 pub fn validate_crc(data: &mut [u8], data_offset: usize, crc_offset: usize) -> bool {
-    let data_crc = u16::from_le_bytes([data[crc_offset + 0], data[crc_offset + 1]]);
-    let temp1 = data[crc_offset + 0];
+    let data_crc = u16::from_le_bytes([data[crc_offset], data[crc_offset + 1]]);
+    let temp1 = data[crc_offset];
     let temp2 = data[crc_offset + 1];
-    data[crc_offset + 0] = 0;
+    data[crc_offset] = 0;
     data[crc_offset + 1] = 0;
     let calculated_crc = get_crc(data, data_offset, 0xFFFF);
-    data[crc_offset + 0] = temp1;
+    data[crc_offset] = temp1;
     data[crc_offset + 1] = temp2;
     data_crc == calculated_crc
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_is_crc16_ccitt() {
+        for index in 0..=u8::MAX {
+            let mut crc = (index as u16) << 8;
+            for _ in 0..8 {
+                crc = if crc & 0x8000 != 0 {
+                    crc << 1 ^ 0x1021
+                } else {
+                    crc << 1
+                };
+            }
+            assert_eq!(table(index), crc, "index {index}");
+        }
+    }
+
+    #[test]
+    fn check_value() {
+        assert_eq!(get_crc(b"123456789", 0, 0xFFFF), 0x29B1);
+        assert_eq!(get_crc(b"xx123456789", 2, 0xFFFF), 0x29B1);
+    }
+
+    #[test]
+    fn inserted_crc_is_valid() {
+        let mut packet = [0u8; 64];
+        packet[0] = 0x21;
+        packet[8..12].copy_from_slice(&[1, 2, 3, 4]);
+        insert_crc(&mut packet, 0, 6);
+        assert!(validate_crc(&mut packet, 0, 6));
+
+        packet[9] ^= 0x01;
+        assert!(!validate_crc(&mut packet, 0, 6));
+    }
+}
@@ -0,0 +1,308 @@
+use std::{borrow::Cow, collections::HashMap, fs, path::Path};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+use super::js_value::JsValue;
+
+const LANGS_PATH: &str = "assets/i18n/langs";
+/// Used for anything the selected locale doesn't have
+pub const FALLBACK_LOCALE: &str = "en";
+
+lazy_static! {
+    /// The language tables of `assets/i18n/langs`, by locale ("en", "zh")
+    static ref TABLES: HashMap<String, LangTable> = load_tables(LANGS_PATH);
+    static ref LOCALE: Mutex<String> = Mutex::new(system_locale());
+}
+
+/// The texts of one language. The vendor files group them by page ("keys", "menu", ...) but the keys are unique
+/// across pages, so they're looked up either way ("key_esc" or "keys.key_esc").
+#[derive(Debug, Default)]
+pub struct LangTable {
+    texts: HashMap<String, String>,
+}
+
+impl LangTable {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.texts.get(key).map(String::as_str)
+    }
+
+    /// Finds the key of a text ("第一行" => "line_first")
+    fn find_key(&self, text: &str) -> Option<&str> {
+        self.texts
+            .iter()
+            .filter(|(key, value)| !key.contains('.') && *value == text)
+            .map(|(key, _)| key.as_str())
+            .min()
+    }
+
+    fn insert(&mut self, page: &str, key: String, value: &str) {
+        if !page.is_empty() {
+            self.texts
+                .insert(format!("{page}.{key}"), value.to_string());
+        }
+        self.texts.entry(key).or_insert_with(|| value.to_string());
+    }
+}
+
+/// The locales which have a language table, sorted
+#[allow(dead_code)] // For the GUI's language selection
+pub fn locales() -> Vec<String> {
+    let mut locales = TABLES.keys().cloned().collect::<Vec<_>>();
+    locales.sort();
+    locales
+}
+
+pub fn locale() -> String {
+    LOCALE.lock().clone()
+}
+
+/// Selects the locale texts are looked up in. Region and encoding are ignored ("zh_CN.UTF-8" => "zh"), returns
+/// false (and keeps the current locale) if there's no table for it.
+#[allow(dead_code)] // For the GUI's language selection
+pub fn set_locale(locale: &str) -> bool {
+    let locale = normalize_locale(locale);
+    if !TABLES.contains_key(&locale) {
+        return false;
+    }
+    *LOCALE.lock() = locale;
+    true
+}
+
+/// Looks up a language key in the selected locale, falling back to English
+pub fn tr(key: &str) -> Option<String> {
+    let locale = locale();
+    [locale.as_str(), FALLBACK_LOCALE]
+        .iter()
+        .filter_map(|locale| TABLES.get(*locale))
+        .find_map(|table| table.get(key))
+        .map(str::to_string)
+}
+
+/// Localizes a name from the vendor data which may be a language key ("layer_onboard_1"), a text of another language
+/// ("第一行") or a name which only exists in Chinese (lighting effects such as "光谱循环 61"). Anything else is
+/// returned as is.
+pub fn translate_name(name: &str) -> String {
+    if let Some(text) = tr(name) {
+        return text;
+    }
+
+    let locale = locale();
+    let key = TABLES
+        .iter()
+        .filter(|(table_locale, _)| **table_locale != locale)
+        .find_map(|(_, table)| table.find_key(name));
+    if let Some(text) = key.and_then(tr) {
+        return text;
+    }
+
+    if locale != "zh" && contains_han(name) {
+        return translate_glossary(name);
+    }
+    name.to_string()
+}
+
+/// Chinese words used in vendor names which aren't in the language tables, mostly lighting effects. Longer entries
+/// are matched first.
+const GLOSSARY: &[(&str, &str)] = &[
+    ("默认配置", "Default Profile"),
+    ("标准配置", "Standard Profile"),
+    ("离线配置", "Offline Profile"),
+    ("板载模式", "Onboard Mode"),
+    ("未检测到设备", "No Device Detected"),
+    ("不支持的设备", "Unsupported Device"),
+    ("光谱循环", "Spectrum Cycle"),
+    ("彩虹波", "Rainbow Wave"),
+    ("彩虹", "Rainbow"),
+    ("风车", "Windmill"),
+    ("星光闪耀", "Starlight"),
+    ("漂流碰撞", "Drift Collision"),
+    ("踏浪", "Wave Riding"),
+    ("大逃杀", "Battle Royale"),
+    ("彩云飞", "Flying Clouds"),
+    ("两极", "Poles"),
+    ("幻彩流光", "Colorful Streamer"),
+    ("同步", "Synced"),
+    ("渐变", "Gradient"),
+    ("变换", "Transition"),
+    ("变化", "Changing"),
+    ("呼吸", "Breathing"),
+    ("常亮", "Always On"),
+    ("全亮", "All On"),
+    ("全局", "All"),
+    ("灯效", "Lighting"),
+    ("上下灯位", "Top And Bottom LEDs"),
+    ("上下灯", "Top And Bottom LEDs"),
+    ("上灯条", "Top Light Bar"),
+    ("下灯条", "Bottom Light Bar"),
+    ("上灯位", "Top LEDs"),
+    ("下灯位", "Bottom LEDs"),
+    ("底灯", "Bottom Light"),
+    ("方向键", "Arrow Keys"),
+    ("功能九键", "Function 9 Keys"),
+    ("功能9键", "Function 9 Keys"),
+    ("计算器", "Calculator"),
+    ("蓝牙版", "Bluetooth"),
+    ("蓝牙", "Bluetooth"),
+    ("鼠标", "Mouse"),
+    ("键", "Key"),
+    ("纯绿", "Pure Green"),
+    ("绿光", "Green"),
+    ("红光", "Red"),
+    ("蓝光", "Blue"),
+    ("白光", "White"),
+    ("紫光", "Purple"),
+    ("绿色", "Green"),
+    ("白色", "White"),
+    ("绿", "Green"),
+    ("红", "Red"),
+    ("蓝", "Blue"),
+    ("白", "White"),
+    ("紫", "Purple"),
+    ("上", "Top"),
+    ("下", "Bottom"),
+];
+
+/// Replaces the glossary words, separating them from their neighbours with spaces ("68键方向键" => "68 Key Arrow
+/// Keys"). Unknown characters are kept.
+fn translate_glossary(name: &str) -> String {
+    let mut words: Vec<(String, bool)> = Vec::new();
+    let mut rest = name;
+    while let Some(c) = rest.chars().next() {
+        let found = GLOSSARY
+            .iter()
+            .filter(|(word, _)| rest.starts_with(word))
+            .max_by_key(|(word, _)| word.len());
+        match found {
+            Some((word, translation)) => {
+                words.push((translation.to_string(), true));
+                rest = &rest[word.len()..];
+            }
+            None => {
+                match words.last_mut() {
+                    Some((text, false)) => text.push(c),
+                    _ => words.push((c.to_string(), false)),
+                }
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    let mut result = String::new();
+    let mut previous_translated = false;
+    for (text, translated) in words {
+        let needs_space = (translated || previous_translated)
+            && result.ends_with(|c: char| c.is_alphanumeric())
+            && text.starts_with(|c: char| c.is_alphanumeric());
+        if needs_space {
+            result.push(' ');
+        }
+        result.push_str(&text);
+        previous_translated = translated;
+    }
+    result
+}
+
+fn contains_han(text: &str) -> bool {
+    text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c))
+}
+
+/// "zh_CN.UTF-8" => "zh"
+fn normalize_locale(locale: &str) -> String {
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// The locale of the environment (`LC_ALL` / `LANG`), English if it isn't set or has no table
+fn system_locale() -> String {
+    ["LC_ALL", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .map(|value| normalize_locale(&value))
+        .find(|locale| !locale.is_empty() && TABLES.contains_key(locale))
+        .unwrap_or_else(|| FALLBACK_LOCALE.to_string())
+}
+
+fn load_tables(path: impl AsRef<Path>) -> HashMap<String, LangTable> {
+    let path = path.as_ref();
+    let mut tables = HashMap::new();
+    for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+        let file = entry.path();
+        if file.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+        let Some(locale) = file.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+        match fs::read(&file) {
+            Ok(bytes) => {
+                let table = parse_table(&String::from_utf8_lossy(&bytes));
+                if table.texts.is_empty() {
+                    log::warn!("No texts found in `{}`", file.display());
+                }
+                tables.insert(locale.to_string(), table);
+            }
+            Err(err) => log::warn!("Failed to load `{}`: {err}", file.display()),
+        }
+    }
+    tables
+}
+
+/// Parses a language table. The vendor files aren't always valid JSON (keys such as `""key_lctrl + ↑""`), these are
+/// repaired first, and if the file still doesn't parse every `"key": "text"` line which does is kept.
+fn parse_table(text: &str) -> LangTable {
+    let repaired = text.lines().map(repair_line).collect::<Vec<_>>().join("\n");
+
+    let mut table = LangTable::default();
+    match JsValue::parse(&repaired) {
+        Ok(JsValue::Object(pages)) => {
+            for (page, texts) in pages {
+                let JsValue::Object(texts) = texts else {
+                    continue;
+                };
+                for (key, value) in texts {
+                    if let Some(value) = value.as_str() {
+                        table.insert(&page, key, value);
+                    }
+                }
+            }
+        }
+        Ok(_) => log::warn!("Language table isn't an object"),
+        Err(err) => {
+            log::warn!("Failed to parse language table ({err}), reading it line by line");
+            let mut page = String::new();
+            for line in repaired.lines() {
+                let Ok((JsValue::String(key), end)) = JsValue::parse_at(line, 0) else {
+                    continue;
+                };
+                let Some(value) = line[end..].trim_start().strip_prefix(':') else {
+                    continue;
+                };
+                match JsValue::parse_at(value, 0) {
+                    Ok((JsValue::String(value), _)) => table.insert(&page, key, &value),
+                    _ if value.trim_start().starts_with('{') => page = key,
+                    _ => {}
+                }
+            }
+        }
+    }
+    table
+}
+
+/// `""key_lwin + space"": "Win+Space"` => `"key_lwin + space": "Win+Space"`
+fn repair_line(line: &str) -> Cow<'_, str> {
+    let trimmed = line.trim_start();
+    let indent = &line[..line.len() - trimmed.len()];
+    match trimmed
+        .strip_prefix("\"\"")
+        .and_then(|rest| rest.split_once("\"\":"))
+    {
+        Some((key, value)) if !key.is_empty() && !key.contains('"') => {
+            Cow::Owned(format!("{indent}\"{key}\":{value}"))
+        }
+        _ => Cow::Borrowed(line),
+    }
+}
@@ -0,0 +1,394 @@
+use std::fmt::{self, Write};
+
+use super::bom;
+
+/// A JSON-like value parsed from a JavaScript object / array literal.
+///
+/// The vendor software ships most of its data inside JS files (`0.js`, `index.js`, some `keymap.json` files are
+/// really JS declarations) so we accept unquoted keys, single quoted strings, comments, trailing commas and the
+/// minifier's `!0` / `!1`. Object keys keep their order so that extracted files can be written out unchanged.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsValue {
+    Null,
+    Bool(bool),
+    /// Kept as written so numbers round trip exactly
+    Number(String),
+    String(String),
+    Array(Vec<JsValue>),
+    Object(Vec<(String, JsValue)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl JsValue {
+    /// Parses a whole document, which may start with a BOM and may only be followed by whitespace / comments
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = bom::strip_bom_str(text).unwrap_or(text);
+        let mut parser = Parser::new(text, 0);
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.pos < text.len() {
+            return Err(parser.error("unexpected trailing data"));
+        }
+        Ok(value)
+    }
+
+    /// Parses the literal starting at byte `start` of `text`, returning the value and the byte offset just past it
+    pub fn parse_at(text: &str, start: usize) -> Result<(Self, usize), ParseError> {
+        let mut parser = Parser::new(text, start);
+        let value = parser.parse_value()?;
+        Ok((value, parser.pos))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsValue> {
+        match self {
+            JsValue::Object(entries) => entries
+                .iter()
+                .find(|(entry_key, _)| entry_key == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsValue]> {
+        match self {
+            JsValue::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Pretty prints the value as JSON using tabs, the same way the files in `assets` are formatted
+    pub fn to_json_pretty(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out, 0);
+        out
+    }
+
+    fn write_json(&self, out: &mut String, indent: usize) {
+        match self {
+            JsValue::Null => out.push_str("null"),
+            JsValue::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+            JsValue::Number(value) => out.push_str(value),
+            JsValue::String(value) => out.push_str(&serde_json::to_string(value).unwrap()),
+            JsValue::Array(values) if values.is_empty() => out.push_str("[]"),
+            JsValue::Object(entries) if entries.is_empty() => out.push_str("{}"),
+            JsValue::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    push_indent(out, indent + 1);
+                    value.write_json(out, indent + 1);
+                }
+                out.push('\n');
+                push_indent(out, indent);
+                out.push(']');
+            }
+            JsValue::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    out.push_str(if i == 0 { "\n" } else { ",\n" });
+                    push_indent(out, indent + 1);
+                    let _ = write!(out, "{}: ", serde_json::to_string(key).unwrap());
+                    value.write_json(out, indent + 1);
+                }
+                out.push('\n');
+                push_indent(out, indent);
+                out.push('}');
+            }
+        }
+    }
+}
+
+impl From<&JsValue> for serde_json::Value {
+    fn from(value: &JsValue) -> Self {
+        match value {
+            JsValue::Null => serde_json::Value::Null,
+            JsValue::Bool(value) => serde_json::Value::Bool(*value),
+            JsValue::Number(value) => {
+                serde_json::from_str(value).unwrap_or(serde_json::Value::Null)
+            }
+            JsValue::String(value) => serde_json::Value::String(value.clone()),
+            JsValue::Array(values) => values.iter().map(serde_json::Value::from).collect(),
+            JsValue::Object(entries) => serde_json::Value::Object(
+                entries
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn push_indent(out: &mut String, indent: usize) {
+    for _ in 0..indent {
+        out.push('\t');
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str, pos: usize) -> Self {
+        Self {
+            text,
+            bytes: text.as_bytes(),
+            pos,
+        }
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        ParseError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        loop {
+            match self.peek() {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'/') => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                Some(b'/') if self.bytes.get(self.pos + 1) == Some(&b'*') => {
+                    match self.text[self.pos + 2..].find("*/") {
+                        Some(end) => self.pos += end + 4,
+                        None => self.pos = self.bytes.len(),
+                    }
+                }
+                _ => {
+                    // Non-breaking spaces / BOMs which sometimes end up in the middle of vendor files
+                    let rest = &self.text[self.pos..];
+                    match rest.chars().next() {
+                        Some(c) if c.is_whitespace() || c == '\u{feff}' => self.pos += c.len_utf8(),
+                        _ => return,
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsValue, ParseError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.parse_object(),
+            Some(b'[') => self.parse_array(),
+            Some(b'"') | Some(b'\'') => Ok(JsValue::String(self.parse_string()?)),
+            Some(b'!') => {
+                // Minified booleans (`!0` / `!1`)
+                self.pos += 1;
+                match self.peek() {
+                    Some(b'0') => {
+                        self.pos += 1;
+                        Ok(JsValue::Bool(true))
+                    }
+                    Some(b'1') => {
+                        self.pos += 1;
+                        Ok(JsValue::Bool(false))
+                    }
+                    _ => Err(self.error("expected `!0` or `!1`")),
+                }
+            }
+            Some(b) if b == b'-' || b == b'+' || b == b'.' || b.is_ascii_digit() => {
+                self.parse_number()
+            }
+            Some(b) if is_identifier_start(b) => {
+                let identifier = self.parse_identifier();
+                match identifier {
+                    "true" => Ok(JsValue::Bool(true)),
+                    "false" => Ok(JsValue::Bool(false)),
+                    "null" | "undefined" => Ok(JsValue::Null),
+                    "void" => {
+                        self.skip_whitespace();
+                        self.parse_number()?;
+                        Ok(JsValue::Null)
+                    }
+                    _ => Err(self.error("expected a literal")),
+                }
+            }
+            Some(_) => Err(self.error("expected a value")),
+            None => Err(self.error("unexpected end of data")),
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsValue, ParseError> {
+        self.pos += 1;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsValue::Object(entries));
+                }
+                Some(b'"') | Some(b'\'') => self.parse_string()?,
+                Some(b) if b.is_ascii_digit() => match self.parse_number()? {
+                    JsValue::Number(number) => number,
+                    _ => unreachable!(),
+                },
+                Some(b) if is_identifier_start(b) => self.parse_identifier().to_string(),
+                _ => return Err(self.error("expected an object key")),
+            };
+
+            self.skip_whitespace();
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected `:`"));
+            }
+            self.pos += 1;
+            let value = self.parse_value()?;
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {}
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsValue, ParseError> {
+        self.pos += 1;
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.peek() == Some(b']') {
+                self.pos += 1;
+                return Ok(JsValue::Array(values));
+            }
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {}
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+    }
+
+    fn parse_identifier(&mut self) -> &'a str {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if is_identifier_start(b) || b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn parse_number(&mut self) -> Result<JsValue, ParseError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if b.is_ascii_alphanumeric() || b"+-.".contains(&b)) {
+            self.pos += 1;
+        }
+        let text = &self.text[start..self.pos];
+
+        // Normalize to something JSON accepts (`.5`, `+1`, `0x1F`)
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let normalized = if let Some(hex) = digits
+            .strip_prefix("0x")
+            .or_else(|| digits.strip_prefix("0X"))
+        {
+            u64::from_str_radix(hex, 16)
+                .map_err(|_| self.error("invalid number"))?
+                .to_string()
+        } else if digits.starts_with('.') {
+            format!("0{}", digits)
+        } else {
+            digits.to_string()
+        };
+        let normalized = if negative {
+            format!("-{}", normalized)
+        } else {
+            normalized
+        };
+
+        if serde_json::from_str::<serde_json::Number>(&normalized).is_err() {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+        Ok(JsValue::Number(normalized))
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        let quote = self.bytes[self.pos];
+        self.pos += 1;
+        let mut result = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += c.len_utf8();
+            match c {
+                _ if c as u32 == quote as u32 => return Ok(result),
+                '\\' => {
+                    let Some(escaped) = self.text[self.pos..].chars().next() else {
+                        return Err(self.error("unterminated string"));
+                    };
+                    self.pos += escaped.len_utf8();
+                    match escaped {
+                        'n' => result.push('\n'),
+                        'r' => result.push('\r'),
+                        't' => result.push('\t'),
+                        'b' => result.push('\u{8}'),
+                        'f' => result.push('\u{c}'),
+                        'v' => result.push('\u{b}'),
+                        '0' => result.push('\0'),
+                        // Line continuation
+                        '\n' => {}
+                        'x' => result.push(self.parse_hex_escape(2)?),
+                        'u' => result.push(self.parse_hex_escape(4)?),
+                        other => result.push(other),
+                    }
+                }
+                _ => result.push(c),
+            }
+        }
+    }
+
+    fn parse_hex_escape(&mut self, len: usize) -> Result<char, ParseError> {
+        let hex = self
+            .text
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| self.error("invalid escape"))?;
+        let code = u32::from_str_radix(hex, 16).map_err(|_| self.error("invalid escape"))?;
+        self.pos += len;
+        Ok(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+fn is_identifier_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_' || b == b'$'
+}
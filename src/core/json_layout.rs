@@ -0,0 +1,481 @@
+use serde_json::Value;
+
+/// A JSON value of a document, by byte range. Containers keep their members in the order they were written and the
+/// whitespace / separators between them are the bytes of the document between two ranges.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub start: usize,
+    pub end: usize,
+    pub kind: NodeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeKind {
+    Scalar,
+    Array(Vec<Node>),
+    Object(Vec<Member>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    /// The decoded key
+    pub key: String,
+    /// The byte range of the key, quotes included
+    pub key_start: usize,
+    pub key_end: usize,
+    pub value: Node,
+}
+
+impl Node {
+    /// Parses the value starting at the first non whitespace byte of `text`. The text is expected to be valid JSON
+    /// (it's only used on documents serde_json has already read).
+    pub fn parse(text: &str) -> Option<Node> {
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            text,
+            pos: 0,
+        };
+        parser.skip_whitespace();
+        parser.parse_value()
+    }
+
+    fn raw<'a>(&self, text: &'a str) -> &'a str {
+        &text[self.start..self.end]
+    }
+
+    fn value(&self, text: &str) -> Option<Value> {
+        serde_json::from_str(self.raw(text)).ok()
+    }
+}
+
+/// The value a new value is written after, see [`render`]
+#[derive(Debug, Clone, Copy)]
+pub struct Template<'a> {
+    pub text: &'a str,
+    pub node: &'a Node,
+    /// Set when the template is the previous item of an array rather than the value which was there before, only
+    /// its layout is followed then
+    pub sibling: bool,
+}
+
+impl<'a> Template<'a> {
+    pub fn new(text: &'a str, node: &'a Node) -> Self {
+        Self {
+            text,
+            node,
+            sibling: false,
+        }
+    }
+
+    fn with_node(self, node: &'a Node) -> Self {
+        Self { node, ..self }
+    }
+
+    fn gap(&self, start: usize, end: usize) -> &'a str {
+        &self.text[start..end]
+    }
+}
+
+/// Writes `new` (a node of `new_text`) following the layout of a template, usually the file the value was read from:
+/// - values equal to the template's are copied as written (number formats, escapes, whitespace)
+/// - object members are written in the template's order, members the template doesn't have come last in the order
+///   of `new`. Members the template has as `null` and `new` leaves out are kept.
+/// - separators and indentation come from the template, items added to an array are laid out like the item before
+/// - anything else (new members, scalars which changed) is written like the vendor files, see [`write_formatted`]
+pub fn render(
+    out: &mut Vec<u8>,
+    new_text: &str,
+    new: &Node,
+    template: Template<'_>,
+    indent: Indent<'_>,
+) {
+    let node = template.node;
+    if new.value(new_text).is_some() && new.value(new_text) == node.value(template.text) {
+        return out.extend_from_slice(node.raw(template.text).as_bytes());
+    }
+
+    match (&new.kind, &node.kind) {
+        (NodeKind::Object(new_members), NodeKind::Object(template_members))
+            if !template_members.is_empty() =>
+        {
+            let first = &template_members[0];
+            let open = template.gap(node.start + 1, first.key_start);
+            let colon = template.gap(first.key_end, first.value.start);
+            let separators = template_members
+                .windows(2)
+                .map(|pair| template.gap(pair[0].value.end, pair[1].key_start))
+                .collect::<Vec<_>>();
+            let last = template_members.last().unwrap();
+            let close = template.gap(last.value.end, node.end - 1);
+
+            // (template member, new member)
+            let mut members = vec![];
+            for template_member in template_members {
+                match new_members
+                    .iter()
+                    .find(|member| member.key == template_member.key)
+                {
+                    Some(member) => members.push((Some(template_member), Some(member))),
+                    None if !template.sibling
+                        && template_member.value.raw(template.text) == "null" =>
+                    {
+                        members.push((Some(template_member), None))
+                    }
+                    None => {}
+                }
+            }
+            members.extend(
+                new_members
+                    .iter()
+                    .filter(|member| !template_members.iter().any(|other| other.key == member.key))
+                    .map(|member| (None, Some(member))),
+            );
+
+            out.push(b'{');
+            for (index, (template_member, member)) in members.into_iter().enumerate() {
+                let before = match index {
+                    0 => open.to_string(),
+                    _ => separators
+                        .get(index - 1)
+                        .or(separators.last())
+                        .map_or_else(|| format!(",{open}"), |separator| separator.to_string()),
+                };
+                out.extend_from_slice(before.as_bytes());
+                let member_indent = indent.of_line(&before).unwrap_or(indent);
+                match (template_member, member) {
+                    (Some(template_member), Some(member)) => {
+                        out.extend_from_slice(
+                            template
+                                .gap(template_member.key_start, template_member.value.start)
+                                .as_bytes(),
+                        );
+                        render(
+                            out,
+                            new_text,
+                            &member.value,
+                            template.with_node(&template_member.value),
+                            member_indent,
+                        );
+                    }
+                    (Some(template_member), None) => out.extend_from_slice(
+                        template
+                            .gap(template_member.key_start, template_member.value.end)
+                            .as_bytes(),
+                    ),
+                    (None, Some(member)) => {
+                        out.extend_from_slice(
+                            &new_text.as_bytes()[member.key_start..member.key_end],
+                        );
+                        out.extend_from_slice(colon.as_bytes());
+                        write_formatted(out, new_text, &member.value, member_indent);
+                    }
+                    (None, None) => {}
+                }
+            }
+            out.extend_from_slice(close.as_bytes());
+            out.push(b'}');
+        }
+        (NodeKind::Array(new_items), NodeKind::Array(template_items))
+            if !template_items.is_empty() && !new_items.is_empty() =>
+        {
+            let open = template.gap(node.start + 1, template_items[0].start);
+            let separators = template_items
+                .windows(2)
+                .map(|pair| template.gap(pair[0].end, pair[1].start))
+                .collect::<Vec<_>>();
+            let close = template.gap(template_items.last().unwrap().end, node.end - 1);
+
+            out.push(b'[');
+            for (index, item) in new_items.iter().enumerate() {
+                let before = match index {
+                    0 => open,
+                    _ => separators
+                        .get(index - 1)
+                        .or(separators.last())
+                        .copied()
+                        .unwrap_or(", "),
+                };
+                out.extend_from_slice(before.as_bytes());
+                let item_indent = indent.of_line(before).unwrap_or(indent.deeper());
+                let item_template = match template_items.get(index) {
+                    Some(template_item) => template.with_node(template_item),
+                    None => Template {
+                        sibling: true,
+                        ..template.with_node(template_items.last().unwrap())
+                    },
+                };
+                render(out, new_text, item, item_template, item_indent);
+            }
+            out.extend_from_slice(close.as_bytes());
+            out.push(b']');
+        }
+        _ => write_formatted(out, new_text, new, indent),
+    }
+}
+
+/// The indentation of a line, as a number of `unit`s
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Indent<'a> {
+    unit: &'a str,
+    level: usize,
+}
+
+impl Indent<'static> {
+    /// The usual indentation of the vendor files
+    pub const TABS: Self = Self {
+        unit: "\t",
+        level: 0,
+    };
+}
+
+impl<'a> Indent<'a> {
+    /// The indentation unit of a document (tabs or spaces), taken from its first indented line
+    pub fn detect(text: &'a str) -> Self {
+        text.lines()
+            .map(|line| &line[..line.len() - line.trim_start().len()])
+            .find(|indentation| !indentation.is_empty())
+            .map_or(Indent::TABS, |unit| Self { unit, level: 0 })
+    }
+
+    fn deeper(self) -> Self {
+        Self {
+            level: self.level + 1,
+            ..self
+        }
+    }
+
+    /// The indentation of the line a separator ends on, if it ends a line
+    fn of_line(self, separator: &str) -> Option<Self> {
+        let (_, indentation) = separator.rsplit_once('\n')?;
+        Some(Self {
+            level: indentation.len() / self.unit.len().max(1),
+            ..self
+        })
+    }
+
+    fn write(self, out: &mut Vec<u8>) {
+        for _ in 0..self.level {
+            out.extend_from_slice(self.unit.as_bytes());
+        }
+    }
+}
+
+/// Writes a value like the vendor files: arrays of objects are written as `[{ ... }, { ... }]` rather than one element
+/// per line. `indent` is the indentation of the line the value starts on.
+pub fn write_formatted(out: &mut Vec<u8>, text: &str, node: &Node, indent: Indent<'_>) {
+    match &node.kind {
+        NodeKind::Object(members) if !members.is_empty() => {
+            out.push(b'{');
+            for (index, member) in members.iter().enumerate() {
+                if index > 0 {
+                    out.push(b',');
+                }
+                out.push(b'\n');
+                indent.deeper().write(out);
+                out.extend_from_slice(&text.as_bytes()[member.key_start..member.key_end]);
+                out.extend_from_slice(b": ");
+                write_formatted(out, text, &member.value, indent.deeper());
+            }
+            out.push(b'\n');
+            indent.write(out);
+            out.push(b'}');
+        }
+        NodeKind::Array(items) if !items.is_empty() => {
+            out.push(b'[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.extend_from_slice(b", ");
+                }
+                write_formatted(out, text, item, indent.deeper());
+            }
+            if items
+                .iter()
+                .any(|item| matches!(item.kind, NodeKind::Object(_)))
+            {
+                out.push(b'\n');
+                indent.write(out);
+            }
+            out.push(b']');
+        }
+        _ => out.extend_from_slice(node.raw(text).as_bytes()),
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.text[self.pos..].chars().next() {
+            if !c.is_whitespace() && c != '\u{feff}' {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        (self.peek()? == byte).then(|| self.pos += 1)
+    }
+
+    fn parse_value(&mut self) -> Option<Node> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let kind = match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                let mut members = vec![];
+                self.skip_whitespace();
+                if self.peek()? == b'}' {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key_start = self.pos;
+                        self.parse_string()?;
+                        let key_end = self.pos;
+                        let key = serde_json::from_str(&self.text[key_start..key_end]).ok()?;
+                        self.expect(b':')?;
+                        let value = self.parse_value()?;
+                        members.push(Member {
+                            key,
+                            key_start,
+                            key_end,
+                            value,
+                        });
+                        self.skip_whitespace();
+                        match self.peek()? {
+                            b',' => self.pos += 1,
+                            b'}' => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return None,
+                        }
+                    }
+                }
+                NodeKind::Object(members)
+            }
+            b'[' => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.peek()? == b']' {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        items.push(self.parse_value()?);
+                        self.skip_whitespace();
+                        match self.peek()? {
+                            b',' => self.pos += 1,
+                            b']' => {
+                                self.pos += 1;
+                                break;
+                            }
+                            _ => return None,
+                        }
+                    }
+                }
+                NodeKind::Array(items)
+            }
+            b'"' => {
+                self.parse_string()?;
+                NodeKind::Scalar
+            }
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || b"+-.".contains(&c))
+                {
+                    self.pos += 1;
+                }
+                if self.pos == start {
+                    return None;
+                }
+                NodeKind::Scalar
+            }
+        };
+        Some(Node {
+            start,
+            end: self.pos,
+            kind,
+        })
+    }
+
+    fn parse_string(&mut self) -> Option<()> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        self.pos += 1;
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return Some(());
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_over(template: &str, new: &str) -> String {
+        let (template_node, new_node) = (Node::parse(template).unwrap(), Node::parse(new).unwrap());
+        let mut out = vec![];
+        render(
+            &mut out,
+            new,
+            &new_node,
+            Template::new(template, &template_node),
+            Indent::detect(template),
+        );
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn nodes_keep_their_byte_ranges() {
+        let text = " {\"a\": [1, {\"b\": \"x\\\"}\"}], \"c\": null}";
+        let node = Node::parse(text).unwrap();
+        assert_eq!((node.start, node.end), (1, text.len()));
+        let NodeKind::Object(members) = &node.kind else {
+            panic!("{node:?}");
+        };
+        assert_eq!(
+            members
+                .iter()
+                .map(|member| member.key.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "c"]
+        );
+        assert_eq!(members[0].value.raw(text), "[1, {\"b\": \"x\\\"}\"}]");
+        assert_eq!(members[1].value.raw(text), "null");
+    }
+
+    #[test]
+    fn unchanged_values_are_copied_as_written() {
+        let template = "{\n    \"a\": 1.0,\n    \"b\": \"\\u0041\"\n}";
+        assert_eq!(render_over(template, r#"{"a":1.0,"b":"A"}"#), template);
+    }
+
+    #[test]
+    fn changes_follow_the_template_layout() {
+        let template = "{\n    \"b\": [{\n            \"x\": 1\n        }],\n    \"a\": null\n}";
+        assert_eq!(
+            render_over(template, r#"{"b":[{"x":1},{"x":2}],"c":{"d":true}}"#),
+            "{\n    \"b\": [{\n            \"x\": 1\n        }, {\n            \"x\": 2\n        }],\n    \"a\": null,\n    \"c\": {\n        \"d\": true\n    }\n}"
+        );
+    }
+}
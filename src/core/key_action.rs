@@ -0,0 +1,774 @@
+use std::{fmt, str::FromStr};
+
+use num::FromPrimitive;
+
+use super::consumer_usages;
+use super::key_values::{
+    self, DriverValue, DriverValueModifier, DriverValueMouseButton, DriverValueType,
+};
+
+/// What a key does, decoded from a driver value.
+///
+/// A driver value is `TTTTXXYY` where `TTTT` is the [`DriverValueType`] and the low 16 bits depend on the type. For
+/// keys `XX` is the key code (HID usage id) and `YY` the modifiers, for the mouse `YY` is the buttons and for the
+/// others it's an index / usage. Every u32 can be converted to a `KeyAction` and back without losing anything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyAction {
+    /// Nothing is bound ([`key_values::UNUSED_KEY_VALUE`])
+    Unused,
+    /// A key, optionally combined with modifiers (LCtrl+LShift+Esc). No key code and no modifiers disables the key.
+    Key {
+        modifiers: DriverValueModifier,
+        key_code: u8,
+    },
+    Mouse {
+        buttons: DriverValueMouseButton,
+    },
+    /// A consumer control usage (media, browser, launch application, etc)
+    System {
+        usage: u16,
+    },
+    Macro {
+        index: u16,
+    },
+    /// Switches layer whilst the key is held (1 = base, 2-4 = layer 1-3, 5 = driver)
+    TempSwitchLayer {
+        layer: u16,
+    },
+    /// Switches layer, same as the keyboard's own layer buttons (1 = driver, 2-4 = layer 1-3)
+    LayerButton {
+        layer: u16,
+    },
+    /// A value of a type we don't know the layout of (lighting, bluetooth, etc), kept as it is
+    Other(u32),
+}
+
+impl KeyAction {
+    pub const DISABLED: Self = KeyAction::Key {
+        modifiers: DriverValueModifier::NONE,
+        key_code: 0,
+    };
+
+    pub fn from_u32(driver_value: u32) -> Self {
+        if driver_value == key_values::UNUSED_KEY_VALUE {
+            return KeyAction::Unused;
+        }
+
+        let low = driver_value as u16;
+        match DriverValueType::from_u16((driver_value >> 16) as u16) {
+            Some(DriverValueType::Key) => KeyAction::Key {
+                modifiers: DriverValueModifier::from_bits_truncate(low as u8),
+                key_code: (low >> 8) as u8,
+            },
+            Some(DriverValueType::Mouse) if low <= u8::MAX as u16 => {
+                match DriverValueMouseButton::from_bits(low as u8) {
+                    Some(buttons) => KeyAction::Mouse { buttons },
+                    None => KeyAction::Other(driver_value),
+                }
+            }
+            Some(DriverValueType::System) => KeyAction::System { usage: low },
+            Some(DriverValueType::Macro) => KeyAction::Macro { index: low },
+            Some(DriverValueType::TempSwitchLayer) => KeyAction::TempSwitchLayer { layer: low },
+            Some(DriverValueType::LayerButton) => KeyAction::LayerButton { layer: low },
+            _ => KeyAction::Other(driver_value),
+        }
+    }
+
+    pub fn to_u32(self) -> u32 {
+        let (value_type, low) = match self {
+            KeyAction::Unused => return key_values::UNUSED_KEY_VALUE,
+            KeyAction::Other(driver_value) => return driver_value,
+            KeyAction::Key {
+                modifiers,
+                key_code,
+            } => (
+                DriverValueType::Key,
+                (key_code as u16) << 8 | modifiers.bits() as u16,
+            ),
+            KeyAction::Mouse { buttons } => (DriverValueType::Mouse, buttons.bits() as u16),
+            KeyAction::System { usage } => (DriverValueType::System, usage),
+            KeyAction::Macro { index } => (DriverValueType::Macro, index),
+            KeyAction::TempSwitchLayer { layer } => (DriverValueType::TempSwitchLayer, layer),
+            KeyAction::LayerButton { layer } => (DriverValueType::LayerButton, layer),
+        };
+        (value_type as u32) << 16 | low as u32
+    }
+
+    pub fn value_type(&self) -> DriverValueType {
+        match self {
+            KeyAction::Unused => DriverValueType::None,
+            KeyAction::Key { .. } => DriverValueType::Key,
+            KeyAction::Mouse { .. } => DriverValueType::Mouse,
+            KeyAction::System { .. } => DriverValueType::System,
+            KeyAction::Macro { .. } => DriverValueType::Macro,
+            KeyAction::TempSwitchLayer { .. } => DriverValueType::TempSwitchLayer,
+            KeyAction::LayerButton { .. } => DriverValueType::LayerButton,
+            KeyAction::Other(driver_value) => {
+                DriverValueType::from_u16((driver_value >> 16) as u16)
+                    .unwrap_or(DriverValueType::None)
+            }
+        }
+    }
+
+    pub fn modifiers(&self) -> DriverValueModifier {
+        match self {
+            KeyAction::Key { modifiers, .. } => *modifiers,
+            _ => DriverValueModifier::NONE,
+        }
+    }
+
+    /// The key without its modifiers (Ctrl+C => C). Modifier only values are kept as they are.
+    pub fn without_modifiers(self) -> Self {
+        match self {
+            KeyAction::Key { key_code, .. } if key_code != 0 => KeyAction::Key {
+                modifiers: DriverValueModifier::NONE,
+                key_code,
+            },
+            _ => self,
+        }
+    }
+
+    /// The named value, if there is one
+    pub fn driver_value(&self) -> Option<DriverValue> {
+        DriverValue::from_u32(self.to_u32())
+    }
+
+    /// If every part of the value is understood (a known key code, mouse button, etc)
+    pub fn is_known(&self) -> bool {
+        match self {
+            // Lighting keys etc only have a name
+            KeyAction::Other(_) => self.driver_value().is_some(),
+            KeyAction::Key { key_code: 0, .. } => true,
+            KeyAction::Key { key_code, .. } => key_values::long_driver_value(*key_code).is_some(),
+            KeyAction::System { usage } => {
+                self.driver_value().is_some() || consumer_usages::usage_name(*usage).is_some()
+            }
+            _ => true,
+        }
+    }
+}
+
+impl From<u32> for KeyAction {
+    fn from(driver_value: u32) -> Self {
+        Self::from_u32(driver_value)
+    }
+}
+
+impl From<KeyAction> for u32 {
+    fn from(action: KeyAction) -> Self {
+        action.to_u32()
+    }
+}
+
+impl From<DriverValue> for KeyAction {
+    fn from(driver_value: DriverValue) -> Self {
+        Self::from_u32(driver_value as u32)
+    }
+}
+
+/// The names used for modifiers in the key action syntax, in display order
+const MODIFIER_NAMES: &[(DriverValueModifier, &str)] = &[
+    (DriverValueModifier::LCTRL, "LCtrl"),
+    (DriverValueModifier::LSHIFT, "LShift"),
+    (DriverValueModifier::LALT, "LAlt"),
+    (DriverValueModifier::LWIN, "LWin"),
+    (DriverValueModifier::RCTRL, "RCtrl"),
+    (DriverValueModifier::RSHIFT, "RShift"),
+    (DriverValueModifier::RALT, "RAlt"),
+    (DriverValueModifier::RWIN, "RWin"),
+];
+
+/// Shorter names which are accepted for the left modifiers
+const MODIFIER_ALIASES: &[(DriverValueModifier, &str)] = &[
+    (DriverValueModifier::LCTRL, "Ctrl"),
+    (DriverValueModifier::LSHIFT, "Shift"),
+    (DriverValueModifier::LALT, "Alt"),
+    (DriverValueModifier::LWIN, "Win"),
+];
+
+const MOUSE_BUTTON_NAMES: &[(DriverValueMouseButton, &str)] = &[
+    (DriverValueMouseButton::LBUTTON, "Left"),
+    (DriverValueMouseButton::RBUTTON, "Right"),
+    (DriverValueMouseButton::MBUTTON, "Middle"),
+    (DriverValueMouseButton::BACK, "Back"),
+    (DriverValueMouseButton::ADVANCE, "Forward"),
+];
+
+/// `TempSwitchLayer` values, these are the same as [`Layer`](super::Layer)
+const TEMP_SWITCH_LAYER_NAMES: &[(u16, &str)] = &[
+    (1, "Base"),
+    (2, "Layer1"),
+    (3, "Layer2"),
+    (4, "Layer3"),
+    (5, "Driver"),
+];
+
+/// `LayerButton` values (there's no button for the base layer, pressing the active layer's button goes back to it)
+const LAYER_BUTTON_NAMES: &[(u16, &str)] =
+    &[(1, "Driver"), (2, "Layer1"), (3, "Layer2"), (4, "Layer3")];
+
+const MOUSE_PREFIX: &str = "Mouse.";
+const MEDIA_PREFIX: &str = "Media.";
+const TEMP_SWITCH_SUFFIX: &str = ".Temp";
+const LAYER_BUTTON_SUFFIX: &str = ".Switch";
+
+/// An error from parsing the key action syntax, with the closest names if it looks like a typo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyActionError {
+    pub message: String,
+    pub suggestions: Vec<String>,
+}
+
+impl ParseKeyActionError {
+    fn new(message: String) -> Self {
+        Self {
+            message,
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn unknown<'a>(kind: &str, name: &str, candidates: impl Iterator<Item = &'a str>) -> Self {
+        Self {
+            message: format!("unknown {kind} `{name}`"),
+            suggestions: suggest(name, candidates),
+        }
+    }
+}
+
+impl fmt::Display for ParseKeyActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some((last, rest)) = self.suggestions.split_last() {
+            let rest = rest
+                .iter()
+                .map(|suggestion| format!("`{suggestion}`"))
+                .collect::<Vec<_>>();
+            if rest.is_empty() {
+                write!(f, ", did you mean `{last}`?")?;
+            } else {
+                write!(f, ", did you mean {} or `{last}`?", rest.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseKeyActionError {}
+
+/// Formats the action the same way it's parsed: `LCtrl+LShift+C`, `Mouse.Back`, `Media.PlayPause`, `Layer2.Temp`,
+/// `Layer1.Switch`, `Macro(3)`, `Disabled`, `Unused`. Anything without a name is written as hex (`0x0A080002`).
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            KeyAction::Unused => write!(f, "Unused"),
+            KeyAction::Key {
+                modifiers,
+                key_code,
+            } => {
+                if modifiers.is_empty() && key_code == 0 {
+                    return write!(f, "Disabled");
+                }
+                let mut parts = MODIFIER_NAMES
+                    .iter()
+                    .filter(|(modifier, _)| modifiers.contains(*modifier))
+                    .map(|(_, name)| name.to_string())
+                    .collect::<Vec<_>>();
+                if key_code != 0 {
+                    parts.push(key_code_name(key_code));
+                }
+                write!(f, "{}", parts.join("+"))
+            }
+            KeyAction::Mouse { buttons } if !buttons.is_empty() => {
+                let parts = MOUSE_BUTTON_NAMES
+                    .iter()
+                    .filter(|(button, _)| buttons.contains(*button))
+                    .map(|(_, name)| format!("{MOUSE_PREFIX}{name}"))
+                    .collect::<Vec<_>>();
+                write!(f, "{}", parts.join("+"))
+            }
+            KeyAction::System { usage } => match self.driver_value() {
+                Some(driver_value) => {
+                    let name = driver_value.to_string();
+                    write!(f, "{MEDIA_PREFIX}{}", name.trim_start_matches("Media"))
+                }
+                None => match consumer_usages::usage_name(usage) {
+                    Some(name) => write!(f, "{MEDIA_PREFIX}{name}"),
+                    None => write!(f, "Media(0x{usage:04X})"),
+                },
+            },
+            KeyAction::Macro { index } => write!(f, "Macro({index})"),
+            KeyAction::TempSwitchLayer { layer } => match find_name(TEMP_SWITCH_LAYER_NAMES, layer)
+            {
+                Some(name) => write!(f, "{name}{TEMP_SWITCH_SUFFIX}"),
+                None => write!(f, "0x{:08X}", self.to_u32()),
+            },
+            KeyAction::LayerButton { layer } => match find_name(LAYER_BUTTON_NAMES, layer) {
+                Some(name) => write!(f, "{name}{LAYER_BUTTON_SUFFIX}"),
+                None => write!(f, "0x{:08X}", self.to_u32()),
+            },
+            KeyAction::Mouse { .. } | KeyAction::Other(_) => match self.driver_value() {
+                Some(driver_value) => write!(f, "{driver_value}"),
+                None => write!(f, "0x{:08X}", self.to_u32()),
+            },
+        }
+    }
+}
+
+impl FromStr for KeyAction {
+    type Err = ParseKeyActionError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(ParseKeyActionError::new("empty key action".to_string()));
+        }
+        if text.eq_ignore_ascii_case("Unused") {
+            return Ok(KeyAction::Unused);
+        }
+        if let Some(driver_value) = parse_hex(text) {
+            return Ok(KeyAction::from_u32(driver_value));
+        }
+        if let Some(index) = strip_call(text, "Macro") {
+            return match index.parse() {
+                Ok(index) => Ok(KeyAction::Macro { index }),
+                Err(_) => Err(ParseKeyActionError::new(format!(
+                    "invalid macro index `{index}`"
+                ))),
+            };
+        }
+        if let Some(usage) = strip_call(text, "Media") {
+            return match parse_hex(usage).and_then(|usage| u16::try_from(usage).ok()) {
+                Some(usage) => Ok(KeyAction::System { usage }),
+                None => Err(ParseKeyActionError::new(format!(
+                    "invalid media usage `{usage}`"
+                ))),
+            };
+        }
+        if let Some(name) = strip_prefix_ignore_case(text, MEDIA_PREFIX) {
+            return parse_media(name);
+        }
+        if let Some(layer) = strip_suffix_ignore_case(text, TEMP_SWITCH_SUFFIX) {
+            return match find_value(TEMP_SWITCH_LAYER_NAMES, layer) {
+                Some(layer) => Ok(KeyAction::TempSwitchLayer { layer }),
+                None => Err(ParseKeyActionError::unknown(
+                    "layer",
+                    layer,
+                    TEMP_SWITCH_LAYER_NAMES.iter().map(|(_, name)| *name),
+                )),
+            };
+        }
+        if let Some(layer) = strip_suffix_ignore_case(text, LAYER_BUTTON_SUFFIX) {
+            return match find_value(LAYER_BUTTON_NAMES, layer) {
+                Some(layer) => Ok(KeyAction::LayerButton { layer }),
+                None => Err(ParseKeyActionError::unknown(
+                    "layer",
+                    layer,
+                    LAYER_BUTTON_NAMES.iter().map(|(_, name)| *name),
+                )),
+            };
+        }
+
+        let parts = text.split('+').map(str::trim).collect::<Vec<_>>();
+        if parts.iter().any(|part| part.is_empty()) {
+            return Err(ParseKeyActionError::new(format!(
+                "`{text}` has an empty part"
+            )));
+        }
+        if strip_prefix_ignore_case(parts[0], MOUSE_PREFIX).is_some() {
+            return parse_mouse(&parts);
+        }
+        if parts.len() == 1 {
+            // Any named value (BrightnessUp, ToggleBluetooth, All, ...)
+            if let Some(action) = find_named_value(text) {
+                return Ok(action);
+            }
+        }
+        parse_key(&parts)
+    }
+}
+
+/// The name of a key code on its own (`0x29` => `Esc`), `Key(0xF0)` if it doesn't have one
+fn key_code_name(key_code: u8) -> String {
+    let action = KeyAction::Key {
+        modifiers: DriverValueModifier::NONE,
+        key_code,
+    };
+    match action.driver_value() {
+        Some(driver_value) => driver_value.to_string(),
+        None => format!("Key(0x{key_code:02X})"),
+    }
+}
+
+fn parse_key(parts: &[&str]) -> Result<KeyAction, ParseKeyActionError> {
+    let mut modifiers = DriverValueModifier::NONE;
+    let mut key = None;
+    for part in parts {
+        if let Some(modifier) = find_modifier(part) {
+            modifiers |= modifier;
+            continue;
+        }
+
+        let key_code = if let Some(key_code) = strip_call(part, "Key") {
+            parse_hex(key_code)
+                .and_then(|key_code| u8::try_from(key_code).ok())
+                .ok_or_else(|| ParseKeyActionError::new(format!("invalid key code `{key_code}`")))?
+        } else {
+            match find_named_value(part) {
+                Some(KeyAction::Key {
+                    modifiers: key_modifiers,
+                    key_code,
+                }) => {
+                    modifiers |= key_modifiers;
+                    key_code
+                }
+                Some(_) => {
+                    return Err(ParseKeyActionError::new(format!(
+                        "`{part}` can't be combined with other keys"
+                    )))
+                }
+                None => return Err(unknown_key(part)),
+            }
+        };
+        if key.replace(key_code).is_some() {
+            return Err(ParseKeyActionError::new(format!(
+                "`{}` has more than one key (only modifiers can be combined)",
+                parts.join("+")
+            )));
+        }
+    }
+
+    Ok(KeyAction::Key {
+        modifiers,
+        key_code: key.unwrap_or(0),
+    })
+}
+
+fn parse_mouse(parts: &[&str]) -> Result<KeyAction, ParseKeyActionError> {
+    let mut buttons = DriverValueMouseButton::NONE;
+    for part in parts {
+        let Some(name) = strip_prefix_ignore_case(part, MOUSE_PREFIX) else {
+            return Err(ParseKeyActionError::new(format!(
+                "`{part}` can't be combined with mouse buttons"
+            )));
+        };
+        match MOUSE_BUTTON_NAMES
+            .iter()
+            .find(|(_, button_name)| button_name.eq_ignore_ascii_case(name))
+        {
+            Some((button, _)) => buttons |= *button,
+            None => {
+                return Err(ParseKeyActionError::unknown(
+                    "mouse button",
+                    name,
+                    MOUSE_BUTTON_NAMES.iter().map(|(_, name)| *name),
+                ))
+            }
+        }
+    }
+    Ok(KeyAction::Mouse { buttons })
+}
+
+fn parse_media(name: &str) -> Result<KeyAction, ParseKeyActionError> {
+    let media_names = media_names();
+    match media_names
+        .iter()
+        .find(|(_, media_name)| media_name.eq_ignore_ascii_case(name))
+    {
+        Some((usage, _)) => Ok(KeyAction::System { usage: *usage }),
+        None => Err(ParseKeyActionError::unknown(
+            "media key",
+            name,
+            media_names.iter().map(|(_, name)| name.as_str()),
+        )),
+    }
+}
+
+/// The names of the `System` usages as written after `Media.`, the [`DriverValue`] names come first as they're what
+/// the usages are displayed as
+fn media_names() -> Vec<(u16, String)> {
+    let driver_value_names =
+        DriverValue::ALL
+            .iter()
+            .filter_map(|driver_value| match KeyAction::from(*driver_value) {
+                KeyAction::System { usage } => {
+                    let name = driver_value.to_string();
+                    Some((usage, name.trim_start_matches("Media").to_string()))
+                }
+                _ => None,
+            });
+    let usage_names = consumer_usages::CONSUMER_USAGES
+        .iter()
+        .map(|(usage, name)| (*usage, name.to_string()));
+    driver_value_names.chain(usage_names).collect()
+}
+
+fn unknown_key(name: &str) -> ParseKeyActionError {
+    let names = DriverValue::ALL
+        .iter()
+        .map(|driver_value| driver_value.to_string())
+        .chain(MODIFIER_ALIASES.iter().map(|(_, alias)| alias.to_string()))
+        .collect::<Vec<_>>();
+    ParseKeyActionError::unknown("key", name, names.iter().map(|name| name.as_str()))
+}
+
+fn find_modifier(name: &str) -> Option<DriverValueModifier> {
+    MODIFIER_NAMES
+        .iter()
+        .chain(MODIFIER_ALIASES)
+        .find(|(_, modifier_name)| modifier_name.eq_ignore_ascii_case(name))
+        .map(|(modifier, _)| *modifier)
+}
+
+/// Finds a [`DriverValue`] by name, otherwise a `keys.json` key by name ("PS", "INS") or language key ("key_esc")
+fn find_named_value(name: &str) -> Option<KeyAction> {
+    // "1" is easier to remember than "D1"
+    let driver_value_name = match name.as_bytes() {
+        [digit] if digit.is_ascii_digit() => format!("D{name}"),
+        _ => name.to_string(),
+    };
+    if let Some(driver_value) = DriverValue::ALL.iter().find(|driver_value| {
+        driver_value
+            .to_string()
+            .eq_ignore_ascii_case(&driver_value_name)
+    }) {
+        return Some(KeyAction::from(*driver_value));
+    }
+
+    let catalog = &*key_values::CATALOG;
+    catalog
+        .find_by_name(name)
+        .or_else(|| catalog.find_by_lang(name))
+        .map(|key| KeyAction::from_u32(key.driver_value))
+}
+
+fn find_name(names: &[(u16, &'static str)], value: u16) -> Option<&'static str> {
+    names
+        .iter()
+        .find(|(name_value, _)| *name_value == value)
+        .map(|(_, name)| *name)
+}
+
+fn find_value(names: &[(u16, &str)], name: &str) -> Option<u16> {
+    names
+        .iter()
+        .find(|(_, value_name)| value_name.eq_ignore_ascii_case(name))
+        .map(|(value, _)| *value)
+}
+
+fn parse_hex(text: &str) -> Option<u32> {
+    let hex = strip_prefix_ignore_case(text, "0x")?;
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// `Macro(3)` => `3`
+fn strip_call<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    let rest = strip_prefix_ignore_case(text, name)?;
+    Some(
+        rest.trim_start()
+            .strip_prefix('(')?
+            .strip_suffix(')')?
+            .trim(),
+    )
+}
+
+fn strip_prefix_ignore_case<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let head = text.get(..prefix.len())?;
+    head.eq_ignore_ascii_case(prefix)
+        .then(|| &text[prefix.len()..])
+}
+
+fn strip_suffix_ignore_case<'a>(text: &'a str, suffix: &str) -> Option<&'a str> {
+    let split = text.len().checked_sub(suffix.len())?;
+    let tail = text.get(split..)?;
+    tail.eq_ignore_ascii_case(suffix).then(|| &text[..split])
+}
+
+/// Up to three names which look like what was typed (ignoring case)
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let name = name.to_ascii_lowercase();
+    let max_distance = (name.len() / 3).max(1);
+
+    let mut matches = candidates
+        .filter_map(|candidate| {
+            let lower = candidate.to_ascii_lowercase();
+            let distance = edit_distance(&name, &lower);
+            if distance <= max_distance {
+                Some((distance, candidate))
+            } else if name.len() >= 2 && lower.starts_with(&name) {
+                Some((max_distance + 1, candidate))
+            } else {
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    matches.sort();
+    matches.dedup_by(|a, b| a.1.eq_ignore_ascii_case(b.1));
+    matches
+        .into_iter()
+        .take(3)
+        .map(|(_, candidate)| candidate.to_string())
+        .collect()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + (a_char != *b_char) as usize;
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn driver_values_are_decoded() {
+        assert_eq!(
+            KeyAction::from_u32(key_values::UNUSED_KEY_VALUE),
+            KeyAction::Unused
+        );
+        assert_eq!(KeyAction::from(DriverValue::Disabled), KeyAction::DISABLED);
+        assert_eq!(
+            KeyAction::from_u32(0x02000603),
+            KeyAction::Key {
+                modifiers: DriverValueModifier::LCTRL | DriverValueModifier::LSHIFT,
+                key_code: 0x06,
+            }
+        );
+        assert_eq!(
+            KeyAction::from_u32(0x01010001),
+            KeyAction::Mouse {
+                buttons: DriverValueMouseButton::LBUTTON,
+            }
+        );
+        assert_eq!(
+            KeyAction::from(DriverValue::MediaPlayPause),
+            KeyAction::System { usage: 0xCD }
+        );
+        assert_eq!(
+            KeyAction::from_u32(0x0A010003),
+            KeyAction::Macro { index: 3 }
+        );
+        assert_eq!(
+            KeyAction::from_u32(0x0A070003),
+            KeyAction::TempSwitchLayer { layer: 3 }
+        );
+        assert_eq!(
+            KeyAction::from_u32(0x0A060002),
+            KeyAction::LayerButton { layer: 2 }
+        );
+        assert_eq!(
+            KeyAction::from_u32(0x0A080002),
+            KeyAction::Other(0x0A080002)
+        );
+        // Mouse values with unknown buttons aren't mouse buttons
+        assert_eq!(
+            KeyAction::from_u32(0x01010100),
+            KeyAction::Other(0x01010100)
+        );
+    }
+
+    #[test]
+    fn driver_values_round_trip() {
+        for driver_value in [
+            key_values::UNUSED_KEY_VALUE,
+            0x00000000,
+            DriverValue::Esc as u32,
+            0x02FF29FF,
+            0x01010007,
+            0x01010100,
+            0x030000CD,
+            0x0A01FFFF,
+            0x0A060004,
+            0x0A070005,
+            0x0A080002,
+            DriverValue::All as u32,
+            0x12345678,
+        ] {
+            assert_eq!(KeyAction::from_u32(driver_value).to_u32(), driver_value);
+        }
+    }
+
+    #[test]
+    fn modifiers_are_removed_from_keys_only() {
+        let ctrl_c = KeyAction::from_u32(0x02000601);
+        assert_eq!(ctrl_c.modifiers(), DriverValueModifier::LCTRL);
+        assert_eq!(ctrl_c.without_modifiers(), KeyAction::from(DriverValue::C));
+        let ctrl = KeyAction::from(DriverValue::LCtrl);
+        assert_eq!(ctrl.without_modifiers(), ctrl);
+    }
+
+    #[test]
+    fn syntax_round_trips() {
+        for text in [
+            "Unused",
+            "Disabled",
+            "Esc",
+            "LCtrl+LShift+C",
+            "RAlt",
+            "Key(0xF0)",
+            "Mouse.Left+Mouse.Back",
+            "Media.PlayPause",
+            "Macro(3)",
+            "Layer2.Temp",
+            "Base.Temp",
+            "Layer1.Switch",
+            "0x0A080002",
+        ] {
+            let action = text.parse::<KeyAction>().unwrap();
+            assert_eq!(action.to_string(), text);
+        }
+    }
+
+    #[test]
+    fn syntax_is_lenient() {
+        let parse = |text: &str| text.parse::<KeyAction>().unwrap();
+        assert_eq!(parse(" shift + ctrl + c "), parse("LCtrl+LShift+C"));
+        assert_eq!(parse("1"), KeyAction::from(DriverValue::D1));
+        assert_eq!(parse("0x02002900"), KeyAction::from(DriverValue::Esc));
+        assert_eq!(parse("macro( 7 )"), KeyAction::Macro { index: 7 });
+        assert_eq!(parse("layer3.switch"), KeyAction::LayerButton { layer: 4 });
+    }
+
+    #[test]
+    fn keys_json_names_are_parsed() {
+        // Nothing loads keys.json up front, the catalog is read on first use
+        let parse = |text: &str| text.parse::<KeyAction>().unwrap();
+        assert_eq!(parse("PS"), KeyAction::from(DriverValue::PrintScreen));
+        assert_eq!(parse("key_insert"), KeyAction::from(DriverValue::Insert));
+        assert!(parse("PS").is_known());
+        assert!(KeyAction::from_u32(0x02000708).is_known());
+        assert!(!KeyAction::from_u32(0x0200F000).is_known());
+        assert!(KeyAction::from(DriverValue::BrightnessUp).is_known());
+        assert!(!KeyAction::from_u32(0x09020003).is_known());
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let error = |text: &str| text.parse::<KeyAction>().unwrap_err();
+        assert_eq!(error("").message, "empty key action");
+        assert_eq!(error("Ctrl++C").message, "`Ctrl++C` has an empty part");
+        assert_eq!(
+            error("A+B").message,
+            "`A+B` has more than one key (only modifiers can be combined)"
+        );
+        assert_eq!(error("Macro(x)").message, "invalid macro index `x`");
+        assert_eq!(error("Base.Switch").message, "unknown layer `Base`");
+        assert_eq!(
+            error("Mouse.Left+A").message,
+            "`A` can't be combined with mouse buttons"
+        );
+
+        let typo = error("Escc");
+        assert_eq!(typo.message, "unknown key `Escc`");
+        assert_eq!(typo.suggestions.first().map(String::as_str), Some("Esc"));
+        assert!(typo.to_string().contains("did you mean `Esc`"));
+    }
+}
@@ -0,0 +1,221 @@
+use std::{fmt, str::FromStr};
+
+use super::{
+    i18n,
+    key_values::{self, DriverValue},
+    model::ModelDefinition,
+    profile,
+    state::MAX_KEY_SET_SIZE,
+};
+
+/// The high bytes of the fake [`KeySelector::QuickSelect`] values (`0xFE0100NN`, NN = row)
+const QUICK_SELECT_DRIVER_VALUE: u32 = 0xFE010000;
+
+/// A host only value which stands for several keys of a model (see [`DriverValue::All`]). Selectors are expanded to
+/// the logic codes of the model before anything is sent to the keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeySelector {
+    /// Every key which has a default value (the Fn key isn't part of the key set)
+    All,
+    /// Every key except Ctrl / Shift / Alt / Win
+    AllExceptModifiers,
+    /// A row of the model's `QuickSelect` table (`config.json`), by index
+    QuickSelect(u8),
+}
+
+impl KeySelector {
+    pub fn from_driver_value(driver_value: u32) -> Option<Self> {
+        match driver_value {
+            value if value == DriverValue::All as u32 => Some(KeySelector::All),
+            value if value == DriverValue::AllExceptModifiers as u32 => {
+                Some(KeySelector::AllExceptModifiers)
+            }
+            value if value & 0xFFFFFF00 == QUICK_SELECT_DRIVER_VALUE => {
+                Some(KeySelector::QuickSelect(value as u8))
+            }
+            _ => None,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn to_driver_value(self) -> u32 {
+        match self {
+            KeySelector::All => DriverValue::All as u32,
+            KeySelector::AllExceptModifiers => DriverValue::AllExceptModifiers as u32,
+            KeySelector::QuickSelect(row) => QUICK_SELECT_DRIVER_VALUE | row as u32,
+        }
+    }
+
+    /// The logic codes of the keys the selector stands for on a model, sorted
+    pub fn expand(self, model: &ModelDefinition) -> Vec<i32> {
+        let is_bindable = |logic_code: i32| {
+            (0..MAX_KEY_SET_SIZE as i32).contains(&logic_code)
+                && model
+                    .get_key_by_logic_code(logic_code)
+                    .is_some_and(|key| key.driver_value != key_values::UNUSED_KEY_VALUE)
+        };
+        let is_modifier = |logic_code: i32| {
+            model
+                .get_key_by_logic_code(logic_code)
+                .and_then(|key| key_values::get_short_driver_value(key.driver_value))
+                .is_some_and(|short_value| (0xE0..=0xE7).contains(&short_value))
+        };
+
+        let mut logic_codes = match self {
+            KeySelector::All | KeySelector::AllExceptModifiers => model
+                .keys()
+                .map(|key| key.logic_code)
+                .filter(|&logic_code| is_bindable(logic_code))
+                .filter(|&logic_code| self == KeySelector::All || !is_modifier(logic_code))
+                .collect::<Vec<_>>(),
+            KeySelector::QuickSelect(row) => match model.config.quick_select.get(row as usize) {
+                Some(row) => row
+                    .location_codes
+                    .iter()
+                    .filter_map(|&location_code| model.get_key_at_location_code(location_code))
+                    .map(|key| key.logic_code)
+                    .filter(|&logic_code| is_bindable(logic_code))
+                    .collect(),
+                None => {
+                    log::warn!(
+                        "Model {} doesn't have QuickSelect row {row}",
+                        model.model_id
+                    );
+                    vec![]
+                }
+            },
+        };
+        logic_codes.sort_unstable();
+        logic_codes.dedup();
+        logic_codes
+    }
+
+    /// A name for the selector, with the localized title of `QuickSelect` rows ("First Line")
+    #[allow(dead_code)]
+    pub fn describe(self, model: &ModelDefinition) -> String {
+        match self {
+            KeySelector::QuickSelect(row) => match model.config.quick_select.get(row as usize) {
+                Some(quick_select) => format!("{self} ({})", quick_select.localized_title()),
+                None => self.to_string(),
+            },
+            _ => self.to_string(),
+        }
+    }
+
+    /// Finds a `QuickSelect` row by its language key or title ("line_first" / "第一行" / "First Line")
+    #[allow(dead_code)]
+    pub fn find_quick_select(model: &ModelDefinition, name: &str) -> Option<Self> {
+        model
+            .config
+            .quick_select
+            .iter()
+            .position(|row| {
+                row.lang.as_deref() == Some(name)
+                    || row.title == name
+                    || row.localized_title().eq_ignore_ascii_case(name)
+                    || i18n::translate_name(&row.title).eq_ignore_ascii_case(name)
+            })
+            .and_then(|row| u8::try_from(row).ok())
+            .map(KeySelector::QuickSelect)
+    }
+}
+
+/// `All`, `AllExceptModifiers`, `QuickSelect(2)`
+impl fmt::Display for KeySelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySelector::All => write!(f, "All"),
+            KeySelector::AllExceptModifiers => write!(f, "AllExceptModifiers"),
+            KeySelector::QuickSelect(row) => write!(f, "QuickSelect({row})"),
+        }
+    }
+}
+
+impl FromStr for KeySelector {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        if text.eq_ignore_ascii_case("All") {
+            return Ok(KeySelector::All);
+        }
+        if text.eq_ignore_ascii_case("AllExceptModifiers") {
+            return Ok(KeySelector::AllExceptModifiers);
+        }
+        let row = text
+            .get(.."QuickSelect(".len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case("QuickSelect("))
+            .and_then(|_| text["QuickSelect(".len()..].strip_suffix(')'));
+        match row.map(|row| row.trim().parse()) {
+            Some(Ok(row)) => Ok(KeySelector::QuickSelect(row)),
+            Some(Err(_)) => Err(format!("invalid QuickSelect row in `{text}`")),
+            None => {
+                match profile::parse_driver_value(text).and_then(KeySelector::from_driver_value) {
+                    Some(selector) => Ok(selector),
+                    None => Err(format!("unknown key selector `{text}`")),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selectors_are_parsed_and_formatted() {
+        for selector in [
+            KeySelector::All,
+            KeySelector::AllExceptModifiers,
+            KeySelector::QuickSelect(2),
+        ] {
+            assert_eq!(selector.to_string().parse(), Ok(selector));
+            assert_eq!(
+                KeySelector::from_driver_value(selector.to_driver_value()),
+                Some(selector)
+            );
+        }
+        assert_eq!(
+            " quickselect( 3 ) ".parse(),
+            Ok(KeySelector::QuickSelect(3))
+        );
+        assert_eq!("0xFE010004".parse(), Ok(KeySelector::QuickSelect(4)));
+        assert!("QuickSelect(x)".parse::<KeySelector>().is_err());
+        assert!("Esc".parse::<KeySelector>().is_err());
+        assert_eq!(
+            KeySelector::from_driver_value(DriverValue::Esc as u32),
+            None
+        );
+    }
+
+    #[test]
+    fn selectors_are_expanded_per_model() {
+        let model = ModelDefinition::get(656801822).unwrap();
+        let logic_code = |driver_value: DriverValue| {
+            model
+                .get_key_by_driver_value(driver_value as u32)
+                .unwrap()
+                .logic_code
+        };
+
+        let all = KeySelector::All.expand(&model);
+        assert!(all.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(all.contains(&logic_code(DriverValue::Esc)));
+        assert!(all.contains(&logic_code(DriverValue::LCtrl)));
+
+        let all_except_modifiers = KeySelector::AllExceptModifiers.expand(&model);
+        assert!(all_except_modifiers.contains(&logic_code(DriverValue::Esc)));
+        assert!(!all_except_modifiers.contains(&logic_code(DriverValue::LCtrl)));
+
+        // The first row of the `QuickSelect` table, by location code
+        let first_row = KeySelector::QuickSelect(0).expand(&model);
+        assert!(first_row.contains(&logic_code(DriverValue::Esc)));
+        assert!(!first_row.contains(&logic_code(DriverValue::Q)));
+        assert!(KeySelector::QuickSelect(100).expand(&model).is_empty());
+        assert_eq!(
+            KeySelector::find_quick_select(&model, "line_first"),
+            Some(KeySelector::QuickSelect(0))
+        );
+    }
+}
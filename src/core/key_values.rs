@@ -1,99 +1,482 @@
-use std::collections::HashMap;
+// num-derive 0.3 wraps the impls of `DriverValue` and `DriverValueType` in a named const
+#![allow(non_local_definitions)]
+
+use std::{collections::HashMap, fs};
 
 use bitflags::bitflags;
 use lazy_static::lazy_static;
-use parking_lot::Mutex;
+use num::FromPrimitive;
+use serde::Deserialize;
+
+use super::{i18n, profile};
 
 /// Unused key valey / invalid key value. Used for keys which aren't mapped on the keyboard.
 pub const UNUSED_KEY_VALUE: u32 = 0xFFFFFFFF;
 
+/// The high bytes of every keyboard key value ([`DriverValueType::Key`])
+const KEY_DRIVER_VALUE: u32 = 0x02000000;
+
 lazy_static! {
-    pub static ref GROUPS: Mutex<Vec<Group>> = Mutex::new(Vec::new());
-    pub static ref KEYS: Mutex<HashMap<u32, Key>> = Mutex::new(HashMap::new());
-    pub static ref KEYS_BY_LOGIC_CODE: Mutex<HashMap<i32, Key>> = Mutex::new(HashMap::new());
-
-    /// These map full driver values (4 bytes long) to the individual driver key codes (1 byte long)
-    /// This is only for actual keys (keys like VolumeUp don't appear here)
-    pub static ref LONG_TO_SHORT_DRIVER_VALUES: Mutex<HashMap<u32, u8>> = Mutex::new(HashMap::new());
-    pub static ref SHORT_TO_LONG_DRIVER_VALUES: Mutex<HashMap<u8, u32>> = Mutex::new(HashMap::new());
+    /// Every key and group of `keys.json`, loaded on first use
+    pub static ref CATALOG: KeyCatalog = KeyCatalog::from_file(KEYS_PATH);
+
+    /// These map full driver values (4 bytes long) to the individual driver key codes (1 byte long), built on first
+    /// use. This is only for actual keys (keys like VolumeUp don't appear here)
+    static ref SHORT_DRIVER_VALUES: ShortDriverValues = ShortDriverValues::new();
+}
+
+const KEYS_PATH: &str = "assets/keys.json";
+
+struct ShortDriverValues {
+    long_to_short: HashMap<u32, u8>,
+    short_to_long: HashMap<u8, u32>,
+}
+
+impl ShortDriverValues {
+    /// The short values of the named values and the [`CATALOG`] keys
+    fn new() -> Self {
+        let mut long_to_short = HashMap::new();
+        let mut short_to_long = HashMap::new();
+        for driver_value in (0..=u8::MAX)
+            .map(|usage| KEY_DRIVER_VALUE | (usage as u32) << 8)
+            .chain((0..8).map(|bit| KEY_DRIVER_VALUE | 1 << bit))
+        {
+            if DriverValue::from_u32(driver_value).is_none()
+                && CATALOG.find_by_driver_value(driver_value).is_none()
+            {
+                continue;
+            }
+            if let Some(short_value) = get_short_driver_value(driver_value) {
+                long_to_short.insert(driver_value, short_value);
+                short_to_long.entry(short_value).or_insert(driver_value);
+            }
+        }
+        Self {
+            long_to_short,
+            short_to_long,
+        }
+    }
+}
+
+/// The HID usage id (keyboard page) of a plain key, which is what the firmware uses for macros and key press
+/// lighting. Modifiers are stored as flags in the low byte (0x02000001 = LCtrl) and map to 0xE0-0xE7. Combinations
+/// (0x02000601 = Ctrl+C) and non key values don't have a short value.
+pub fn get_short_driver_value(driver_value: u32) -> Option<u8> {
+    if driver_value & 0xFFFF0000 != KEY_DRIVER_VALUE {
+        return None;
+    }
+    let usage = (driver_value >> 8) as u8;
+    let modifiers = driver_value as u8;
+    match (usage, modifiers) {
+        (0, 0) => None,
+        (usage, 0) => Some(usage),
+        (0, modifier) if modifier.is_power_of_two() => Some(0xE0 + modifier.trailing_zeros() as u8),
+        _ => None,
+    }
+}
+
+/// Looks up a known short value (see [`get_short_driver_value`])
+pub fn short_driver_value(driver_value: u32) -> Option<u8> {
+    SHORT_DRIVER_VALUES
+        .long_to_short
+        .get(&driver_value)
+        .copied()
+}
+
+/// Looks up the full driver value of a short value (`0x29` => `0x02002900` / Esc)
+pub fn long_driver_value(short_value: u8) -> Option<u32> {
+    SHORT_DRIVER_VALUES.short_to_long.get(&short_value).copied()
+}
+
+/// A group of `keys.json`
+#[derive(Deserialize)]
+struct KeysFileGroup {
+    title: String,
+    #[serde(rename = "keytype")]
+    key_type: String,
+    #[serde(default)]
+    lang: String,
+    #[serde(default)]
+    icon: String,
+    #[serde(rename = "pname", default)]
+    p_name: String,
+    keys: Vec<KeysFileRow>,
+}
+
+#[derive(Deserialize)]
+struct KeysFileRow {
+    #[serde(rename = "linekeys")]
+    line_keys: Vec<KeysFileKey>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct KeysFileKey {
+    #[serde(default = "default_code")]
+    location_code: i32,
+    #[serde(default = "default_code")]
+    logic_code: i32,
+    name: String,
+    #[serde(default)]
+    lang_title: String,
+    lang_title_mac: Option<String>,
+    lang_title_linux: Option<String>,
+    icon: Option<String>,
+    driver_value: String,
+    #[serde(default)]
+    is_comb_func: bool,
+}
+
+fn default_code() -> i32 {
+    -1
+}
+
+/// The index of a [`Key`] in [`KeyCatalog::keys`]
+pub type KeyId = usize;
+/// The index of a [`Group`] in [`KeyCatalog::groups`]
+pub type GroupId = usize;
+
+/// The keys and groups of `keys.json`. Groups refer to their keys by id (and keys to their group), lookups return
+/// the first key in file order when a value appears more than once (Esc is both in "Standard" and "Disabled").
+#[derive(Debug, Default)]
+pub struct KeyCatalog {
+    groups: Vec<Group>,
+    keys: Vec<Key>,
+    by_driver_value: HashMap<u32, KeyId>,
+    by_logic_code: HashMap<i32, KeyId>,
+    /// Lowercase names
+    by_name: HashMap<String, KeyId>,
+    by_lang: HashMap<String, KeyId>,
 }
 
-pub fn load() {
-    let file_path = "assets/keys.json";
+impl KeyCatalog {
+    /// Reads `keys.json`, the catalog is empty if it can't be read
+    pub fn from_file(path: &str) -> Self {
+        let file_groups = match fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| {
+                serde_json::from_slice::<Vec<KeysFileGroup>>(&bytes).map_err(|err| err.to_string())
+            }) {
+            Ok(file_groups) => file_groups,
+            Err(err) => {
+                log::warn!("Failed to load `{path}`: {err}");
+                Vec::new()
+            }
+        };
+
+        let mut catalog = KeyCatalog::default();
+        for file_group in file_groups {
+            let group = catalog.add_group(Group {
+                id: 0,
+                key_type: file_group.key_type,
+                p_name: file_group.p_name,
+                title: file_group.title,
+                lang: file_group.lang,
+                icon: file_group.icon,
+                keys: vec![],
+            });
+            for file_key in file_group.keys.into_iter().flat_map(|row| row.line_keys) {
+                // Multi key values ("disable_all", etc) aren't numbers
+                let disable_set = DisableSet::from_name(&file_key.driver_value);
+                catalog.add_key(Key {
+                    id: 0,
+                    group,
+                    location_code: file_key.location_code,
+                    logic_code: file_key.logic_code,
+                    name: file_key.name,
+                    lang_title: file_key.lang_title,
+                    lang_title_mac: file_key.lang_title_mac,
+                    lang_title_linux: file_key.lang_title_linux,
+                    icon: file_key.icon,
+                    is_comb_func: file_key.is_comb_func,
+                    driver_value: profile::parse_driver_value(&file_key.driver_value)
+                        .unwrap_or(UNUSED_KEY_VALUE),
+                    driver_value_array: disable_set
+                        .map_or_else(Vec::new, DisableSet::driver_values),
+                    disable_set,
+                });
+            }
+        }
+        catalog
+    }
+
+    /// Adds a group (without keys, see [`KeyCatalog::add_key`]) and returns its id
+    pub fn add_group(&mut self, mut group: Group) -> GroupId {
+        let id = self.groups.len();
+        group.id = id;
+        group.keys.clear();
+        self.groups.push(group);
+        id
+    }
+
+    /// Adds a key to the end of its group and returns its id
+    pub fn add_key(&mut self, mut key: Key) -> KeyId {
+        let id = self.keys.len();
+        key.id = id;
+        self.groups[key.group].keys.push(id);
+
+        if key.driver_value != UNUSED_KEY_VALUE {
+            self.by_driver_value.entry(key.driver_value).or_insert(id);
+        }
+        if key.logic_code >= 0 {
+            self.by_logic_code.entry(key.logic_code).or_insert(id);
+        }
+        if !key.name.is_empty() {
+            self.by_name.entry(key.name.to_lowercase()).or_insert(id);
+        }
+        for lang in [
+            Some(&key.lang_title),
+            key.lang_title_mac.as_ref(),
+            key.lang_title_linux.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|lang| !lang.is_empty())
+        {
+            self.by_lang.entry(lang.clone()).or_insert(id);
+        }
+        self.keys.push(key);
+        id
+    }
+
+    #[allow(dead_code)]
+    pub fn groups(&self) -> &[Group] {
+        &self.groups
+    }
+
+    #[allow(dead_code)]
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+
+    #[allow(dead_code)]
+    pub fn group(&self, id: GroupId) -> Option<&Group> {
+        self.groups.get(id)
+    }
+
+    #[allow(dead_code)]
+    pub fn key(&self, id: KeyId) -> Option<&Key> {
+        self.keys.get(id)
+    }
+
+    /// The keys of a group, in file order
+    #[allow(dead_code)]
+    pub fn group_keys(&self, id: GroupId) -> impl Iterator<Item = &Key> {
+        self.groups
+            .get(id)
+            .into_iter()
+            .flat_map(|group| group.keys.iter().map(|&key| &self.keys[key]))
+    }
+
+    /// Finds a group by its `keytype` ("mode-layer-temp-switch")
+    #[allow(dead_code)]
+    pub fn find_group(&self, key_type: &str) -> Option<&Group> {
+        self.groups.iter().find(|group| group.key_type == key_type)
+    }
+
+    pub fn find_by_driver_value(&self, driver_value: u32) -> Option<&Key> {
+        self.by_driver_value
+            .get(&driver_value)
+            .map(|&id| &self.keys[id])
+    }
+
+    pub fn find_by_logic_code(&self, logic_code: i32) -> Option<&Key> {
+        self.by_logic_code
+            .get(&logic_code)
+            .map(|&id| &self.keys[id])
+    }
 
-    GROUPS.lock().clear();
-    KEYS.lock().clear();
-    KEYS_BY_LOGIC_CODE.lock().clear();
+    /// Finds a key by name, ignoring case
+    pub fn find_by_name(&self, name: &str) -> Option<&Key> {
+        self.by_name
+            .get(&name.to_lowercase())
+            .map(|&id| &self.keys[id])
+    }
 
-    LONG_TO_SHORT_DRIVER_VALUES.lock().clear();
-    SHORT_TO_LONG_DRIVER_VALUES.lock().clear();
+    /// Finds a key by language key ("key_esc"), including the macOS / Linux variants
+    pub fn find_by_lang(&self, lang: &str) -> Option<&Key> {
+        self.by_lang.get(lang).map(|&id| &self.keys[id])
+    }
 
-    // foreach (DriverValue value in Enum.GetValues(typeof(DriverValue)))
-    for value in 0..=255 {
-        let long_value = value as u32;
-        LONG_TO_SHORT_DRIVER_VALUES
-            .lock()
-            .insert(long_value, value as u8);
-        SHORT_TO_LONG_DRIVER_VALUES
-            .lock()
-            .insert(value as u8, long_value);
+    /// Finds the catalog entry of a multi key disable
+    #[allow(dead_code)]
+    pub fn find_by_disable_set(&self, disable_set: DisableSet) -> Option<&Key> {
+        self.keys
+            .iter()
+            .find(|key| key.disable_set == Some(disable_set))
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct Group {
+    pub id: GroupId,
     pub key_type: String,
     pub p_name: String,
     pub title: String,
-    pub keys: Vec<Key>,
+    /// The language key of the title
+    pub lang: String,
+    pub icon: String,
+    /// The keys of the group, in file order
+    pub keys: Vec<KeyId>,
 }
 
 impl Group {
-    pub fn new() -> Self {
-        Self {
-            key_type: String::new(),
-            p_name: String::new(),
-            title: String::new(),
-            keys: vec![],
-        }
+    /// The localized title of the group
+    #[allow(dead_code)]
+    pub fn localized_title(&self) -> String {
+        i18n::tr(&self.lang).unwrap_or_else(|| i18n::translate_name(&self.title))
     }
 }
 
+#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct Key {
+    pub id: KeyId,
     /// The owning group
-    pub group: Group,
+    pub group: GroupId,
     /// Where the key appears visually
     pub location_code: i32,
     /// Where the index of the key as defined in the keyboard profile.json
     pub logic_code: i32,
     /// The name of the key
     pub name: String,
-    /// The localized name of the key (can be None)
-    pub title: String,
+    /// The language key of the key's name ("key_esc")
+    pub lang_title: String,
+    /// Used instead of `lang_title` on macOS ("key_lwin" => "key_lcommand")
+    pub lang_title_mac: Option<String>,
+    /// Used instead of `lang_title` on Linux
+    pub lang_title_linux: Option<String>,
+    pub icon: Option<String>,
+    /// If the key can be part of a combination (Ctrl+Shift+Esc)
+    pub is_comb_func: bool,
     /// The key value which the keyboard firmware understands
     pub driver_value: u32,
-    /// Used for disabling multiple keys
-    pub driver_value_array: Vec<i32>,
+    /// Used for disabling multiple keys, the values of the keys which [`Key::disable_set`] disables
+    pub driver_value_array: Vec<u32>,
+    /// Set for the entries of the "disable" group which disable (or re-enable) several keys at once
+    pub disable_set: Option<DisableSet>,
 }
 
 impl Key {
-    pub fn new(group: Group) -> Self {
-        let mut s = Self {
-            group,
-            location_code: -1,
-            logic_code: -1,
-            name: String::new(),
-            title: String::new(),
-            driver_value: UNUSED_KEY_VALUE,
-            driver_value_array: vec![],
+    /// The language key of the key's name on this platform
+    pub fn lang_key(&self) -> &str {
+        let platform_lang = if cfg!(target_os = "macos") {
+            self.lang_title_mac.as_deref()
+        } else if cfg!(target_os = "linux") {
+            self.lang_title_linux.as_deref()
+        } else {
+            None
         };
-        s.group.keys.push(s.clone());
-        s
+        platform_lang.unwrap_or(&self.lang_title)
+    }
+
+    /// The localized name of the key, its `Name` if there's no translation
+    #[allow(dead_code)]
+    pub fn localized_title(&self) -> String {
+        i18n::tr(self.lang_key()).unwrap_or_else(|| i18n::translate_name(&self.name))
+    }
+}
+
+/// The multi key entries of the "disable" group of `keys.json`. Binding one of these to a key disables every key of
+/// the set on the layer (by the key's default value, so "Num" disables the number row wherever it is).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisableSet {
+    /// Every key (not the mouse / media / layer keys)
+    All,
+    /// Re-enables every disabled key
+    Enable,
+    /// The number row
+    Num,
+    /// The numpad
+    Pad,
+    /// F1-F12
+    Fx,
+    Letter,
+    Symbol,
+    /// Ctrl / Shift / Alt / Win
+    Ctrls,
+    /// The 9 keys above the arrows (PrintScreen ... PageDown)
+    Func9,
+    /// The arrows
+    Direction,
+}
+
+impl DisableSet {
+    pub const ALL: &'static [DisableSet] = &[
+        DisableSet::All,
+        DisableSet::Enable,
+        DisableSet::Num,
+        DisableSet::Pad,
+        DisableSet::Fx,
+        DisableSet::Letter,
+        DisableSet::Symbol,
+        DisableSet::Ctrls,
+        DisableSet::Func9,
+        DisableSet::Direction,
+    ];
+
+    /// The `DriverValue` used in `keys.json` ("disable_num")
+    pub fn name(self) -> &'static str {
+        match self {
+            DisableSet::All => "disable_all",
+            DisableSet::Enable => "disable_enable",
+            DisableSet::Num => "disable_num",
+            DisableSet::Pad => "disable_pad",
+            DisableSet::Fx => "disable_fx",
+            DisableSet::Letter => "disable_letter",
+            DisableSet::Symbol => "disable_symbol",
+            DisableSet::Ctrls => "disable_ctrls",
+            DisableSet::Func9 => "disable_func9",
+            DisableSet::Direction => "disable_direction",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|disable_set| disable_set.name().eq_ignore_ascii_case(name))
+            .copied()
+    }
+
+    /// The short values (HID usage ids) of the keys in the set, empty for `All` and `Enable`
+    fn short_values(self) -> Vec<u8> {
+        match self {
+            DisableSet::All | DisableSet::Enable => vec![],
+            DisableSet::Num => (0x1E..=0x27).collect(),
+            DisableSet::Pad => (0x53..=0x63).collect(),
+            DisableSet::Fx => (0x3A..=0x45).collect(),
+            DisableSet::Letter => (0x04..=0x1D).collect(),
+            DisableSet::Symbol => (0x2D..=0x38).chain([0x64]).collect(),
+            DisableSet::Ctrls => (0xE0..=0xE7).collect(),
+            DisableSet::Func9 => (0x46..=0x4E).collect(),
+            DisableSet::Direction => (0x4F..=0x52).collect(),
+        }
+    }
+
+    /// The driver values of the keys in the set, empty for `All` and `Enable`
+    pub fn driver_values(self) -> Vec<u32> {
+        self.short_values()
+            .into_iter()
+            .map(|short_value| match short_value {
+                0xE0..=0xE7 => KEY_DRIVER_VALUE | 1 << (short_value - 0xE0),
+                usage => KEY_DRIVER_VALUE | (usage as u32) << 8,
+            })
+            .collect()
+    }
+
+    /// If a key with the given default value is disabled by the set
+    pub fn contains(self, driver_value: u32) -> bool {
+        match self {
+            DisableSet::All => get_short_driver_value(driver_value).is_some(),
+            DisableSet::Enable => false,
+            _ => get_short_driver_value(driver_value)
+                .is_some_and(|short_value| self.short_values().contains(&short_value)),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy, FromPrimitive, ToPrimitive)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
 #[repr(u32)]
 pub enum DriverValue {
     None = 0,
@@ -343,6 +726,8 @@ pub enum DriverValue {
     /// Use 0xFEXXXXXX for fake values
     /// Used to assign all keys to a given value
     All = 0xFE000001,
+    /// Used to assign every key except the modifiers to a given value
+    AllExceptModifiers = 0xFE000002,
     // TODO: Find Bluetooth buttons 1-3
     // TODO: Find Fn value (if it even exists)
     // TODO: Find flash memory value (if it even exists)
@@ -354,7 +739,189 @@ impl std::fmt::Display for DriverValue {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+impl DriverValue {
+    /// Every named value, in the order they're declared
+    pub const ALL: &'static [DriverValue] = &[
+        DriverValue::None,
+        DriverValue::Esc,
+        DriverValue::Disabled,
+        DriverValue::F1,
+        DriverValue::F2,
+        DriverValue::F3,
+        DriverValue::F4,
+        DriverValue::F5,
+        DriverValue::F6,
+        DriverValue::F7,
+        DriverValue::F8,
+        DriverValue::F9,
+        DriverValue::F10,
+        DriverValue::F11,
+        DriverValue::F12,
+        DriverValue::PrintScreen,
+        DriverValue::ScrollLock,
+        DriverValue::Pause,
+        DriverValue::BackTick,
+        DriverValue::D1,
+        DriverValue::D2,
+        DriverValue::D3,
+        DriverValue::D4,
+        DriverValue::D5,
+        DriverValue::D6,
+        DriverValue::D7,
+        DriverValue::D8,
+        DriverValue::D9,
+        DriverValue::D0,
+        DriverValue::Subtract,
+        DriverValue::Add,
+        DriverValue::Backspace,
+        DriverValue::Insert,
+        DriverValue::Home,
+        DriverValue::PageUp,
+        DriverValue::Tab,
+        DriverValue::Q,
+        DriverValue::W,
+        DriverValue::E,
+        DriverValue::R,
+        DriverValue::T,
+        DriverValue::Y,
+        DriverValue::U,
+        DriverValue::I,
+        DriverValue::O,
+        DriverValue::P,
+        DriverValue::OpenSquareBrace,
+        DriverValue::CloseSquareBrace,
+        DriverValue::Backslash,
+        DriverValue::Delete,
+        DriverValue::End,
+        DriverValue::PageDown,
+        DriverValue::CapsLock,
+        DriverValue::A,
+        DriverValue::S,
+        DriverValue::D,
+        DriverValue::F,
+        DriverValue::G,
+        DriverValue::H,
+        DriverValue::J,
+        DriverValue::K,
+        DriverValue::L,
+        DriverValue::Semicolon,
+        DriverValue::Quotes,
+        DriverValue::Enter,
+        DriverValue::LShift,
+        DriverValue::AltBackslash,
+        DriverValue::Z,
+        DriverValue::X,
+        DriverValue::C,
+        DriverValue::V,
+        DriverValue::B,
+        DriverValue::N,
+        DriverValue::M,
+        DriverValue::Comma,
+        DriverValue::Period,
+        DriverValue::Slash,
+        DriverValue::RShift,
+        DriverValue::Up,
+        DriverValue::LCtrl,
+        DriverValue::LWin,
+        DriverValue::LAlt,
+        DriverValue::Space,
+        DriverValue::RAlt,
+        DriverValue::RWin,
+        DriverValue::Menu,
+        DriverValue::RCtrl,
+        DriverValue::Left,
+        DriverValue::Down,
+        DriverValue::Right,
+        DriverValue::NumLock,
+        DriverValue::NumPadSlash,
+        DriverValue::NumPadAsterisk,
+        DriverValue::NumPadSubtract,
+        DriverValue::NumPad7,
+        DriverValue::NumPad8,
+        DriverValue::NumPad9,
+        DriverValue::NumPadAdd,
+        DriverValue::NumPad4,
+        DriverValue::NumPad5,
+        DriverValue::NumPad6,
+        DriverValue::NumPad1,
+        DriverValue::NumPad2,
+        DriverValue::NumPad3,
+        DriverValue::NumPad0,
+        DriverValue::NumPadPeriod,
+        DriverValue::NumPadEnter,
+        DriverValue::OpenMediaPlayer,
+        DriverValue::MediaPlayPause,
+        DriverValue::MediaStop,
+        DriverValue::MediaPrevious,
+        DriverValue::MediaNext,
+        DriverValue::VolumeUp,
+        DriverValue::VolumeDown,
+        DriverValue::VolumeMute,
+        DriverValue::BrowserSearch,
+        DriverValue::BrowserStop,
+        DriverValue::BrowserBack,
+        DriverValue::BrowserForward,
+        DriverValue::BrowserRefresh,
+        DriverValue::BrowserFavorites,
+        DriverValue::BrowserHome,
+        DriverValue::OpenEmail,
+        DriverValue::OpenMyComputer,
+        DriverValue::OpenCalculator,
+        DriverValue::Copy,
+        DriverValue::Paste,
+        DriverValue::MouseLClick,
+        DriverValue::MouseRClick,
+        DriverValue::MouseMClick,
+        DriverValue::MouseBack,
+        DriverValue::MouseAdvance,
+        DriverValue::TempSwitchLayerBase,
+        DriverValue::TempSwitchLayer1,
+        DriverValue::TempSwitchLayer2,
+        DriverValue::TempSwitchLayer3,
+        DriverValue::TempSwitchLayerDriver,
+        DriverValue::Power,
+        DriverValue::Clear,
+        DriverValue::F13,
+        DriverValue::F14,
+        DriverValue::F15,
+        DriverValue::F16,
+        DriverValue::F17,
+        DriverValue::F18,
+        DriverValue::F19,
+        DriverValue::F20,
+        DriverValue::F21,
+        DriverValue::F22,
+        DriverValue::F23,
+        DriverValue::F24,
+        DriverValue::NumPadComma,
+        DriverValue::IntlRo,
+        DriverValue::KanaMode,
+        DriverValue::IntlYen,
+        DriverValue::Convert,
+        DriverValue::NonConvert,
+        DriverValue::Lang3,
+        DriverValue::Lang4,
+        DriverValue::ToggleLockWindowsKey,
+        DriverValue::ToggleBluetooth,
+        DriverValue::ToggleBluetoothNoLED,
+        DriverValue::DriverLayerButton,
+        DriverValue::Layer1Button,
+        DriverValue::Layer2Button,
+        DriverValue::Layer3Button,
+        DriverValue::NextLightingEffect,
+        DriverValue::NextReactiveLightingEffect,
+        DriverValue::BrightnessUp,
+        DriverValue::BrightnessDown,
+        DriverValue::LightingSpeedDecrease,
+        DriverValue::LightingSpeedIncrease,
+        DriverValue::LightingPauseResume,
+        DriverValue::ToggleLighting,
+        DriverValue::All,
+        DriverValue::AllExceptModifiers,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, FromPrimitive, ToPrimitive)]
 #[repr(u16)]
 pub enum DriverValueType {
     None = 0,
@@ -363,6 +930,8 @@ pub enum DriverValueType {
     /// Open "My Computer", calculator, etc
     System = 0x0300,
     Macro = 0x0A01,
+    /// See [`DriverValue::Layer1Button`]
+    LayerButton = 0x0A06,
     TempSwitchLayer = 0x0A07,
 }
 
@@ -390,3 +959,135 @@ bitflags! {
         const ADVANCE = 0x10;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_driver_values() {
+        let short = |driver_value: DriverValue| get_short_driver_value(driver_value as u32);
+        assert_eq!(short(DriverValue::Esc), Some(0x29));
+        assert_eq!(short(DriverValue::A), Some(0x04));
+        assert_eq!(short(DriverValue::LCtrl), Some(0xE0));
+        assert_eq!(short(DriverValue::RWin), Some(0xE7));
+        assert_eq!(short(DriverValue::Disabled), None);
+        assert_eq!(short(DriverValue::MediaPlayPause), None);
+        // Combinations
+        assert_eq!(get_short_driver_value(0x02000601), None);
+        assert_eq!(get_short_driver_value(0x02000003), None);
+        assert_eq!(get_short_driver_value(UNUSED_KEY_VALUE), None);
+    }
+
+    fn key(group: GroupId, name: &str, lang: &str, driver_value: u32, logic_code: i32) -> Key {
+        Key {
+            id: 0,
+            group,
+            location_code: -1,
+            logic_code,
+            name: name.to_string(),
+            lang_title: lang.to_string(),
+            lang_title_mac: None,
+            lang_title_linux: None,
+            icon: None,
+            is_comb_func: false,
+            driver_value,
+            driver_value_array: vec![],
+            disable_set: None,
+        }
+    }
+
+    fn group(key_type: &str) -> Group {
+        Group {
+            id: 0,
+            key_type: key_type.to_string(),
+            p_name: String::new(),
+            title: String::new(),
+            lang: String::new(),
+            icon: String::new(),
+            keys: vec![],
+        }
+    }
+
+    #[test]
+    fn catalog_lookups_return_the_first_key() {
+        let mut catalog = KeyCatalog::default();
+        let standard = catalog.add_group(group("standard"));
+        let disabled = catalog.add_group(group("disable"));
+        let esc = catalog.add_key(key(standard, "ESC", "key_esc", DriverValue::Esc as u32, 0));
+        let mut lwin = key(standard, "LWIN", "key_lwin", DriverValue::LWin as u32, 1);
+        lwin.lang_title_mac = Some("key_lcommand".to_string());
+        let lwin = catalog.add_key(lwin);
+        let disabled_esc = catalog.add_key(key(disabled, "Esc", "", DriverValue::Esc as u32, -1));
+
+        assert_eq!(
+            catalog
+                .group_keys(standard)
+                .map(|key| key.id)
+                .collect::<Vec<_>>(),
+            [esc, lwin]
+        );
+        assert_eq!(catalog.key(disabled_esc).unwrap().group, disabled);
+        assert_eq!(catalog.find_group("disable").unwrap().id, disabled);
+        assert_eq!(
+            catalog
+                .find_by_driver_value(DriverValue::Esc as u32)
+                .unwrap()
+                .id,
+            esc
+        );
+        assert_eq!(catalog.find_by_name("esc").unwrap().id, esc);
+        assert_eq!(catalog.find_by_logic_code(1).unwrap().id, lwin);
+        assert!(catalog.find_by_logic_code(-1).is_none());
+        assert_eq!(catalog.find_by_lang("key_lcommand").unwrap().id, lwin);
+        assert!(catalog.find_by_lang("").is_none());
+    }
+
+    #[test]
+    fn disable_sets() {
+        assert_eq!(DisableSet::from_name("DISABLE_NUM"), Some(DisableSet::Num));
+        assert_eq!(DisableSet::from_name("disable"), None);
+        assert_eq!(
+            DisableSet::Direction.driver_values(),
+            [
+                DriverValue::Right as u32,
+                DriverValue::Left as u32,
+                DriverValue::Down as u32,
+                DriverValue::Up as u32,
+            ]
+        );
+        assert!(DisableSet::Ctrls
+            .driver_values()
+            .contains(&(DriverValue::RWin as u32)));
+        assert!(DisableSet::All.driver_values().is_empty());
+
+        assert!(DisableSet::Letter.contains(DriverValue::Q as u32));
+        assert!(!DisableSet::Letter.contains(DriverValue::D1 as u32));
+        assert!(DisableSet::All.contains(DriverValue::LShift as u32));
+        assert!(!DisableSet::All.contains(DriverValue::MediaPlayPause as u32));
+        assert!(!DisableSet::Enable.contains(DriverValue::Q as u32));
+    }
+
+    #[test]
+    fn keys_json_is_loaded() {
+        // Nothing is loaded up front, the tables are built on first use
+        let catalog = &*CATALOG;
+        let esc = catalog.find_by_name("Esc").unwrap();
+        assert_eq!(esc.driver_value, DriverValue::Esc as u32);
+        assert_eq!(esc.lang_title, "key_esc");
+        assert_eq!(catalog.group(esc.group).unwrap().key_type, "primary");
+
+        let disable = catalog.find_group("disable").unwrap();
+        let disable_num = catalog.find_by_disable_set(DisableSet::Num).unwrap();
+        assert_eq!(disable_num.group, disable.id);
+        assert_eq!(disable_num.driver_value, UNUSED_KEY_VALUE);
+        assert_eq!(
+            disable_num.driver_value_array,
+            DisableSet::Num.driver_values()
+        );
+
+        assert_eq!(short_driver_value(DriverValue::Esc as u32), Some(0x29));
+        assert_eq!(long_driver_value(0x29), Some(DriverValue::Esc as u32));
+        assert_eq!(long_driver_value(0xE0), Some(DriverValue::LCtrl as u32));
+    }
+}
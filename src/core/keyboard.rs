@@ -1,8 +1,312 @@
-use hidapi::HidDevice;
+use std::fmt;
 
-use super::State;
+use hidapi::{HidDevice, HidError};
+
+use super::{
+    crc16,
+    opcodes::{LayerDataType, OpCodes},
+    sanity::{self, LayoutIssue, Severity},
+    state::LayerContents,
+    Layer, State,
+};
+
+/// The size of every packet sent to / received from the keyboard
+const PACKET_SIZE: usize = 64;
+/// Where the CRC of a packet is stored (it covers the whole packet)
+const CRC_OFFSET: usize = 6;
+/// Where the payload of a packet starts
+const DATA_OFFSET: usize = 8;
+/// The largest chunk of data which fits in a packet
+const MAX_CHUNK_SIZE: usize = PACKET_SIZE - DATA_OFFSET;
+/// How long to wait for the keyboard to acknowledge a packet
+const READ_TIMEOUT_MS: i32 = 1000;
 
 pub struct Keyboard {
     device: HidDevice,
     state: State,
 }
+
+#[derive(Debug)]
+pub enum KeyboardError {
+    Hid(HidError),
+    /// The keyboard didn't answer a packet in time
+    Timeout(OpCodes),
+    /// The keyboard answered with a different op code, a bad CRC or an error status
+    BadResponse(OpCodes),
+    /// The layer hasn't been created (see [`State::initialize_buffers`])
+    NoLayer(Layer),
+    /// The layer has errors (see [`sanity::check`]), upload it with `force` to do it anyway
+    UnsafeLayout(Vec<LayoutIssue>),
+    /// Every key of the layer is unused (there's no factory profile for it and nothing was bound), upload it with
+    /// `force` to clear the layer on the keyboard anyway
+    EmptyLayer(Layer),
+    /// [`State`] doesn't keep the data the type resets (the layer's lighting, the macros), resetting it on the
+    /// keyboard would leave the host out of date
+    UnsupportedDataType(LayerDataType),
+}
+
+impl fmt::Display for KeyboardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyboardError::Hid(err) => write!(f, "{err}"),
+            KeyboardError::Timeout(op) => write!(f, "the keyboard didn't answer {op:?}"),
+            KeyboardError::BadResponse(op) => write!(f, "the keyboard rejected {op:?}"),
+            KeyboardError::NoLayer(layer) => write!(f, "layer {layer:?} hasn't been created"),
+            KeyboardError::UnsafeLayout(issues) => {
+                write!(f, "refusing to upload the layer")?;
+                for issue in issues {
+                    write!(f, "\n{issue}")?;
+                }
+                Ok(())
+            }
+            KeyboardError::EmptyLayer(layer) => {
+                write!(
+                    f,
+                    "refusing to upload layer {layer:?}, none of its keys are bound"
+                )
+            }
+            KeyboardError::UnsupportedDataType(data_type) => {
+                write!(f, "{data_type:?} can't be reset")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeyboardError {}
+
+impl From<HidError> for KeyboardError {
+    fn from(err: HidError) -> Self {
+        KeyboardError::Hid(err)
+    }
+}
+
+impl Keyboard {
+    pub fn new(device: HidDevice, state: State) -> Self {
+        Self { device, state }
+    }
+
+    pub fn state(&self) -> &State {
+        &self.state
+    }
+
+    pub fn state_mut(&mut self) -> &mut State {
+        &mut self.state
+    }
+
+    /// Uploads the keys, Fn keys and key press lighting of a layer from [`State`].
+    ///
+    /// The onboard layers (base / 1 / 2 / 3) have their data types reset first and are then written in chunks. The
+    /// driver layer is never stored on the keyboard, its keys are set with [`OpCodes::DriverLayerSetKeyValues`] and
+    /// its config with [`OpCodes::DriverLayerSetConfig`] (only if the profile has lighting, see
+    /// [`StateLayer::has_le_set`](super::state::StateLayer::has_le_set)).
+    ///
+    /// The layer is checked with [`sanity::check`] first, warnings are logged and errors (no way back to the base
+    /// layer) refuse the upload unless `force` is set, as does a layer without any bound key.
+    pub fn apply_layer(&mut self, layer: Layer, force: bool) -> Result<(), KeyboardError> {
+        let issues = sanity::check(&self.state)
+            .into_iter()
+            .filter(|issue| issue.layer == layer)
+            .collect::<Vec<_>>();
+        for issue in &issues {
+            log::warn!("{issue}");
+        }
+        if !force && issues.iter().any(|issue| issue.severity == Severity::Error) {
+            return Err(KeyboardError::UnsafeLayout(issues));
+        }
+
+        let state_layer = self
+            .state
+            .get_layer(layer)
+            .ok_or(KeyboardError::NoLayer(layer))?;
+        if !force && state_layer.is_empty() {
+            return Err(KeyboardError::EmptyLayer(layer));
+        }
+        let key_set = key_values_bytes(&state_layer.key_set);
+        let fn_key_set = key_values_bytes(&state_layer.fn_key_set);
+        let key_press_lighting = state_layer.key_press_lighting_effect.clone();
+        let has_le_set = state_layer.has_le_set;
+
+        if layer == Layer::Driver {
+            self.write_data(OpCodes::DriverLayerSetKeyValues, 0, &key_set)?;
+            if has_le_set {
+                self.write_packet(OpCodes::DriverLayerSetConfig, 0x01, &[])?;
+            }
+            return Ok(());
+        }
+
+        let layer_index = layer as u8;
+        for data_type in [
+            LayerDataType::KeySet,
+            LayerDataType::FnKeySet,
+            LayerDataType::KeyPressLighting,
+        ] {
+            self.reset_data_type(layer, data_type)?;
+        }
+        self.write_data(OpCodes::LayerSetKeyValues, layer_index, &key_set)?;
+        self.write_data(OpCodes::LayerFnSetKeyValues, layer_index, &fn_key_set)?;
+        self.write_data(
+            OpCodes::LayerSetKeyPressLightingEffect,
+            layer_index,
+            &key_press_lighting,
+        )?;
+        Ok(())
+    }
+
+    /// Resets data types of a layer to the factory defaults, on the keyboard and in [`State`] (from
+    /// [`StateLayer::factory_default_model_data`](super::state::StateLayer::factory_default_model_data)) so both
+    /// agree. Only the data types [`State`] keeps can be reset (see [`KeyboardError::UnsupportedDataType`]). The
+    /// driver layer isn't stored on the keyboard, it's reset in [`State`] and uploaded again.
+    pub fn reset(&mut self, layer: Layer, types: &[LayerDataType]) -> Result<(), KeyboardError> {
+        if let Some(&data_type) = types
+            .iter()
+            .find(|data_type| matches!(data_type, LayerDataType::Lighting | LayerDataType::Macros))
+        {
+            return Err(KeyboardError::UnsupportedDataType(data_type));
+        }
+        if self.state.get_layer(layer).is_none() {
+            return Err(KeyboardError::NoLayer(layer));
+        }
+
+        for &data_type in types {
+            if layer != Layer::Driver {
+                self.reset_data_type(layer, data_type)?;
+            }
+            match data_type {
+                LayerDataType::KeySet => self.state.reset_key_set(layer, false),
+                LayerDataType::FnKeySet => self.state.reset_key_set(layer, true),
+                LayerDataType::KeyPressLighting => {
+                    self.state
+                        .copy_factory_default(layer, layer, LayerContents::LIGHTING)
+                }
+                LayerDataType::Lighting | LayerDataType::Macros => unreachable!(),
+            }
+            .map_err(|_| KeyboardError::NoLayer(layer))?;
+        }
+        if layer == Layer::Driver {
+            self.apply_layer(layer, true)?;
+        }
+        Ok(())
+    }
+
+    fn reset_data_type(&self, layer: Layer, data_type: LayerDataType) -> Result<(), KeyboardError> {
+        self.write_packet(OpCodes::LayerResetDataType, data_type as u8, &[layer as u8])
+    }
+
+    /// Writes a buffer in chunks, see [`data_packets`]
+    fn write_data(&self, op: OpCodes, op2: u8, data: &[u8]) -> Result<(), KeyboardError> {
+        for packet in data_packets(op, op2, data) {
+            self.write_raw_packet(op, &packet)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a single packet and waits for the keyboard to acknowledge it
+    fn write_packet(&self, op: OpCodes, op2: u8, data: &[u8]) -> Result<(), KeyboardError> {
+        self.write_raw_packet(op, &packet(op, op2, 0, data))
+    }
+
+    fn write_raw_packet(
+        &self,
+        op: OpCodes,
+        packet: &[u8; PACKET_SIZE],
+    ) -> Result<(), KeyboardError> {
+        // The first byte is the report id
+        let mut report = [0u8; PACKET_SIZE + 1];
+        report[1..].copy_from_slice(packet);
+        self.device.write(&report)?;
+
+        let mut response = [0u8; PACKET_SIZE];
+        let read = self.device.read_timeout(&mut response, READ_TIMEOUT_MS)?;
+        if read == 0 {
+            return Err(KeyboardError::Timeout(op));
+        }
+        if response[0] != op as u8 || !crc16::validate_crc(&mut response, 0, CRC_OFFSET) {
+            log::warn!("Unexpected response to {op:?}: {:02X?}", &response[..read]);
+            return Err(KeyboardError::BadResponse(op));
+        }
+        Ok(())
+    }
+}
+
+/// Frames a packet: the op codes, the offset (u16) and length (u8) of the data, the CRC (which covers the whole
+/// packet) and then the data
+fn packet(op: OpCodes, op2: u8, offset: u16, data: &[u8]) -> [u8; PACKET_SIZE] {
+    let mut packet = [0u8; PACKET_SIZE];
+    packet[0] = op as u8;
+    packet[1] = op2;
+    packet[2..4].copy_from_slice(&offset.to_le_bytes());
+    packet[4] = data.len() as u8;
+    packet[DATA_OFFSET..DATA_OFFSET + data.len()].copy_from_slice(data);
+    crc16::insert_crc(&mut packet, 0, CRC_OFFSET);
+    packet
+}
+
+/// Splits a buffer into packets of [`MAX_CHUNK_SIZE`] bytes, each with the offset of its chunk
+fn data_packets(op: OpCodes, op2: u8, data: &[u8]) -> Vec<[u8; PACKET_SIZE]> {
+    data.chunks(MAX_CHUNK_SIZE)
+        .enumerate()
+        .map(|(index, chunk)| packet(op, op2, (index * MAX_CHUNK_SIZE) as u16, chunk))
+        .collect()
+}
+
+/// Key buffers are sent as little endian u32 values
+fn key_values_bytes(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{key_values::UNUSED_KEY_VALUE, state};
+
+    #[test]
+    fn packet_framing() {
+        let data = [0x11, 0x22, 0x33];
+        let mut packet = packet(OpCodes::LayerSetKeyValues, 0x02, 0x0138, &data);
+        assert_eq!(packet[0], OpCodes::LayerSetKeyValues as u8);
+        assert_eq!(packet[1], 0x02);
+        assert_eq!(packet[2..4], [0x38, 0x01]);
+        assert_eq!(packet[4], 3);
+        assert_eq!(packet[5], 0);
+        assert_eq!(packet[8..11], data);
+        assert!(packet[11..].iter().all(|&byte| byte == 0));
+
+        // The CRC covers the whole packet, with its own bytes zeroed
+        let crc = u16::from_le_bytes([packet[6], packet[7]]);
+        let mut unsigned = packet;
+        unsigned[6..8].fill(0);
+        assert_eq!(crc, crc16::get_crc(&unsigned, 0, 0xFFFF));
+        assert!(crc16::validate_crc(&mut packet, 0, CRC_OFFSET));
+    }
+
+    #[test]
+    fn key_press_lighting_is_sent_in_chunks() {
+        let data = (0..state::KEY_PRESS_LIGHTING_EFFECT_SIZE)
+            .map(|index| index as u8)
+            .collect::<Vec<_>>();
+        let packets = data_packets(OpCodes::LayerSetKeyPressLightingEffect, 0x01, &data);
+        assert_eq!(packets.len(), 5);
+
+        let mut received = vec![];
+        for packet in &packets {
+            let offset = u16::from_le_bytes([packet[2], packet[3]]) as usize;
+            let len = packet[4] as usize;
+            assert_eq!(offset, received.len());
+            assert!(len <= MAX_CHUNK_SIZE);
+            received.extend_from_slice(&packet[DATA_OFFSET..DATA_OFFSET + len]);
+        }
+        assert_eq!(received, data);
+        assert_eq!(packets.last().unwrap()[4], 32);
+    }
+
+    #[test]
+    fn key_values_are_little_endian() {
+        assert_eq!(
+            key_values_bytes(&[0x02002900, UNUSED_KEY_VALUE]),
+            [0x00, 0x29, 0x00, 0x02, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+}
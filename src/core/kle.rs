@@ -0,0 +1,229 @@
+use serde_json::{json, Map, Value};
+
+use super::{
+    key_action::KeyAction,
+    key_values,
+    qmk::QmkKeyRect,
+    state::{Key, KeyRect, LayerEditError, State, MAX_KEY_SET_SIZE},
+    Layer,
+};
+
+/// The size of a 1u key in the keymaps written by [`to_keymap`], and the distance to its neighbours
+const KEY_SIZE: i32 = 50;
+const KEY_PITCH: i32 = 54;
+
+/// KLE positions and sizes are rounded to quarter units
+const QUARTERS: f64 = 4.0;
+
+/// A key of a Keyboard Layout Editor file, in key units
+#[derive(Debug, Clone, PartialEq)]
+pub struct KleKey {
+    pub x: f64,
+    pub y: f64,
+    pub w: f64,
+    pub h: f64,
+    /// The legends by KLE position (top left, bottom left, top right, ...), empty ones included
+    pub legends: Vec<String>,
+}
+
+impl KleKey {
+    /// The first legend which isn't empty
+    pub fn label(&self) -> &str {
+        self.legends
+            .iter()
+            .map(|legend| legend.trim())
+            .find(|legend| !legend.is_empty())
+            .unwrap_or_default()
+    }
+}
+
+impl From<&KleKey> for QmkKeyRect {
+    fn from(key: &KleKey) -> Self {
+        QmkKeyRect {
+            x: key.x,
+            y: key.y,
+            w: key.w,
+            h: key.h,
+        }
+    }
+}
+
+/// Exports a layer as KLE raw JSON. Each key's legend is the action bound to it, with the key's own label on the
+/// front when it's different (`LCtrl` on `Caps Lock`).
+pub fn export(state: &State, layer: Layer, fn_layer: bool) -> Result<Value, LayerEditError> {
+    let model = state.model();
+    let key_set = state
+        .get_layer(layer)
+        .ok_or(LayerEditError::NoLayer(layer))?
+        .key_set(fn_layer);
+    let (unit_x, unit_y) = model.layout_units();
+    let rows = model.layout_rows();
+    let gap_x = unit_x - median_size(rows.iter().flatten().map(|key| key.position.width));
+    let gap_y = unit_y - median_size(rows.iter().flatten().map(|key| key.position.height));
+    let left = rows
+        .iter()
+        .flatten()
+        .map(|key| key.position.left)
+        .min()
+        .unwrap_or_default();
+    let top = rows
+        .iter()
+        .flatten()
+        .map(|key| key.position.top)
+        .min()
+        .unwrap_or_default();
+
+    let name = format!(
+        "{} - {layer:?}{}",
+        model.model_name,
+        if fn_layer { " Fn" } else { "" }
+    );
+    let mut kle = vec![json!({ "name": name })];
+    // In quarter units, KLE moves down one unit after each row
+    let mut y = -4;
+    for row in &rows {
+        y += 4;
+        let mut x = 0;
+        let mut items = vec![];
+        for key in row {
+            let key_x = quarters((key.position.left - left) as f64 / unit_x);
+            let key_y = quarters((key.position.top - top) as f64 / unit_y);
+            let w = quarters((key.position.width as f64 + gap_x) / unit_x).max(1);
+            let h = quarters((key.position.height as f64 + gap_y) / unit_y).max(1);
+
+            let mut properties = Map::new();
+            if key_y != y {
+                properties.insert("y".to_string(), json!((key_y - y) as f64 / QUARTERS));
+                y = key_y;
+            }
+            if key_x != x {
+                properties.insert("x".to_string(), json!((key_x - x) as f64 / QUARTERS));
+            }
+            if w != 4 {
+                properties.insert("w".to_string(), json!(w as f64 / QUARTERS));
+            }
+            if h != 4 {
+                properties.insert("h".to_string(), json!(h as f64 / QUARTERS));
+            }
+            if !properties.is_empty() {
+                items.push(Value::Object(properties));
+            }
+            items.push(Value::String(legend(key, key_set)));
+            x = key_x + w;
+        }
+        kle.push(Value::Array(items));
+    }
+    Ok(Value::Array(kle))
+}
+
+/// Exports a layer of a model's factory default profiles, see [`export`]
+pub fn export_factory_default(model_id: u32, layer: Layer, fn_layer: bool) -> Option<Value> {
+    let mut state = State::get_keyboard_state(model_id)?;
+    state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+    export(&state, layer, fn_layer).ok()
+}
+
+/// Reads the keys of KLE raw JSON (rows of legends and property objects, optionally starting with the metadata
+/// object). Rotated keys aren't supported.
+pub fn parse(kle: &Value) -> Result<Vec<KleKey>, String> {
+    let rows = kle.as_array().ok_or("a KLE layout is an array of rows")?;
+    let mut keys = vec![];
+    let mut y = 0.0;
+    for row in rows.iter().filter(|row| !row.is_object()) {
+        let items = row.as_array().ok_or("a KLE row isn't an array")?;
+        let mut x = 0.0;
+        let (mut w, mut h) = (1.0, 1.0);
+        for item in items {
+            match item {
+                Value::Object(properties) => {
+                    let number = |name: &str| properties.get(name).and_then(Value::as_f64);
+                    if number("r").is_some_and(|rotation| rotation != 0.0) {
+                        return Err("rotated keys aren't supported".to_string());
+                    }
+                    x += number("x").unwrap_or_default();
+                    y += number("y").unwrap_or_default();
+                    w = number("w").unwrap_or(w);
+                    h = number("h").unwrap_or(h);
+                }
+                Value::String(legend) => {
+                    keys.push(KleKey {
+                        x,
+                        y,
+                        w,
+                        h,
+                        legends: legend.split('\n').map(str::to_string).collect(),
+                    });
+                    x += w;
+                    (w, h) = (1.0, 1.0);
+                }
+                _ => return Err(format!("unexpected KLE item `{item}`")),
+            }
+        }
+        y += 1.0;
+    }
+    Ok(keys)
+}
+
+/// Turns KLE keys into the keys of a vendor style `keymap.json`, for boards without data files. The names are the
+/// first legends, the location codes follow the KLE order and the logic codes are left at -1 since KLE doesn't know
+/// them.
+pub fn to_keymap(keys: &[KleKey]) -> Vec<Key> {
+    keys.iter()
+        .enumerate()
+        .map(|(index, key)| {
+            let label = match key.label() {
+                "" => format!("Key{index}"),
+                label => label.to_string(),
+            };
+            Key {
+                key_name: label.clone(),
+                show: label,
+                logic_code: -1,
+                location_code: index as i32,
+                position: KeyRect {
+                    left: (key.x * KEY_PITCH as f64).round() as i32,
+                    top: (key.y * KEY_PITCH as f64).round() as i32,
+                    width: (key.w * KEY_PITCH as f64).round() as i32 - (KEY_PITCH - KEY_SIZE),
+                    height: (key.h * KEY_PITCH as f64).round() as i32 - (KEY_PITCH - KEY_SIZE),
+                },
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// The action bound to a key, with the key's label on the front (KLE legend 4) if it's different
+fn legend(key: &Key, key_set: &[u32]) -> String {
+    if key.key_name.eq_ignore_ascii_case("Fn") {
+        return "Fn".to_string();
+    }
+    let driver_value = usize::try_from(key.logic_code)
+        .ok()
+        .and_then(|logic_code| key_set.get(logic_code))
+        .copied()
+        .unwrap_or(key_values::UNUSED_KEY_VALUE);
+    let action = match KeyAction::from_u32(driver_value) {
+        KeyAction::Unused => String::new(),
+        action => action.to_string(),
+    };
+    let label = if key.show.is_empty() {
+        &key.key_name
+    } else {
+        &key.show
+    };
+    if action.eq_ignore_ascii_case(label) {
+        action
+    } else {
+        format!("{action}\n\n\n\n{label}")
+    }
+}
+
+fn quarters(units: f64) -> i32 {
+    (units * QUARTERS).round() as i32
+}
+
+fn median_size(sizes: impl Iterator<Item = i32>) -> f64 {
+    let mut sizes = sizes.collect::<Vec<_>>();
+    sizes.sort_unstable();
+    sizes.get(sizes.len() / 2).copied().unwrap_or_default() as f64
+}
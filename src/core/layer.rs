@@ -1,5 +1,6 @@
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Layer {
+    #[allow(dead_code)]
     Invalid = 0,
     Base = 1,
     Layer1 = 2,
@@ -7,3 +8,43 @@ pub enum Layer {
     Layer3 = 4,
     Driver = 5,
 }
+
+impl Layer {
+    pub const ALL: [Layer; 5] = [
+        Layer::Base,
+        Layer::Layer1,
+        Layer::Layer2,
+        Layer::Layer3,
+        Layer::Driver,
+    ];
+
+    /// Parses the name of a layer ("Layer1"), ignoring case, or its number (0 for the base layer, 1-3 for the
+    /// onboard layers)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.trim();
+        match name {
+            "0" => Some(Layer::Base),
+            "1" => Some(Layer::Layer1),
+            "2" => Some(Layer::Layer2),
+            "3" => Some(Layer::Layer3),
+            _ => Layer::ALL
+                .into_iter()
+                .find(|layer| format!("{layer:?}").eq_ignore_ascii_case(name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_are_parsed_by_name_or_number() {
+        assert_eq!(Layer::from_name("layer2"), Some(Layer::Layer2));
+        assert_eq!(Layer::from_name("Driver"), Some(Layer::Driver));
+        assert_eq!(Layer::from_name("0"), Some(Layer::Base));
+        assert_eq!(Layer::from_name("2"), Some(Layer::Layer2));
+        assert_eq!(Layer::from_name("4"), None);
+        assert_eq!(Layer::from_name("Invalid"), None);
+    }
+}
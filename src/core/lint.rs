@@ -0,0 +1,570 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use super::{
+    bom,
+    key_action::KeyAction,
+    key_values,
+    profile::{self, Profile},
+    state::Key,
+    variants::DataVariants,
+};
+
+/// Problems found in the vendor data files, see [`lint_assets`]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    /// The file isn't valid JSON (JS declarations, non UTF-8 text, etc)
+    InvalidJson {
+        file: String,
+        error: String,
+    },
+    /// The file is valid JSON but doesn't have the expected layout
+    UnexpectedData {
+        file: String,
+        error: String,
+    },
+    Bom {
+        file: String,
+    },
+    /// There's more data after the first JSON value
+    TrailingGarbage {
+        file: String,
+        offset: usize,
+    },
+    /// A keymap key which has no DriverValue in the default profile or in `keys.json`
+    MissingLogicCode {
+        file: String,
+        model_id: u32,
+        model_name: String,
+        key_name: String,
+        logic_code: i32,
+        location_code: i32,
+    },
+    DuplicateLocationCode {
+        file: String,
+        location_code: i32,
+        key_names: Vec<String>,
+    },
+    /// A key set index beyond the key buffer of the model's data (see [`key_buffer_size`])
+    KeySetIndexOutOfRange {
+        file: String,
+        key_set: String,
+        index: i32,
+        buffer_size: usize,
+    },
+    /// A key set value which isn't understood, see [`KeyAction::is_known`]. Modifier combinations and macros are
+    /// fine.
+    UnknownDriverValue {
+        file: String,
+        key_set: String,
+        driver_value: String,
+        indices: Vec<i32>,
+    },
+}
+
+impl Issue {
+    pub fn file(&self) -> &str {
+        match self {
+            Issue::InvalidJson { file, .. }
+            | Issue::UnexpectedData { file, .. }
+            | Issue::Bom { file }
+            | Issue::TrailingGarbage { file, .. }
+            | Issue::MissingLogicCode { file, .. }
+            | Issue::DuplicateLocationCode { file, .. }
+            | Issue::KeySetIndexOutOfRange { file, .. }
+            | Issue::UnknownDriverValue { file, .. } => file,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::InvalidJson { file, error } => write!(f, "{file}: invalid JSON: {error}"),
+            Issue::UnexpectedData { file, error } => write!(f, "{file}: unexpected data: {error}"),
+            Issue::Bom { file } => write!(f, "{file}: starts with a UTF-8 BOM"),
+            Issue::TrailingGarbage { file, offset } => {
+                write!(f, "{file}: trailing garbage after byte {offset}")
+            }
+            Issue::MissingLogicCode {
+                file,
+                model_id,
+                model_name,
+                key_name,
+                logic_code,
+                location_code,
+            } => write!(
+                f,
+                "{file}: Couldn't find DriverValue for key '{key_name}' logicCode: {logic_code} locationCode: {location_code} modelId: {model_id} modelName: {model_name}"
+            ),
+            Issue::DuplicateLocationCode {
+                file,
+                location_code,
+                key_names,
+            } => write!(
+                f,
+                "{file}: locationCode {location_code} is used by more than one key: '{}'",
+                key_names.join("', '")
+            ),
+            Issue::KeySetIndexOutOfRange {
+                file,
+                key_set,
+                index,
+                buffer_size,
+            } => write!(
+                f,
+                "{file}: {key_set} index {index} is beyond the key buffer (size {buffer_size})"
+            ),
+            Issue::UnknownDriverValue {
+                file,
+                key_set,
+                driver_value,
+                indices,
+            } => write!(
+                f,
+                "{file}: unknown DriverValue {driver_value} in {key_set} at {indices:?}"
+            ),
+        }
+    }
+}
+
+/// Checks every JSON file and every model folder under `assets_path`. The result is sorted by file.
+pub fn lint_assets(assets_path: impl AsRef<Path>) -> Vec<Issue> {
+    let assets_path = assets_path.as_ref();
+    let mut issues = Vec::new();
+
+    let mut files = Vec::new();
+    collect_json_files(assets_path, &mut files);
+    for path in &files {
+        lint_json_file(assets_path, path, &mut issues);
+    }
+
+    let model_names = load_model_names(&assets_path.join("device").join("models.json"));
+    let device_path = assets_path.join("device");
+    let mut model_ids = fs::read_dir(&device_path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    model_ids.sort_unstable();
+
+    for model_id in model_ids {
+        let model_path = device_path.join(model_id.to_string());
+        if model_path.join("data").is_dir() {
+            let model_name = model_names.get(&model_id).cloned().unwrap_or_default();
+            lint_model(assets_path, &model_path, model_id, &model_name, &mut issues);
+        }
+    }
+
+    issues.sort_by(|a, b| a.file().cmp(b.file()).then_with(|| a.cmp(b)));
+    issues.dedup();
+    issues
+}
+
+/// The human readable report (`assets/DuplicateKeys.txt`)
+pub fn format_report(issues: &[Issue]) -> String {
+    let mut report = String::from("Generated by `gk6xui lint-assets`. Do not edit by hand.\n\n");
+    for issue in issues {
+        report.push_str(&issue.to_string());
+        report.push('\n');
+    }
+    report
+}
+
+fn collect_json_files(path: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(path) else {
+        return;
+    };
+    let mut paths = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_json_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path);
+        }
+    }
+}
+
+fn relative_path(assets_path: &Path, path: &Path) -> String {
+    path.strip_prefix(assets_path)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn lint_json_file(assets_path: &Path, path: &Path, issues: &mut Vec<Issue>) {
+    let file = relative_path(assets_path, path);
+    let Ok(bytes) = fs::read(path) else {
+        return;
+    };
+
+    let bytes = match bom::strip_bom(&bytes) {
+        Some(bytes) => {
+            issues.push(Issue::Bom { file: file.clone() });
+            bytes
+        }
+        None => &bytes[..],
+    };
+
+    let mut stream = serde_json::Deserializer::from_slice(bytes).into_iter::<serde_json::Value>();
+    match stream.next() {
+        Some(Ok(_)) => {
+            let offset = stream.byte_offset();
+            if bytes[offset..].iter().any(|b| !b.is_ascii_whitespace()) {
+                issues.push(Issue::TrailingGarbage { file, offset });
+            }
+        }
+        Some(Err(err)) => issues.push(Issue::InvalidJson {
+            file,
+            error: error_message(&err),
+        }),
+        None => issues.push(Issue::InvalidJson {
+            file,
+            error: "empty file".to_string(),
+        }),
+    }
+}
+
+fn load_model_names(path: &Path) -> HashMap<u32, String> {
+    #[derive(serde::Deserialize)]
+    struct DeviceModel {
+        #[serde(rename = "modelID")]
+        model_id: u32,
+        name: String,
+    }
+
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Vec<DeviceModel>>(&bytes).ok())
+        .map(|models| {
+            models
+                .into_iter()
+                .map(|model| (model.model_id, model.name))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Reads a file which has already passed [`lint_json_file`], returning `None` if it's not valid JSON
+fn read_json_bytes(path: &Path) -> Option<Vec<u8>> {
+    let bytes = fs::read(path).ok()?;
+    let bytes = match bom::strip_bom(&bytes) {
+        Some(bytes) => bytes.to_vec(),
+        None => bytes,
+    };
+    let mut stream = serde_json::Deserializer::from_slice(&bytes).into_iter::<serde_json::Value>();
+    match stream.next() {
+        Some(Ok(_)) => {
+            let offset = stream.byte_offset();
+            Some(bytes[..offset].to_vec())
+        }
+        _ => None,
+    }
+}
+
+fn lint_model(
+    assets_path: &Path,
+    model_path: &Path,
+    model_id: u32,
+    model_name: &str,
+    issues: &mut Vec<Issue>,
+) {
+    let variants = DataVariants::discover(model_path);
+
+    let mut keymaps = vec![];
+    for variant in &variants.layouts {
+        let path = model_path.join(&variant.path);
+        let file = relative_path(assets_path, &path);
+        let Some(bytes) = read_json_bytes(&path) else {
+            continue;
+        };
+        match serde_json::from_slice::<Vec<Key>>(&bytes) {
+            Ok(keys) => keymaps.push((variant, file, keys)),
+            Err(err) => issues.push(Issue::UnexpectedData {
+                file,
+                error: error_message(&err),
+            }),
+        }
+    }
+
+    let mut profiles = HashMap::new();
+    for variant in &variants.profiles {
+        let path = model_path.join(&variant.path);
+        let file = relative_path(assets_path, &path);
+        let Some(bytes) = read_json_bytes(&path) else {
+            continue;
+        };
+        match Profile::from_slice(&bytes) {
+            Ok(profile) => {
+                profiles.insert(variant.path.clone(), (file, profile));
+            }
+            Err(err) => issues.push(Issue::UnexpectedData {
+                file,
+                error: error_message(&err),
+            }),
+        }
+    }
+
+    for variant in &variants.profiles {
+        let Some((file, profile)) = profiles.get(&variant.path) else {
+            continue;
+        };
+        // The buffer of the model's data is what the vendor software loads for it: the default profile and the
+        // keymap next to the profile (see `ModelDefinition::load_keys`)
+        let folder = folder(&variant.path);
+        let default_profile = profiles
+            .get(&format!("{folder}/profile.json"))
+            .map(|(_, profile)| profile);
+        let keys = keymaps
+            .iter()
+            .find(|(layout, _, _)| layout.path == format!("{folder}/keymap.json"))
+            .map(|(_, _, keys)| keys.as_slice());
+        let buffer_size = key_buffer_size(default_profile, keys);
+        lint_profile(file, profile, buffer_size, issues);
+    }
+
+    for (variant, file, keys) in keymaps {
+        // The same lookup as `ModelDefinition::load_keys`, the default profile next to the keymap first and then
+        // `keys.json`
+        let default_driver_values = profiles
+            .get(&format!("{}/profile.json", folder(&variant.path)))
+            .and_then(|(_, profile)| profile.key_set.as_ref())
+            .map(|key_set| {
+                key_set
+                    .iter()
+                    .filter(|entry| entry.driver_value() != Some(key_values::UNUSED_KEY_VALUE))
+                    .map(|entry| entry.index)
+                    .collect::<Vec<_>>()
+            });
+
+        let mut keys_by_location_code = BTreeMap::<i32, Vec<String>>::new();
+        for key in &keys {
+            keys_by_location_code
+                .entry(key.location_code)
+                .or_default()
+                .push(key.key_name.clone());
+        }
+        for (location_code, key_names) in keys_by_location_code {
+            if key_names.len() > 1 {
+                issues.push(Issue::DuplicateLocationCode {
+                    file: file.clone(),
+                    location_code,
+                    key_names,
+                });
+            }
+        }
+
+        if let Some(ref default_driver_values) = default_driver_values {
+            let catalog = &*key_values::CATALOG;
+            for key in &keys {
+                if key.logic_code > 0
+                    && !default_driver_values.contains(&key.logic_code)
+                    && catalog.find_by_logic_code(key.logic_code).is_none()
+                {
+                    issues.push(Issue::MissingLogicCode {
+                        file: file.clone(),
+                        model_id,
+                        model_name: model_name.to_string(),
+                        key_name: key.key_name.clone(),
+                        logic_code: key.logic_code,
+                        location_code: key.location_code,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The folder of a data file, relative to the model's folder ("data", "data - 副本")
+fn folder(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(folder, _)| folder)
+}
+
+/// The key buffer size of a model's data: one more than the highest logic code of its default profile and keymap.
+/// `None` if the folder has neither.
+fn key_buffer_size(default_profile: Option<&Profile>, keys: Option<&[Key]>) -> Option<usize> {
+    let profile_indices = default_profile
+        .and_then(|profile| profile.key_set.as_ref())
+        .into_iter()
+        .flatten()
+        .map(|entry| entry.index);
+    let logic_codes = keys.into_iter().flatten().map(|key| key.logic_code);
+    profile_indices
+        .chain(logic_codes)
+        .max()
+        .map(|logic_code| (logic_code + 1).max(0) as usize)
+}
+
+/// serde_json's message without the line / column, which changes between serde_json versions
+fn error_message(err: &serde_json::Error) -> String {
+    let message = err.to_string();
+    match message.rfind(" at line ") {
+        Some(index) if err.line() > 0 => message[..index].to_string(),
+        _ => message,
+    }
+}
+
+fn lint_profile(
+    file: &str,
+    profile: &Profile,
+    buffer_size: Option<usize>,
+    issues: &mut Vec<Issue>,
+) {
+    let key_sets = [
+        ("KeySet", &profile.key_set),
+        ("FnKeySet", &profile.fn_key_set),
+    ];
+    for (key_set_name, key_set) in key_sets {
+        let Some(key_set) = key_set else {
+            continue;
+        };
+
+        let mut unknown = BTreeMap::<String, Vec<i32>>::new();
+        for entry in key_set {
+            if let Some(buffer_size) = buffer_size {
+                if entry.index < 0 || entry.index as usize >= buffer_size {
+                    issues.push(Issue::KeySetIndexOutOfRange {
+                        file: file.to_string(),
+                        key_set: key_set_name.to_string(),
+                        index: entry.index,
+                        buffer_size,
+                    });
+                }
+            }
+
+            let known = match entry.driver_value() {
+                Some(driver_value) => KeyAction::from_u32(driver_value).is_known(),
+                None => false,
+            };
+            if !known {
+                let driver_value = match entry.driver_value() {
+                    Some(driver_value) => profile::format_driver_value(driver_value),
+                    None => entry.driver_value.clone(),
+                };
+                unknown.entry(driver_value).or_default().push(entry.index);
+            }
+        }
+
+        for (driver_value, indices) in unknown {
+            issues.push(Issue::UnknownDriverValue {
+                file: file.to_string(),
+                key_set: key_set_name.to_string(),
+                driver_value,
+                indices,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_messages_have_no_position() {
+        let err = serde_json::from_str::<serde_json::Value>("{\n  \"a\" 1}").unwrap_err();
+        assert_eq!(error_message(&err), "expected `:`");
+        let err = serde_json::from_str::<Vec<Key>>("[{\"LogicCode\": \"\"}]").unwrap_err();
+        assert!(!error_message(&err).contains("line"), "{err}");
+    }
+
+    /// A model folder with one of each problem, so that a check which stops finding anything fails here rather
+    /// than silently dropping lines from the report
+    #[test]
+    fn problems_are_found() {
+        let assets_path = std::env::temp_dir().join(format!("gk6xui-lint-{}", std::process::id()));
+        let data_path = assets_path.join("device").join("1").join("data");
+        fs::create_dir_all(&data_path).unwrap();
+        let write = |path: &Path, text: &str| fs::write(path, text).unwrap();
+        let profile = |key_set: &str| {
+            format!(
+                r#"{{"GUID": "", "ModeIndex": 0, "Name": "", "Active": 0, "KeySet": [{key_set}]}}"#
+            )
+        };
+        let key = |name: &str, logic_code: i32, location_code: i32| {
+            format!(
+                r#"{{"KeyName": "{name}", "LogicCode": {logic_code}, "LocationCode": {location_code}, "Position": {{"Left": 0, "Top": 0, "Width": 1, "Height": 1}}}}"#
+            )
+        };
+
+        write(
+            &assets_path.join("device").join("models.json"),
+            r#"[{"modelID": 1, "name": "Test"}]"#,
+        );
+        write(
+            &data_path.join("keymap.json"),
+            &format!(
+                "[{}, {}, {}]",
+                key("A", 1, 1),
+                key("B", 2, 1),
+                key("X", 120, 3)
+            ),
+        );
+        write(
+            &data_path.join("profile.json"),
+            &profile(
+                r#"{"Index": 1, "DriverValue": "0x02000400"}, {"Index": 2, "DriverValue": "0x02000500"}"#,
+            ),
+        );
+        // Modifier combinations and macros are fine
+        write(
+            &data_path.join("profile_offline_1.json"),
+            &profile(concat!(
+                r#"{"Index": 1, "DriverValue": "0x02000708"}, {"Index": 2, "DriverValue": "0x0A010001"}, "#,
+                r#"{"Index": 3, "DriverValue": "0x0C000002"}, {"Index": 300, "DriverValue": "0x02000400"}"#
+            )),
+        );
+        write(
+            &data_path.join("profile_offline_2.json"),
+            &format!("\u{FEFF}{}", profile("")),
+        );
+        write(&data_path.join("profile_offline_3.json"), "{} }");
+
+        let issues = lint_assets(&assets_path)
+            .iter()
+            .map(Issue::to_string)
+            .collect::<Vec<_>>();
+        fs::remove_dir_all(&assets_path).unwrap();
+        assert_eq!(
+            issues,
+            [
+                "device/1/data/keymap.json: Couldn't find DriverValue for key 'X' logicCode: 120 locationCode: 3 modelId: 1 modelName: Test",
+                "device/1/data/keymap.json: locationCode 1 is used by more than one key: 'A', 'B'",
+                "device/1/data/profile_offline_1.json: KeySet index 300 is beyond the key buffer (size 121)",
+                "device/1/data/profile_offline_1.json: unknown DriverValue 0x0C000002 in KeySet at [3]",
+                "device/1/data/profile_offline_2.json: starts with a UTF-8 BOM",
+                "device/1/data/profile_offline_3.json: unexpected data: missing field `GUID`",
+                "device/1/data/profile_offline_3.json: trailing garbage after byte 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn key_buffer_covers_the_default_profile_and_the_keymap() {
+        let mut profile = Profile::default();
+        profile.key_set = Some(vec![
+            profile::KeySetEntry::new(1, 0x02000400),
+            profile::KeySetEntry::new(104, 0x02000500),
+        ]);
+        let keys = [Key {
+            logic_code: 110,
+            ..Key::new()
+        }];
+        assert_eq!(key_buffer_size(Some(&profile), None), Some(105));
+        assert_eq!(key_buffer_size(Some(&profile), Some(&keys)), Some(111));
+        assert_eq!(key_buffer_size(None, None), None);
+    }
+}
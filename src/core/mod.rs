@@ -1,11 +1,35 @@
+pub mod asset_diff;
+mod bom;
+mod consumer_usages;
+// The device side (crc16, keyboard, manager, modes, opcodes) isn't reachable from the CLI commands until the GUI
+// uses it. Elsewhere the items only the GUI uses are marked with `#[allow(dead_code)]`.
+#[allow(dead_code)]
 mod crc16;
+mod i18n;
+mod js_value;
+mod json_layout;
+mod key_action;
+mod key_selector;
 mod key_values;
+#[allow(dead_code)]
 mod keyboard;
+pub mod kle;
 mod layer;
+pub mod lint;
+#[allow(dead_code)]
 mod manager;
+pub mod model;
+#[allow(dead_code)]
 mod modes;
+#[allow(dead_code)]
 mod opcodes;
+pub mod presets;
+mod profile;
+pub mod qmk;
+pub mod sanity;
 mod state;
+mod variants;
+pub mod vendor_data;
 
-use layer::Layer;
+pub use layer::Layer;
 use state::State;
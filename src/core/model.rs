@@ -0,0 +1,436 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs,
+    path::Path,
+    sync::Arc,
+};
+
+use lazy_static::lazy_static;
+use num::FromPrimitive;
+use parking_lot::Mutex;
+use serde::Deserialize;
+
+use super::{
+    bom, i18n,
+    key_values::{self, DriverValue},
+    profile::Profile,
+    state::{self, Key},
+    variants::{DataVariants, DEFAULT_VARIANT},
+};
+
+const MODEL_LIST_PATH: &str = "assets/device/models.json";
+
+lazy_static! {
+    /// The entries of `models.json`, read the first time a model is requested
+    static ref MODELS: HashMap<u32, ModelInfo> = load_model_list();
+    /// Every model definition loaded so far, by model id and layout
+    static ref MODEL_CACHE: Mutex<HashMap<(u32, String), Arc<ModelDefinition>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// An entry of `models.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelInfo {
+    #[serde(rename = "modelID")]
+    pub model_id: u32,
+    #[serde(rename = "firmwareID")]
+    pub firmware_id: String,
+    pub name: String,
+    #[allow(dead_code)]
+    #[serde(rename = "LEType")]
+    pub le_type: String,
+}
+
+/// The data files of a model for a given layout. These never change once loaded, so they're shared between every
+/// connected keyboard of the same model (see [`ModelDefinition::get`]) and the per device data lives in `State`.
+#[derive(Debug, Clone, Default)]
+pub struct ModelDefinition {
+    pub model_id: u32,
+    pub firmware_id: u32,
+    pub model_name: String,
+    /// The name of the layout (keymap) variant the keys were loaded from
+    pub layout: String,
+    /// Alternative layouts / default profiles shipped for this model
+    pub variants: DataVariants,
+    /// The menus, key rows and Fn shortcuts of the model's `config.json`
+    pub config: ModelConfig,
+
+    keys_by_location_code: HashMap<i32, Key>,
+    keys_by_logic_code: HashMap<i32, Key>,
+    keys_by_driver_value: HashMap<u32, Key>,
+    /// A unique name for every key. Keys with duplicate DriverValue entries will be given seperate names here.
+    keys_by_driver_value_name: HashMap<String, Key>,
+}
+
+impl ModelDefinition {
+    /// Gets the model with its default layout, loading its files on first use
+    pub fn get(model_id: u32) -> Option<Arc<Self>> {
+        Self::get_with_layout(model_id, DEFAULT_VARIANT)
+    }
+
+    /// Gets the model with one of its layout variants, loading its files on first use
+    pub fn get_with_layout(model_id: u32, layout: &str) -> Option<Arc<Self>> {
+        let cache_key = (model_id, layout.to_string());
+        if let Some(model) = MODEL_CACHE.lock().get(&cache_key) {
+            return Some(model.clone());
+        }
+        // Loaded without holding the lock, if another thread got there first its model is kept
+        let model = Arc::new(Self::load(MODELS.get(&model_id)?, layout)?);
+        Some(MODEL_CACHE.lock().entry(cache_key).or_insert(model).clone())
+    }
+
+    /// All of the model ids listed in `models.json`
+    #[allow(dead_code)]
+    pub fn model_ids() -> Vec<u32> {
+        let mut model_ids = MODELS.keys().copied().collect::<Vec<_>>();
+        model_ids.sort_unstable();
+        model_ids
+    }
+
+    #[allow(dead_code)]
+    pub fn model_info(model_id: u32) -> Option<&'static ModelInfo> {
+        MODELS.get(&model_id)
+    }
+
+    /// The folder of a model's files (`config.json`, `data/`, see [`DataVariants::discover`])
+    pub fn path_for(model_id: u32) -> String {
+        format!("assets/device/{}", model_id)
+    }
+
+    pub fn data_path_for(model_id: u32) -> String {
+        format!("assets/device/{}/data", model_id)
+    }
+
+    pub fn config_path_for(model_id: u32) -> String {
+        format!("assets/device/{}/config.json", model_id)
+    }
+
+    pub fn path(&self) -> String {
+        Self::path_for(self.model_id)
+    }
+
+    pub fn data_path(&self) -> String {
+        Self::data_path_for(self.model_id)
+    }
+
+    fn load(info: &ModelInfo, layout: &str) -> Option<Self> {
+        let mut model = Self {
+            model_id: info.model_id,
+            firmware_id: u32::from_str_radix(info.firmware_id.trim_start_matches("0x"), 16)
+                .unwrap_or_default(),
+            model_name: info.name.clone(),
+            layout: layout.to_string(),
+            variants: DataVariants::discover(Self::path_for(info.model_id)),
+            config: ModelConfig::load(info.model_id),
+            ..Default::default()
+        };
+
+        // The vendor data only ships `keymap*.json` files (JS style keymaps are detected by their content in
+        // `load_keys`), there's no `keymap.js` in assets/device
+        let keys_path = match model.variants.layout(layout) {
+            Some(variant) => variant.path.clone(),
+            None if layout == DEFAULT_VARIANT => "data/keymap.json".to_string(),
+            None => return None,
+        };
+        if let Err(err) = model.load_keys(&keys_path) {
+            log::warn!("{err}");
+        }
+        Some(model)
+    }
+
+    /// The driver values of the default profile in `folder` (relative to the model's folder)
+    fn get_default_profile_driver_values(&self, folder: &str) -> Option<Vec<u32>> {
+        let profile_path = format!("{}/{}/profile.json", self.path(), folder);
+        if Path::new(&profile_path).exists() {
+            let model_data = Profile::from_file(profile_path).ok()?;
+            let mut result = vec![key_values::UNUSED_KEY_VALUE; i16::MAX as usize];
+            if let Some(ref entries) = model_data.key_set {
+                state::setup_driver_key_set_buffer(entries, &mut result);
+            }
+            return Some(result);
+        }
+        None
+    }
+
+    /// Loads the keys of a keymap, `keys_path` is relative to the model's folder ("data/keymap.json")
+    fn load_keys(&mut self, keys_path: &str) -> Result<(), String> {
+        // A keymap goes with the default profile next to it
+        let folder = keys_path.rsplit_once('/').map_or("", |(folder, _)| folder);
+        let driver_values = self
+            .get_default_profile_driver_values(folder)
+            .unwrap_or_else(|| vec![key_values::UNUSED_KEY_VALUE; i16::MAX as usize]);
+
+        let keys_path = format!("{}/{}", self.path(), keys_path);
+        if !Path::new(&keys_path).exists() {
+            return Ok(());
+        }
+
+        let text = fs::read_to_string(&keys_path)
+            .map_err(|err| format!("Failed to read keymap `{keys_path}`: {err}"))?;
+        if text.replace(' ', "").contains("KeyName:") {
+            log::debug!("TODO: Handle keymap which is declared as JS `{keys_path}`");
+            return Ok(());
+        }
+
+        let mut device_keys = serde_json::from_str::<Vec<Key>>(&text)
+            .map_err(|err| format!("Failed to load keymap `{keys_path}`: {err}"))?;
+        for key in device_keys.iter_mut() {
+            let default_driver_value = usize::try_from(key.logic_code)
+                .ok()
+                .and_then(|logic_code| driver_values.get(logic_code))
+                .copied()
+                .filter(|driver_value| *driver_value != key_values::UNUSED_KEY_VALUE);
+            if let Some(driver_value) = default_driver_value {
+                key.driver_value = driver_value;
+            } else {
+                if key.logic_code > 0 {
+                    if let Some(all_keys_key) =
+                        key_values::CATALOG.find_by_logic_code(key.logic_code)
+                    {
+                        key.driver_value = all_keys_key.driver_value;
+                    } else {
+                        log::debug!("Couldn't find DriverValue for key `{}` logicCode: {} locationCode: {} modelId: {} modelName: {}", key.key_name, key.logic_code, key.location_code, self.model_id, self.model_name);
+                    }
+                } else {
+                    key.driver_value = key_values::UNUSED_KEY_VALUE;
+                }
+            }
+
+            self.keys_by_location_code
+                .insert(key.location_code, key.clone());
+            self.keys_by_logic_code.insert(key.logic_code, key.clone());
+            self.keys_by_driver_value
+                .insert(key.driver_value, key.clone());
+
+            let base_name = match DriverValue::from_u32(key.driver_value) {
+                Some(driver_value) => driver_value.to_string(),
+                None => format!("0x{:08X}", key.driver_value),
+            };
+            for i in 1..i32::MAX {
+                let driver_value_name = format!(
+                    "{}{}",
+                    base_name,
+                    if i > 1 {
+                        format!("_{}", i)
+                    } else {
+                        "".to_string()
+                    }
+                );
+                if let Entry::Vacant(entry) =
+                    self.keys_by_driver_value_name.entry(driver_value_name)
+                {
+                    key.driver_value_name = entry.key().clone();
+                    entry.insert(key.clone());
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get_key_at_location_code(&self, location_code: i32) -> Option<&Key> {
+        self.keys_by_location_code.get(&location_code)
+    }
+
+    pub fn get_key_by_logic_code(&self, logic_code: i32) -> Option<&Key> {
+        self.keys_by_logic_code.get(&logic_code)
+    }
+
+    #[allow(dead_code)]
+    pub fn get_key_by_driver_value(&self, driver_value: u32) -> Option<&Key> {
+        self.keys_by_driver_value.get(&driver_value)
+    }
+
+    pub fn get_key_by_driver_value_name(&self, driver_value_name: &str) -> Option<&Key> {
+        self.keys_by_driver_value_name.get(driver_value_name)
+    }
+
+    /// Finds a key by its `KeyName` ("Escape"), ignoring case
+    pub fn get_key_by_name(&self, name: &str) -> Option<&Key> {
+        self.keys()
+            .filter(|key| key.key_name.eq_ignore_ascii_case(name))
+            .min_by_key(|key| key.logic_code)
+    }
+
+    /// All of the keys of the layout, in no particular order
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.keys_by_driver_value_name.values()
+    }
+
+    /// The keys of the layout by row (top to bottom, then left to right). A key starts a new row once its top is
+    /// below the middle of the first key of the current row.
+    pub fn layout_rows(&self) -> Vec<Vec<&Key>> {
+        let mut keys = self.keys().collect::<Vec<_>>();
+        keys.sort_by_key(|key| (key.position.top, key.position.left, key.logic_code));
+
+        let mut rows: Vec<Vec<&Key>> = vec![];
+        for key in keys {
+            match rows.last_mut() {
+                Some(row)
+                    if key.position.top < row[0].position.top + row[0].position.height / 2 =>
+                {
+                    row.push(key)
+                }
+                _ => rows.push(vec![key]),
+            }
+        }
+        for row in &mut rows {
+            row.sort_by_key(|key| (key.position.left, key.logic_code));
+        }
+        rows
+    }
+
+    /// The size of a 1u key including the gap to its neighbours (in pixels, horizontally and vertically), which is
+    /// the median distance between neighbouring keys of a row and between rows
+    pub fn layout_units(&self) -> (f64, f64) {
+        let rows = self.layout_rows();
+        let median = |mut distances: Vec<i32>, fallback: Option<i32>| {
+            distances.retain(|&distance| distance > 0);
+            distances.sort_unstable();
+            distances
+                .get(distances.len() / 2)
+                .copied()
+                .or(fallback)
+                .unwrap_or(1)
+                .max(1) as f64
+        };
+        let horizontal = median(
+            rows.iter()
+                .flat_map(|row| row.windows(2))
+                .map(|pair| pair[1].position.left - pair[0].position.left)
+                .collect(),
+            rows.first().map(|row| row[0].position.width),
+        );
+        let tops = rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|key| key.position.top)
+                    .min()
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>();
+        let vertical = median(
+            tops.windows(2).map(|pair| pair[1] - pair[0]).collect(),
+            rows.first().map(|row| row[0].position.height),
+        );
+        (horizontal, vertical)
+    }
+}
+
+/// The parts of a model's `config.json` which are shown to the user
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelConfig {
+    /// The pages of the vendor software which apply to the model
+    #[allow(dead_code)]
+    #[serde(rename = "FuncTable", default)]
+    pub menus: Vec<MenuEntry>,
+    /// Rows of keys which can be selected at once
+    #[serde(rename = "QuickSelect", default)]
+    pub quick_select: Vec<QuickSelectRow>,
+    /// The Fn shortcuts built into the firmware
+    #[serde(rename = "UserManual", default)]
+    pub user_manual: Vec<UserManualSection>,
+}
+
+impl ModelConfig {
+    /// Loads the config of a model, empty if it doesn't have one
+    pub fn load(model_id: u32) -> Self {
+        let path = ModelDefinition::config_path_for(model_id);
+        let Ok(bytes) = fs::read(&path) else {
+            return Self::default();
+        };
+        let bytes = bom::strip_bom(&bytes).unwrap_or(&bytes);
+        serde_json::from_slice(bytes).unwrap_or_else(|err| {
+            log::warn!("Failed to load model config `{path}`: {err}");
+            Self::default()
+        })
+    }
+}
+
+/// A page of the vendor software (older configs use `Func` / `Name`)
+#[allow(dead_code)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct MenuEntry {
+    #[serde(alias = "Name")]
+    pub name: String,
+    pub lang: Option<String>,
+    #[serde(alias = "Func", default)]
+    pub component: String,
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+}
+
+impl MenuEntry {
+    #[allow(dead_code)]
+    pub fn localized_name(&self) -> String {
+        localize(self.lang.as_deref(), &self.name)
+    }
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuickSelectRow {
+    pub title: String,
+    pub lang: Option<String>,
+    /// Location codes, despite the name (`lcodes`)
+    #[serde(rename = "lcodes", default)]
+    pub location_codes: Vec<i32>,
+}
+
+impl QuickSelectRow {
+    #[allow(dead_code)]
+    pub fn localized_title(&self) -> String {
+        localize(self.lang.as_deref(), &self.title)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserManualSection {
+    pub title_lang: String,
+    #[serde(default)]
+    pub items: Vec<UserManualItem>,
+}
+
+impl UserManualSection {
+    #[allow(dead_code)]
+    pub fn localized_title(&self) -> String {
+        i18n::translate_name(&self.title_lang)
+    }
+}
+
+/// A Fn shortcut ("Fn+W" switches to the first onboard layer)
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserManualItem {
+    pub key: String,
+    pub desc_lang: String,
+}
+
+impl UserManualItem {
+    pub fn description(&self) -> String {
+        i18n::translate_name(&self.desc_lang)
+    }
+}
+
+/// The text of a language key, otherwise the (possibly Chinese) name it was given
+fn localize(lang: Option<&str>, name: &str) -> String {
+    lang.and_then(i18n::tr)
+        .unwrap_or_else(|| i18n::translate_name(name))
+}
+
+fn load_model_list() -> HashMap<u32, ModelInfo> {
+    let models = fs::read(MODEL_LIST_PATH)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<Vec<ModelInfo>>(&bytes).ok())
+        .unwrap_or_else(|| {
+            log::warn!("Failed to load the model list `{MODEL_LIST_PATH}`");
+            Vec::new()
+        });
+    models
+        .into_iter()
+        .map(|model| (model.model_id, model))
+        .collect()
+}
@@ -1,5 +1,7 @@
 // TODO: When there is better lighting / macro support, put these in the appropriate files
 
+use super::key_values::{self, DriverValueMouseButton, DriverValueType};
+
 pub enum LightingEffectType {
     /// Static "DIY" lighting with an RGB value for each key
     Static = 0,
@@ -11,7 +13,7 @@ pub enum LightingEffectColorType {
     /// Single solid color
     Monochrome = 0,
     /// Color which changes through the color spectrum
-    RGB = 1,
+    Rgb = 1,
     /// Color which changes through the color spectrum, and visually "breathes"
     Breathing = 2,
 }
@@ -29,12 +31,70 @@ pub enum MacroRepeatType {
 }
 
 /// This is different compared to DriverValue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MacroKeyType {
     Key = 1,
     Mouse = 2,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MacroKeyState {
     Down = 1,
     Up = 2,
 }
+
+/// A single key / mouse button event of a macro
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MacroKeyEvent {
+    pub key_type: MacroKeyType,
+    pub state: MacroKeyState,
+    /// The short driver value for keys (0x29 for Esc), the button flags for the mouse
+    pub key_code: u8,
+    /// How long to wait after the event (milliseconds)
+    pub delay: u16,
+}
+
+impl MacroKeyEvent {
+    /// Creates a key event from a full driver value. Only plain keys / modifiers can be used in macros.
+    pub fn key(driver_value: u32, state: MacroKeyState, delay: u16) -> Option<Self> {
+        Some(Self {
+            key_type: MacroKeyType::Key,
+            state,
+            key_code: key_values::short_driver_value(driver_value)?,
+            delay,
+        })
+    }
+
+    pub fn mouse(button: DriverValueMouseButton, state: MacroKeyState, delay: u16) -> Self {
+        Self {
+            key_type: MacroKeyType::Mouse,
+            state,
+            key_code: button.bits(),
+            delay,
+        }
+    }
+
+    /// The full driver value of the key / mouse button
+    pub fn driver_value(&self) -> Option<u32> {
+        match self.key_type {
+            MacroKeyType::Key => key_values::long_driver_value(self.key_code),
+            MacroKeyType::Mouse => {
+                Some((DriverValueType::Mouse as u32) << 16 | self.key_code as u32)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::key_values::DriverValue;
+
+    #[test]
+    fn macro_key_events_use_short_values() {
+        let event = MacroKeyEvent::key(DriverValue::Esc as u32, MacroKeyState::Down, 10).unwrap();
+        assert_eq!(event.key_code, 0x29);
+        assert_eq!(event.driver_value(), Some(DriverValue::Esc as u32));
+        assert!(MacroKeyEvent::key(0x02000601, MacroKeyState::Down, 10).is_none());
+    }
+}
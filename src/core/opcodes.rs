@@ -1,3 +1,4 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OpCodes {
     /// Information about the keyboard
     Info = 0x01,
@@ -23,8 +24,8 @@ pub enum OpCodes {
     /// Resets a type of data (keys, lights, etc) for a layer
     LayerResetDataType = 0x21,
     LayerSetKeyValues = 0x22,
-    Unk23KbData = 0x23, // Likely a keyboard data set (see KeyboardLayerDataType)
-    Unk24KbDataLighting = 0x24, // Some lighting related data (see KeyboardLayerDataType)
+    Unk23KbData = 0x23, // Likely a keyboard data set (see LayerDataType)
+    Unk24KbDataLighting = 0x24, // Some lighting related data (see LayerDataType)
     LayerSetMacros = 0x25,
     /// Sets the lighting effects which should play when pressing keys ("Press Light")
     LayerSetKeyPressLightingEffect = 0x26,
@@ -32,3 +33,24 @@ pub enum OpCodes {
     /// Function key values
     LayerFnSetKeyValues = 0x31,
 }
+
+/// The data types of a layer, see [`OpCodes::LayerResetDataType`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayerDataType {
+    KeySet = 0x01,
+    Lighting = 0x02,
+    Macros = 0x03,
+    /// See [`OpCodes::LayerSetKeyPressLightingEffect`]
+    KeyPressLighting = 0x04,
+    FnKeySet = 0x06,
+}
+
+impl LayerDataType {
+    pub const ALL: [LayerDataType; 5] = [
+        LayerDataType::KeySet,
+        LayerDataType::Lighting,
+        LayerDataType::Macros,
+        LayerDataType::KeyPressLighting,
+        LayerDataType::FnKeySet,
+    ];
+}
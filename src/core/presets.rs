@@ -0,0 +1,317 @@
+use std::fmt;
+
+use super::{
+    key_values::DriverValue,
+    state::{KeyAddress, KeyChange, LayerEditError, State, MAX_KEY_SET_SIZE},
+    Layer,
+};
+
+/// A built-in remap. Keys are found by their default value (their driver value name, see
+/// [`Key::driver_value_name`](super::state::Key::driver_value_name)) so a preset works on any board which has the
+/// keys, whatever their logic codes are. Keys a board doesn't have are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// The default value of a key and the value to bind to it
+    pub remaps: &'static [(DriverValue, DriverValue)],
+}
+
+use DriverValue::*;
+
+pub const PRESETS: &[Preset] = &[
+    Preset {
+        name: "dvorak",
+        description: "US Dvorak",
+        remaps: &[
+            (Subtract, OpenSquareBrace),
+            (Add, CloseSquareBrace),
+            (Q, Quotes),
+            (W, Comma),
+            (E, Period),
+            (R, P),
+            (T, Y),
+            (Y, F),
+            (U, G),
+            (I, C),
+            (O, R),
+            (P, L),
+            (OpenSquareBrace, Slash),
+            (CloseSquareBrace, Add),
+            (S, O),
+            (D, E),
+            (F, U),
+            (G, I),
+            (H, D),
+            (J, H),
+            (K, T),
+            (L, N),
+            (Semicolon, S),
+            (Quotes, Subtract),
+            (Z, Semicolon),
+            (X, Q),
+            (C, J),
+            (V, K),
+            (B, X),
+            (N, B),
+            (Comma, W),
+            (Period, V),
+            (Slash, Z),
+        ],
+    },
+    Preset {
+        name: "colemak",
+        description: "Colemak (Caps Lock is left alone, see caps-as-ctrl)",
+        remaps: &[
+            (E, F),
+            (R, P),
+            (T, G),
+            (Y, J),
+            (U, L),
+            (I, U),
+            (O, Y),
+            (P, Semicolon),
+            (S, R),
+            (D, S),
+            (F, T),
+            (G, D),
+            (J, N),
+            (K, E),
+            (L, I),
+            (Semicolon, O),
+            (N, K),
+        ],
+    },
+    Preset {
+        name: "colemak-dh",
+        description: "Colemak Mod-DH for row staggered ANSI boards (with the angle mod)",
+        remaps: &[
+            (E, F),
+            (R, P),
+            (T, B),
+            (Y, J),
+            (U, L),
+            (I, U),
+            (O, Y),
+            (P, Semicolon),
+            (S, R),
+            (D, S),
+            (F, T),
+            (H, M),
+            (J, N),
+            (K, E),
+            (L, I),
+            (Semicolon, O),
+            (Z, X),
+            (X, C),
+            (C, D),
+            (B, Z),
+            (N, K),
+            (M, H),
+        ],
+    },
+    Preset {
+        name: "workman",
+        description: "Workman",
+        remaps: &[
+            (W, D),
+            (E, R),
+            (R, W),
+            (T, B),
+            (Y, J),
+            (U, F),
+            (I, U),
+            (O, P),
+            (P, Semicolon),
+            (D, H),
+            (F, T),
+            (H, Y),
+            (J, N),
+            (K, E),
+            (L, O),
+            (Semicolon, I),
+            (C, M),
+            (V, C),
+            (B, V),
+            (N, K),
+            (M, L),
+        ],
+    },
+    Preset {
+        name: "mac-modifiers",
+        description: "Swaps Alt and Win so Option / Command sit where a Mac keyboard has them",
+        remaps: &[(LAlt, LWin), (LWin, LAlt), (RAlt, RWin), (RWin, RAlt)],
+    },
+    Preset {
+        name: "caps-as-ctrl",
+        description: "Caps Lock is a left Ctrl",
+        remaps: &[(CapsLock, LCtrl)],
+    },
+    Preset {
+        name: "wasd-arrows",
+        description: "Swaps WASD and the arrows",
+        remaps: &[
+            (W, Up),
+            (A, Left),
+            (S, Down),
+            (D, Right),
+            (Up, W),
+            (Left, A),
+            (Down, S),
+            (Right, D),
+        ],
+    },
+    Preset {
+        name: "numpad",
+        description: "A numpad under the right hand (7 8 9 / U I O / J K L / M), meant for a layer or the Fn keys",
+        remaps: &[
+            (Subtract, NumLock),
+            (D7, NumPad7),
+            (D8, NumPad8),
+            (D9, NumPad9),
+            (D0, NumPadSlash),
+            (U, NumPad4),
+            (I, NumPad5),
+            (O, NumPad6),
+            (P, NumPadAsterisk),
+            (J, NumPad1),
+            (K, NumPad2),
+            (L, NumPad3),
+            (Semicolon, NumPadSubtract),
+            (M, NumPad0),
+            (Period, NumPadPeriod),
+            (Slash, NumPadAdd),
+            (Enter, NumPadEnter),
+        ],
+    },
+];
+
+impl Preset {
+    /// Finds a preset by name, ignoring case
+    pub fn find(name: &str) -> Option<&'static Preset> {
+        PRESETS
+            .iter()
+            .find(|preset| preset.name.eq_ignore_ascii_case(name.trim()))
+    }
+}
+
+/// The result of [`apply`]
+#[derive(Debug, Clone, Default)]
+pub struct PresetReport {
+    pub changes: Vec<KeyChange>,
+    /// [`PresetReport::changes`] formatted by [`State::describe_change`]
+    pub summary: Vec<String>,
+    pub skipped: Vec<SkippedRemap>,
+}
+
+/// A remap of a preset which wasn't applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedRemap {
+    pub preset: &'static str,
+    pub key: DriverValue,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for SkippedRemap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} ({})", self.preset, self.key, self.reason)
+    }
+}
+
+/// A remap of a composed preset (see [`compose`]): the preset's name, the default value of the key and the value to
+/// bind to it
+pub type Remap = (&'static str, DriverValue, DriverValue);
+
+/// Merges presets into a single remap. A later preset wins for the keys both remap, so `dvorak` followed by
+/// `wasd-arrows` puts the arrows on the Dvorak layout's WASD keys. The overridden remaps are returned as skipped.
+pub fn compose(presets: &[&Preset]) -> (Vec<Remap>, Vec<SkippedRemap>) {
+    let mut remaps: Vec<Remap> = vec![];
+    let mut skipped = vec![];
+    for preset in presets {
+        for &(key, value) in preset.remaps {
+            if let Some(index) = remaps.iter().position(|&(_, other, _)| other == key) {
+                let (overridden, _, _) = remaps.remove(index);
+                skipped.push(SkippedRemap {
+                    preset: overridden,
+                    key,
+                    reason: "overridden by a later preset",
+                });
+            }
+            remaps.push((preset.name, key, value));
+        }
+    }
+    (remaps, skipped)
+}
+
+/// Applies presets (see [`compose`]) to the keys or Fn keys of a layer. Every key whose default value is remapped
+/// is rebound, including duplicates (`LShift_2`). Keys the model doesn't have are reported as skipped.
+pub fn apply(
+    state: &mut State,
+    layer: Layer,
+    fn_layer: bool,
+    presets: &[&Preset],
+) -> Result<PresetReport, LayerEditError> {
+    if state.get_layer(layer).is_none() {
+        return Err(LayerEditError::NoLayer(layer));
+    }
+    let (remaps, skipped) = compose(presets);
+    let mut report = PresetReport {
+        skipped,
+        ..Default::default()
+    };
+
+    // Keys are found on the model by their default value, not by what's bound to them, so the order doesn't matter
+    // and swaps (`LAlt` <-> `LWin`) work
+    for (preset, key, value) in remaps {
+        let logic_codes = logic_codes(state, key);
+        if logic_codes.is_empty() {
+            report.skipped.push(SkippedRemap {
+                preset,
+                key,
+                reason: "the model doesn't have the key",
+            });
+        }
+        for logic_code in logic_codes {
+            report.changes.extend(state.bind_key(
+                layer,
+                fn_layer,
+                &KeyAddress::LogicCode(logic_code),
+                value as u32,
+            )?);
+        }
+    }
+    report.summary = report
+        .changes
+        .iter()
+        .map(|change| state.describe_change(change))
+        .collect();
+    Ok(report)
+}
+
+/// Applies presets to a layer of a model's factory default profiles, see [`apply`]
+pub fn apply_to_factory_default(
+    model_id: u32,
+    layer: Layer,
+    fn_layer: bool,
+    presets: &[&Preset],
+) -> Result<PresetReport, String> {
+    let mut state =
+        State::get_keyboard_state(model_id).ok_or_else(|| format!("unknown model {model_id}"))?;
+    state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+    apply(&mut state, layer, fn_layer, presets).map_err(|err| err.to_string())
+}
+
+/// The logic codes of every key of the model whose default value is `key` (`Q`, `Q_2`, ...)
+fn logic_codes(state: &State, key: DriverValue) -> Vec<i32> {
+    let model = state.model();
+    let size = state.key_buffer_size() as i32;
+    (1..)
+        .map(|index| match index {
+            1 => key.to_string(),
+            _ => format!("{key}_{index}"),
+        })
+        .map_while(|name| model.get_key_by_driver_value_name(&name))
+        .map(|key| key.logic_code)
+        .filter(|logic_code| (0..size).contains(logic_code))
+        .collect()
+}
@@ -0,0 +1,491 @@
+use std::{borrow::Cow, fs, path::Path, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{
+    bom, i18n,
+    json_layout::{self, Indent, Node, Template},
+    key_selector::KeySelector,
+};
+
+/// A vendor profile (`profile*.json`) describing the key sets and lighting of one layer.
+///
+/// Fields are declared in the order the vendor software writes them, and anything we don't model is kept in
+/// `extra` so that a profile can be read, edited and written back without losing data.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    #[serde(rename = "GUID")]
+    pub guid: String,
+    #[serde(rename = "ModeIndex")]
+    pub mode_index: i32,
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "Active")]
+    pub active: i32,
+    #[serde(rename = "ModelID", default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<u32>,
+    #[serde(rename = "SystemOS", default, skip_serializing_if = "Option::is_none")]
+    pub system_os: Option<String>,
+    #[serde(
+        rename = "Application",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub application: Option<Application>,
+    #[serde(rename = "Game", default, skip_serializing_if = "Option::is_none")]
+    pub game: Option<NamedGuid>,
+    #[serde(rename = "ModeLE", default, skip_serializing_if = "Option::is_none")]
+    pub mode_le: Option<ModeLe>,
+    #[serde(rename = "DeviceLE", default, skip_serializing_if = "Option::is_none")]
+    pub device_le: Option<DeviceLe>,
+    #[serde(rename = "DriverLE", default, skip_serializing_if = "Option::is_none")]
+    pub driver_le: Option<Vec<NamedGuid>>,
+    #[serde(rename = "KeySet", default, skip_serializing_if = "Option::is_none")]
+    pub key_set: Option<Vec<KeySetEntry>>,
+    #[serde(rename = "FnKeySet", default, skip_serializing_if = "Option::is_none")]
+    pub fn_key_set: Option<Vec<KeySetEntry>>,
+
+    /// Fields which aren't modeled above (mouse DPI settings, `HotKeySet`, etc)
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+
+    #[serde(skip)]
+    layout: FileLayout,
+}
+
+/// Byte level details of the file a profile was read from, so they can be reproduced when writing it back
+#[derive(Debug, Clone, Default)]
+struct FileLayout {
+    bom: bool,
+    trailing_newline: bool,
+    /// The file isn't valid UTF-8 (a few vendor files have GBK encoded names), see [`RAW_BYTE_BASE`]
+    raw_bytes: bool,
+    /// The text of the file (without the BOM), its key order and whitespace are kept when writing the profile back
+    source: Option<Arc<str>>,
+}
+
+/// The source text is only a formatting template, profiles read from differently formatted files are still equal
+impl PartialEq for FileLayout {
+    fn eq(&self, other: &Self) -> bool {
+        self.bom == other.bom
+            && self.trailing_newline == other.trailing_newline
+            && self.raw_bytes == other.raw_bytes
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Application {
+    #[serde(rename = "AppName")]
+    pub app_name: String,
+    #[serde(rename = "AppPath")]
+    pub app_path: String,
+}
+
+/// A reference to a lighting effect / game by GUID (`Game`, `DriverLE` and `KeyLE` entries)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NamedGuid {
+    #[serde(rename = "GUID")]
+    pub guid: String,
+    #[serde(rename = "Name")]
+    pub name: String,
+}
+
+impl NamedGuid {
+    /// The localized name of the lighting effect / game
+    #[allow(dead_code)]
+    pub fn display_name(&self) -> String {
+        i18n::translate_name(&self.name)
+    }
+}
+
+/// The lighting effect which is active for the layer
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModeLe {
+    #[serde(rename = "GUID")]
+    pub guid: String,
+    #[serde(rename = "Name")]
+    pub name: String,
+    #[serde(rename = "LESet", default, skip_serializing_if = "Option::is_none")]
+    pub le_set: Option<LeSet>,
+    #[serde(rename = "LEData", default, skip_serializing_if = "Option::is_none")]
+    pub le_data: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl ModeLe {
+    /// The localized name of the lighting effect ("光谱循环" => "Spectrum Cycle")
+    #[allow(dead_code)]
+    pub fn display_name(&self) -> String {
+        i18n::translate_name(&self.name)
+    }
+}
+
+/// The built in lighting effects of the device
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeviceLe {
+    #[serde(rename = "Index")]
+    pub index: i32,
+    #[serde(rename = "LESet")]
+    pub le_set: Vec<LeSet>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LeSet {
+    #[serde(rename = "LEConfig", default, skip_serializing_if = "Option::is_none")]
+    pub le_config: Option<i32>,
+    #[serde(rename = "LEModel")]
+    pub le_model: i32,
+    #[serde(rename = "LESubModel")]
+    pub le_sub_model: i32,
+    #[serde(rename = "LELight")]
+    pub le_light: i32,
+    #[serde(rename = "LESpeed", default, skip_serializing_if = "Option::is_none")]
+    pub le_speed: Option<i32>,
+    #[serde(rename = "LEDir", default, skip_serializing_if = "Option::is_none")]
+    pub le_dir: Option<i32>,
+    #[serde(rename = "LEColor")]
+    pub le_color: i32,
+    #[serde(rename = "LEEnable")]
+    pub le_enable: i32,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A single key binding in `KeySet` / `FnKeySet`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeySetEntry {
+    /// The logic code of the key
+    #[serde(rename = "Index")]
+    pub index: i32,
+    /// Host only, binds several keys at once instead of `Index` ("All", "AllExceptModifiers", "QuickSelect(0)"), see
+    /// [`KeySetEntry::key_selector`]
+    #[serde(rename = "Selector", default, skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
+    /// Usually a string, but some vendor files store a number here
+    #[serde(rename = "MenuPID", default, skip_serializing_if = "Option::is_none")]
+    pub menu_pid: Option<Value>,
+    #[serde(rename = "MenuID", default, skip_serializing_if = "Option::is_none")]
+    pub menu_id: Option<Value>,
+    #[serde(rename = "MenuName", default, skip_serializing_if = "Option::is_none")]
+    pub menu_name: Option<String>,
+    /// Kept as written (e.g. "0x02000001" / "0x0a070001"), see [`KeySetEntry::driver_value`]
+    #[serde(rename = "DriverValue")]
+    pub driver_value: String,
+    #[serde(rename = "Task", default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<Task>,
+    #[serde(rename = "KeyLE", default, skip_serializing_if = "Option::is_none")]
+    pub key_le: Option<NamedGuid>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// An action bound to a key which is run by the host software (open a URL / app, run a macro)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Task {
+    #[serde(rename = "Type")]
+    pub task_type: String,
+    #[serde(rename = "Data")]
+    pub data: TaskData,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskData {
+    #[serde(rename = "Type", default, skip_serializing_if = "Option::is_none")]
+    pub data_type: Option<String>,
+    #[serde(rename = "AppPath", default, skip_serializing_if = "Option::is_none")]
+    pub app_path: Option<String>,
+    #[serde(rename = "GUID", default, skip_serializing_if = "Option::is_none")]
+    pub guid: Option<String>,
+    #[serde(rename = "Repeats", default, skip_serializing_if = "Option::is_none")]
+    pub repeats: Option<i32>,
+    #[serde(rename = "StopMode", default, skip_serializing_if = "Option::is_none")]
+    pub stop_mode: Option<i32>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Profile {
+    pub fn from_slice(bytes: &[u8]) -> serde_json::Result<Self> {
+        let (bom, bytes) = match bom::strip_bom(bytes) {
+            Some(bytes) => (true, bytes),
+            None => (false, bytes),
+        };
+        let text = decode_raw_bytes(bytes);
+        let mut profile = serde_json::from_str::<Self>(&text)?;
+        profile.layout = FileLayout {
+            bom,
+            trailing_newline: bytes.ends_with(b"\n"),
+            raw_bytes: matches!(text, Cow::Owned(_)),
+            source: Some(Arc::from(text)),
+        };
+        Ok(profile)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> serde_json::Result<Self> {
+        let bytes = fs::read(path).map_err(serde_json::Error::io)?;
+        Self::from_slice(&bytes)
+    }
+
+    /// Serializes the profile the same way the vendor software formats it. A profile read from a file is written
+    /// with the file's key order and whitespace, so an unedited profile is written back byte for byte.
+    pub fn to_vec(&self) -> serde_json::Result<Vec<u8>> {
+        let mut writer = Vec::new();
+        if self.layout.bom {
+            writer.extend_from_slice(bom::UTF8_BOM);
+        }
+        let text = serde_json::to_string(self)?;
+        let node = Node::parse(&text).expect("serde_json writes valid JSON");
+        let source = self.layout.source.as_deref();
+        match source.and_then(|source| Some((source, Node::parse(source)?))) {
+            Some((source, template)) => {
+                writer.extend_from_slice(&source.as_bytes()[..template.start]);
+                let indent = Indent::detect(source);
+                json_layout::render(
+                    &mut writer,
+                    &text,
+                    &node,
+                    Template::new(source, &template),
+                    indent,
+                );
+                writer.extend_from_slice(&source.as_bytes()[template.end..]);
+            }
+            None => {
+                json_layout::write_formatted(&mut writer, &text, &node, Indent::TABS);
+                if self.layout.trailing_newline {
+                    writer.push(b'\n');
+                }
+            }
+        }
+        if self.layout.raw_bytes {
+            writer = encode_raw_bytes(
+                &String::from_utf8(writer).expect("the profile is written as UTF-8"),
+            );
+        }
+        Ok(writer)
+    }
+
+    #[allow(dead_code)]
+    pub fn save(&self, path: impl AsRef<Path>) -> serde_json::Result<()> {
+        fs::write(path, self.to_vec()?).map_err(serde_json::Error::io)
+    }
+}
+
+impl KeySetEntry {
+    pub fn new(index: i32, driver_value: u32) -> Self {
+        Self {
+            index,
+            menu_pid: Some(Value::String(String::new())),
+            menu_id: Some(Value::String(String::new())),
+            menu_name: Some(String::new()),
+            driver_value: format_driver_value(driver_value),
+            ..Default::default()
+        }
+    }
+
+    /// An entry which binds every key of a selector, expanded when the profile is loaded into a layer
+    #[allow(dead_code)]
+    pub fn with_selector(selector: KeySelector, driver_value: u32) -> Self {
+        Self {
+            index: -1,
+            selector: Some(selector.to_string()),
+            ..Self::new(-1, driver_value)
+        }
+    }
+
+    /// Parses the `Selector`, `Ok(None)` if the entry is for a single key
+    pub fn key_selector(&self) -> Result<Option<KeySelector>, String> {
+        self.selector.as_deref().map(str::parse).transpose()
+    }
+
+    /// Parses the hex `DriverValue` string ("0x02002900")
+    pub fn driver_value(&self) -> Option<u32> {
+        parse_driver_value(&self.driver_value)
+    }
+
+    #[allow(dead_code)]
+    pub fn set_driver_value(&mut self, driver_value: u32) {
+        self.driver_value = format_driver_value(driver_value);
+    }
+}
+
+/// A file which isn't UTF-8 is in some other (unknown) encoding, its non ASCII bytes are read as
+/// `RAW_BYTE_BASE + byte` (private use characters) so it can still be read and is written back unchanged. The non
+/// ASCII text shows up as placeholders.
+const RAW_BYTE_BASE: u32 = 0xF700;
+
+fn decode_raw_bytes(bytes: &[u8]) -> Cow<'_, str> {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(
+            bytes
+                .iter()
+                .map(|byte| match byte {
+                    0..=0x7F => *byte as char,
+                    _ => char::from_u32(RAW_BYTE_BASE + *byte as u32).unwrap(),
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn encode_raw_bytes(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for c in text.chars() {
+        match (c as u32).checked_sub(RAW_BYTE_BASE) {
+            Some(byte @ 0..=0xFF) => bytes.push(byte as u8),
+            _ => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    bytes
+}
+
+pub fn parse_driver_value(text: &str) -> Option<u32> {
+    let text = text.trim();
+    let hex = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u32::from_str_radix(hex, 16).ok()
+}
+
+pub fn format_driver_value(driver_value: u32) -> String {
+    format!("0x{:08X}", driver_value)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const PROFILE: &str = "assets/device/655491085/data/profile.json";
+
+    fn vendor_profiles(dir: impl AsRef<Path>) -> Vec<PathBuf> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let name = path.file_name().unwrap().to_string_lossy();
+            if path.is_dir() {
+                paths.extend(vendor_profiles(&path));
+            } else if name.starts_with("profile") && name.ends_with(".json") {
+                paths.push(path);
+            }
+        }
+        paths
+    }
+
+    #[test]
+    fn vendor_profiles_are_written_back_unchanged() {
+        let mut read = 0;
+        for path in vendor_profiles("assets/device") {
+            let bytes = fs::read(&path).unwrap();
+            // A few vendor files have GBK encoded names, which are kept as raw bytes
+            let profile = Profile::from_slice(&bytes)
+                .unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+            read += 1;
+            assert!(
+                profile.to_vec().unwrap() == bytes,
+                "{} isn't written back as it was read",
+                path.display()
+            );
+        }
+        assert!(read > 1000, "only {read} profiles could be read");
+    }
+
+    #[test]
+    fn gbk_names_are_kept_as_raw_bytes() {
+        let bytes = fs::read("assets/device/655491165/data/profile_offline_2.json").unwrap();
+        let mut profile = Profile::from_slice(&bytes).unwrap();
+        let name = &profile.mode_le.as_ref().unwrap().name;
+        assert_eq!(name.chars().count(), 8);
+        assert!(name.chars().all(|c| ('\u{F780}'..='\u{F7FF}').contains(&c)));
+
+        let entry = &mut profile.key_set.as_mut().unwrap()[0];
+        entry.set_driver_value(0x02002C00);
+        let written = profile.to_vec().unwrap();
+        assert_eq!(written.len(), bytes.len());
+        // "全亮紫光"
+        let gbk_name = b"\"Name\": \"\xC8\xAB\xC1\xC1\xD7\xCF\xB9\xE2\"";
+        assert!(written
+            .windows(gbk_name.len())
+            .any(|window| window == gbk_name));
+        assert_eq!(Profile::from_slice(&written).unwrap(), profile);
+    }
+
+    #[test]
+    fn edited_value_only_changes_its_line() {
+        let bytes = fs::read(PROFILE).unwrap();
+        let mut profile = Profile::from_slice(&bytes).unwrap();
+        let entry = &mut profile.key_set.as_mut().unwrap()[0];
+        entry.set_driver_value(0x02002C00);
+
+        let written = profile.to_vec().unwrap();
+        let (before, after) = (
+            String::from_utf8(bytes).unwrap(),
+            String::from_utf8(written).unwrap(),
+        );
+        let changed = before
+            .lines()
+            .zip(after.lines())
+            .filter(|(before, after)| before != after)
+            .collect::<Vec<_>>();
+        assert_eq!(before.lines().count(), after.lines().count());
+        assert_eq!(
+            changed,
+            [(
+                "\t\t\t\"DriverValue\": \"0x02006500\",",
+                "\t\t\t\"DriverValue\": \"0x02002C00\","
+            )]
+        );
+        assert_eq!(Profile::from_slice(after.as_bytes()).unwrap(), profile);
+    }
+
+    #[test]
+    fn added_entry_is_laid_out_like_the_one_before() {
+        let bytes = fs::read(PROFILE).unwrap();
+        let mut profile = Profile::from_slice(&bytes).unwrap();
+        let last = profile.key_set.as_mut().unwrap().pop().unwrap();
+
+        let mut profile = Profile::from_slice(&profile.to_vec().unwrap()).unwrap();
+        profile.key_set.as_mut().unwrap().push(last);
+        assert!(profile.to_vec().unwrap() == bytes);
+    }
+
+    #[test]
+    fn unknown_fields_keep_their_position() {
+        let text = "{\n\t\"GUID\": \"\",\n\t\"ModeIndex\": 0,\n\t\"Name\": \"\",\n\t\"Active\": 0,\n\t\"HotKeySet\": [],\n\t\"KeySet\": [],\n\t\"Zoom\": 1\n}";
+        let mut profile = Profile::from_slice(text.as_bytes()).unwrap();
+        profile.name = "Game".to_string();
+        profile.extra.insert("Zoom".to_string(), Value::from(2));
+
+        assert_eq!(
+            String::from_utf8(profile.to_vec().unwrap()).unwrap(),
+            text.replace("\"Name\": \"\"", "\"Name\": \"Game\"")
+                .replace("\"Zoom\": 1", "\"Zoom\": 2")
+        );
+    }
+
+    #[test]
+    fn new_profile_uses_the_vendor_layout() {
+        let profile = Profile {
+            name: "Layer".to_string(),
+            key_set: Some(vec![KeySetEntry::new(1, 0x02000400)]),
+            ..Default::default()
+        };
+        assert_eq!(
+            String::from_utf8(profile.to_vec().unwrap()).unwrap(),
+            "{\n\t\"GUID\": \"\",\n\t\"ModeIndex\": 0,\n\t\"Name\": \"Layer\",\n\t\"Active\": 0,\n\t\"KeySet\": [{\n\t\t\t\"Index\": 1,\n\t\t\t\"MenuPID\": \"\",\n\t\t\t\"MenuID\": \"\",\n\t\t\t\"MenuName\": \"\",\n\t\t\t\"DriverValue\": \"0x02000400\"\n\t\t}\n\t]\n}"
+        );
+    }
+
+    #[test]
+    fn driver_values_are_parsed_and_formatted_as_hex() {
+        assert_eq!(parse_driver_value("0x0a070001"), Some(0x0A070001));
+        assert_eq!(parse_driver_value(" 0X02006500 "), Some(0x02006500));
+        assert_eq!(parse_driver_value("02000400"), Some(0x02000400));
+        assert_eq!(parse_driver_value("Q"), None);
+        assert_eq!(format_driver_value(0x0A070001), "0x0A070001");
+    }
+}
@@ -0,0 +1,918 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{
+    key_action::KeyAction,
+    key_values::{self, DriverValueModifier, DriverValueMouseButton},
+    kle,
+    state::{Key, KeyAddress, KeyChange, LayerEditError, State, MAX_KEY_SET_SIZE},
+    Layer,
+};
+
+/// The onboard layers, which is what [`export_factory_default`] exports (the driver layer only exists while the
+/// application is running)
+pub const ONBOARD_LAYERS: [Layer; 4] = [Layer::Base, Layer::Layer1, Layer::Layer2, Layer::Layer3];
+
+/// Written in place of actions QMK can't express, see [`UnsupportedAction`]
+const NO_KEYCODE: &str = "KC_NO";
+const TRANSPARENT_KEYCODE: &str = "KC_TRNS";
+
+/// Modifiers with their QMK keycode and the function used to add them to another keycode (`LCTL(KC_C)`)
+const MODIFIERS: &[(DriverValueModifier, &str, &str)] = &[
+    (DriverValueModifier::LCTRL, "KC_LCTL", "LCTL"),
+    (DriverValueModifier::LSHIFT, "KC_LSFT", "LSFT"),
+    (DriverValueModifier::LALT, "KC_LALT", "LALT"),
+    (DriverValueModifier::LWIN, "KC_LGUI", "LGUI"),
+    (DriverValueModifier::RCTRL, "KC_RCTL", "RCTL"),
+    (DriverValueModifier::RSHIFT, "KC_RSFT", "RSFT"),
+    (DriverValueModifier::RALT, "KC_RALT", "RALT"),
+    (DriverValueModifier::RWIN, "KC_RGUI", "RGUI"),
+];
+
+const MOUSE_BUTTONS: &[(DriverValueMouseButton, &str)] = &[
+    (DriverValueMouseButton::LBUTTON, "KC_BTN1"),
+    (DriverValueMouseButton::RBUTTON, "KC_BTN2"),
+    (DriverValueMouseButton::MBUTTON, "KC_BTN3"),
+    (DriverValueMouseButton::BACK, "KC_BTN4"),
+    (DriverValueMouseButton::ADVANCE, "KC_BTN5"),
+];
+
+/// The consumer page usages QMK has a keycode for, sorted
+const CONSUMER_KEYCODES: &[(u16, &str)] = &[
+    (0x006F, "KC_BRIU"),
+    (0x0070, "KC_BRID"),
+    (0x00B3, "KC_MFFD"),
+    (0x00B4, "KC_MRWD"),
+    (0x00B5, "KC_MNXT"),
+    (0x00B6, "KC_MPRV"),
+    (0x00B7, "KC_MSTP"),
+    (0x00B8, "KC_EJCT"),
+    (0x00CD, "KC_MPLY"),
+    (0x00E2, "KC_MUTE"),
+    (0x00E9, "KC_VOLU"),
+    (0x00EA, "KC_VOLD"),
+    (0x0183, "KC_MSEL"),
+    (0x018A, "KC_MAIL"),
+    (0x0192, "KC_CALC"),
+    (0x0194, "KC_MYCM"),
+    (0x0221, "KC_WSCH"),
+    (0x0223, "KC_WHOM"),
+    (0x0224, "KC_WBAK"),
+    (0x0225, "KC_WFWD"),
+    (0x0226, "KC_WSTP"),
+    (0x0227, "KC_WREF"),
+    (0x022A, "KC_WFAV"),
+];
+
+/// The keyboard page usages which don't follow a range (letters, digits, F keys, keypad digits)
+const USAGE_KEYCODES: &[(u8, &str)] = &[
+    (0x28, "KC_ENT"),
+    (0x29, "KC_ESC"),
+    (0x2A, "KC_BSPC"),
+    (0x2B, "KC_TAB"),
+    (0x2C, "KC_SPC"),
+    (0x2D, "KC_MINS"),
+    (0x2E, "KC_EQL"),
+    (0x2F, "KC_LBRC"),
+    (0x30, "KC_RBRC"),
+    (0x31, "KC_BSLS"),
+    (0x32, "KC_NUHS"),
+    (0x33, "KC_SCLN"),
+    (0x34, "KC_QUOT"),
+    (0x35, "KC_GRV"),
+    (0x36, "KC_COMM"),
+    (0x37, "KC_DOT"),
+    (0x38, "KC_SLSH"),
+    (0x39, "KC_CAPS"),
+    (0x46, "KC_PSCR"),
+    (0x47, "KC_SCRL"),
+    (0x48, "KC_PAUS"),
+    (0x49, "KC_INS"),
+    (0x4A, "KC_HOME"),
+    (0x4B, "KC_PGUP"),
+    (0x4C, "KC_DEL"),
+    (0x4D, "KC_END"),
+    (0x4E, "KC_PGDN"),
+    (0x4F, "KC_RGHT"),
+    (0x50, "KC_LEFT"),
+    (0x51, "KC_DOWN"),
+    (0x52, "KC_UP"),
+    (0x53, "KC_NUM"),
+    (0x54, "KC_PSLS"),
+    (0x55, "KC_PAST"),
+    (0x56, "KC_PMNS"),
+    (0x57, "KC_PPLS"),
+    (0x58, "KC_PENT"),
+    (0x63, "KC_PDOT"),
+    (0x64, "KC_NUBS"),
+    (0x65, "KC_APP"),
+    (0x66, "KC_KB_POWER"),
+    (0x67, "KC_PEQL"),
+    (0x74, "KC_EXEC"),
+    (0x75, "KC_HELP"),
+    (0x76, "KC_MENU"),
+    (0x77, "KC_SLCT"),
+    (0x78, "KC_STOP"),
+    (0x79, "KC_AGIN"),
+    (0x7A, "KC_UNDO"),
+    (0x7B, "KC_CUT"),
+    (0x7C, "KC_COPY"),
+    (0x7D, "KC_PSTE"),
+    (0x7E, "KC_FIND"),
+    (0x7F, "KC_KB_MUTE"),
+    (0x80, "KC_KB_VOLUME_UP"),
+    (0x81, "KC_KB_VOLUME_DOWN"),
+    (0x85, "KC_PCMM"),
+    (0x9A, "KC_ERAS"),
+    (0x9C, "KC_CLR"),
+];
+
+/// A QMK `keymap.json` (also accepted by VIA). The keycodes of each layer follow the order of
+/// [`ModelDefinition::layout_rows`](super::model::ModelDefinition::layout_rows).
+#[derive(Debug, Clone, Serialize)]
+pub struct Keymap {
+    pub version: u32,
+    /// A name derived from the model, this has to be changed to the QMK keyboard the keymap is used with
+    pub keyboard: String,
+    pub keymap: String,
+    pub layout: String,
+    pub layers: Vec<Vec<String>>,
+    /// The actions which were exported as `KC_NO`, one per line
+    pub notes: String,
+}
+
+/// An action QMK can't express, it's exported as `KC_NO`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnsupportedAction {
+    /// The index of the layer in [`Keymap::layers`]
+    pub qmk_layer: usize,
+    pub layer: String,
+    pub key_name: String,
+    pub action: String,
+    pub reason: String,
+}
+
+/// `layer 3 (Layer1 Fn), Q: Macro(3) (macro contents aren't exported)`
+impl fmt::Display for UnsupportedAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "layer {} ({}), {}: {} ({})",
+            self.qmk_layer, self.layer, self.key_name, self.action, self.reason
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Export {
+    pub keymap: Keymap,
+    pub unsupported: Vec<UnsupportedAction>,
+}
+
+/// Exports layers as a QMK keymap. Each layer becomes two QMK layers, its keys followed by its Fn keys, and the Fn
+/// key is exported as `MO()` of the Fn layer. Layer actions which target a layer that isn't exported are unsupported.
+pub fn export(state: &State, layers: &[Layer]) -> Result<Export, LayerEditError> {
+    let model = state.model();
+    let keys = model
+        .layout_rows()
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+    let qmk_layer = |layer: Layer| layers.iter().position(|&exported| exported == layer);
+
+    let mut qmk_layers = vec![];
+    let mut unsupported = vec![];
+    for &layer in layers {
+        let state_layer = state
+            .get_layer(layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
+        for fn_layer in [false, true] {
+            let index = qmk_layers.len();
+            let key_set = state_layer.key_set(fn_layer);
+            let keycodes = keys
+                .iter()
+                .map(|key| {
+                    if is_fn_key(key) {
+                        return if fn_layer {
+                            TRANSPARENT_KEYCODE.to_string()
+                        } else {
+                            format!("MO({})", index + 1)
+                        };
+                    }
+                    let driver_value = usize::try_from(key.logic_code)
+                        .ok()
+                        .and_then(|logic_code| key_set.get(logic_code))
+                        .copied()
+                        .unwrap_or(key_values::UNUSED_KEY_VALUE);
+                    let action = KeyAction::from_u32(driver_value);
+                    match keycode(action, |layer| qmk_layer(layer).map(|layer| layer * 2)) {
+                        Ok(keycode) => keycode,
+                        Err(reason) => {
+                            unsupported.push(UnsupportedAction {
+                                qmk_layer: index,
+                                layer: format!("{layer:?}{}", if fn_layer { " Fn" } else { "" }),
+                                key_name: key.key_name.clone(),
+                                action: action.to_string(),
+                                reason,
+                            });
+                            NO_KEYCODE.to_string()
+                        }
+                    }
+                })
+                .collect();
+            qmk_layers.push(keycodes);
+        }
+    }
+
+    let notes = unsupported
+        .iter()
+        .map(|action| format!("Not supported by QMK: {action}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(Export {
+        keymap: Keymap {
+            version: 1,
+            keyboard: keyboard_name(&model.model_name, model.model_id),
+            keymap: "default".to_string(),
+            layout: "LAYOUT".to_string(),
+            layers: qmk_layers,
+            notes,
+        },
+        unsupported,
+    })
+}
+
+/// Exports the factory default profiles of the onboard layers of a model
+pub fn export_factory_default(model_id: u32) -> Option<Export> {
+    let mut state = State::get_keyboard_state(model_id)?;
+    state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+    export(&state, &ONBOARD_LAYERS).ok()
+}
+
+/// The QMK keycode of an action. `qmk_layer` gives the index of an exported layer, for `MO()` / `TO()`.
+pub fn keycode(
+    action: KeyAction,
+    qmk_layer: impl Fn(Layer) -> Option<usize>,
+) -> Result<String, String> {
+    match action {
+        KeyAction::Unused => Ok(NO_KEYCODE.to_string()),
+        KeyAction::Key {
+            modifiers,
+            key_code,
+        } => key_keycode(modifiers, key_code),
+        KeyAction::Mouse { buttons } => MOUSE_BUTTONS
+            .iter()
+            .find(|(button, _)| *button == buttons)
+            .map(|(_, keycode)| keycode.to_string())
+            .ok_or_else(|| "QMK keycodes press a single mouse button".to_string()),
+        KeyAction::System { usage } => CONSUMER_KEYCODES
+            .binary_search_by_key(&usage, |(usage, _)| *usage)
+            .map(|index| CONSUMER_KEYCODES[index].1.to_string())
+            .map_err(|_| format!("QMK has no keycode for consumer usage 0x{usage:04X}")),
+        KeyAction::Macro { .. } => Err("macro contents aren't exported".to_string()),
+        KeyAction::TempSwitchLayer { layer } => layer_keycode("MO", find_layer(layer), qmk_layer),
+        KeyAction::LayerButton { layer } => {
+            // The first layer button is the driver layer's, the others match `TempSwitchLayer`
+            let target = match layer {
+                1 => Some(Layer::Driver),
+                _ => find_layer(layer),
+            };
+            layer_keycode("TO", target, qmk_layer)
+        }
+        KeyAction::Other(_) => Err("QMK has no equivalent".to_string()),
+    }
+}
+
+fn key_keycode(modifiers: DriverValueModifier, key_code: u8) -> Result<String, String> {
+    let mut modifiers = MODIFIERS
+        .iter()
+        .filter(|(modifier, _, _)| modifiers.contains(*modifier))
+        .collect::<Vec<_>>();
+    let base = if key_code != 0 {
+        usage_keycode(key_code)
+            .ok_or_else(|| format!("QMK has no keycode for usage 0x{key_code:02X}"))?
+    } else if modifiers.is_empty() {
+        // Disabled
+        return Ok(NO_KEYCODE.to_string());
+    } else {
+        modifiers.remove(0).1.to_string()
+    };
+
+    // QMK's modified keycodes have a single left / right flag for all of their modifiers
+    let has_left = modifiers
+        .iter()
+        .any(|(modifier, _, _)| modifier.bits() & 0x0F != 0);
+    let has_right = modifiers
+        .iter()
+        .any(|(modifier, _, _)| modifier.bits() & 0xF0 != 0);
+    if has_left && has_right {
+        return Err("QMK can't mix left and right modifiers on one key".to_string());
+    }
+    Ok(modifiers
+        .iter()
+        .rev()
+        .fold(base, |keycode, (_, _, function)| {
+            format!("{function}({keycode})")
+        }))
+}
+
+/// `KC_A`, `KC_1`, `KC_F1`, `KC_P1`, ...
+fn usage_keycode(usage: u8) -> Option<String> {
+    let keycode = match usage {
+        0x04..=0x1D => format!("KC_{}", (b'A' + usage - 0x04) as char),
+        0x1E..=0x26 => format!("KC_{}", usage - 0x1D),
+        0x27 => "KC_0".to_string(),
+        0x3A..=0x45 => format!("KC_F{}", usage - 0x39),
+        0x59..=0x61 => format!("KC_P{}", usage - 0x58),
+        0x62 => "KC_P0".to_string(),
+        0x68..=0x73 => format!("KC_F{}", usage - 0x68 + 13),
+        0x87..=0x8F => format!("KC_INT{}", usage - 0x86),
+        0x90..=0x98 => format!("KC_LNG{}", usage - 0x8F),
+        0xE0..=0xE7 => MODIFIERS[(usage - 0xE0) as usize].1.to_string(),
+        _ => USAGE_KEYCODES
+            .iter()
+            .find(|(keycode_usage, _)| *keycode_usage == usage)?
+            .1
+            .to_string(),
+    };
+    Some(keycode)
+}
+
+fn layer_keycode(
+    function: &str,
+    target: Option<Layer>,
+    qmk_layer: impl Fn(Layer) -> Option<usize>,
+) -> Result<String, String> {
+    let target = target.ok_or_else(|| "unknown layer".to_string())?;
+    match qmk_layer(target) {
+        Some(index) => Ok(format!("{function}({index})")),
+        None => Err(format!("{target:?} isn't exported")),
+    }
+}
+
+fn find_layer(value: u16) -> Option<Layer> {
+    Layer::ALL.into_iter().find(|&layer| layer as u16 == value)
+}
+
+/// The keymaps have a `Fn` key, which isn't part of the key set
+fn is_fn_key(key: &Key) -> bool {
+    key.key_name.eq_ignore_ascii_case("Fn")
+}
+
+/// "GK61 (RGB)" => "gk61_rgb", models without a name use their id
+fn keyboard_name(model_name: &str, model_id: u32) -> String {
+    let name = model_name
+        .to_ascii_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if name.is_empty() {
+        model_id.to_string()
+    } else {
+        name
+    }
+}
+
+/// The layers [`import`] fills, in order
+pub const IMPORTED_LAYERS: [Layer; 3] = [Layer::Layer1, Layer::Layer2, Layer::Layer3];
+
+/// Other names QMK accepts for keycodes (older versions and VIA use the long ones)
+const KEYCODE_ALIASES: &[(&str, &str)] = &[
+    ("KC_ENTER", "KC_ENT"),
+    ("KC_ESCAPE", "KC_ESC"),
+    ("KC_BSPACE", "KC_BSPC"),
+    ("KC_BACKSPACE", "KC_BSPC"),
+    ("KC_SPACE", "KC_SPC"),
+    ("KC_MINUS", "KC_MINS"),
+    ("KC_EQUAL", "KC_EQL"),
+    ("KC_LBRACKET", "KC_LBRC"),
+    ("KC_LEFT_BRACKET", "KC_LBRC"),
+    ("KC_RBRACKET", "KC_RBRC"),
+    ("KC_RIGHT_BRACKET", "KC_RBRC"),
+    ("KC_BSLASH", "KC_BSLS"),
+    ("KC_BACKSLASH", "KC_BSLS"),
+    ("KC_SCOLON", "KC_SCLN"),
+    ("KC_SEMICOLON", "KC_SCLN"),
+    ("KC_QUOTE", "KC_QUOT"),
+    ("KC_GRAVE", "KC_GRV"),
+    ("KC_COMMA", "KC_COMM"),
+    ("KC_SLASH", "KC_SLSH"),
+    ("KC_CAPSLOCK", "KC_CAPS"),
+    ("KC_CAPS_LOCK", "KC_CAPS"),
+    ("KC_PSCREEN", "KC_PSCR"),
+    ("KC_PRINT_SCREEN", "KC_PSCR"),
+    ("KC_SLCK", "KC_SCRL"),
+    ("KC_SCROLLLOCK", "KC_SCRL"),
+    ("KC_SCROLL_LOCK", "KC_SCRL"),
+    ("KC_PAUSE", "KC_PAUS"),
+    ("KC_INSERT", "KC_INS"),
+    ("KC_PGDOWN", "KC_PGDN"),
+    ("KC_PAGE_UP", "KC_PGUP"),
+    ("KC_PAGE_DOWN", "KC_PGDN"),
+    ("KC_DELETE", "KC_DEL"),
+    ("KC_RIGHT", "KC_RGHT"),
+    ("KC_NLCK", "KC_NUM"),
+    ("KC_NUMLOCK", "KC_NUM"),
+    ("KC_NUM_LOCK", "KC_NUM"),
+    ("KC_APPLICATION", "KC_APP"),
+    ("KC_LCTRL", "KC_LCTL"),
+    ("KC_LEFT_CTRL", "KC_LCTL"),
+    ("KC_LSHIFT", "KC_LSFT"),
+    ("KC_LEFT_SHIFT", "KC_LSFT"),
+    ("KC_LEFT_ALT", "KC_LALT"),
+    ("KC_LOPT", "KC_LALT"),
+    ("KC_LEFT_GUI", "KC_LGUI"),
+    ("KC_LCMD", "KC_LGUI"),
+    ("KC_LWIN", "KC_LGUI"),
+    ("KC_RCTRL", "KC_RCTL"),
+    ("KC_RIGHT_CTRL", "KC_RCTL"),
+    ("KC_RSHIFT", "KC_RSFT"),
+    ("KC_RIGHT_SHIFT", "KC_RSFT"),
+    ("KC_RIGHT_ALT", "KC_RALT"),
+    ("KC_ROPT", "KC_RALT"),
+    ("KC_ALGR", "KC_RALT"),
+    ("KC_RIGHT_GUI", "KC_RGUI"),
+    ("KC_RCMD", "KC_RGUI"),
+    ("KC_RWIN", "KC_RGUI"),
+    ("KC_AUDIO_MUTE", "KC_MUTE"),
+    ("KC_AUDIO_VOL_UP", "KC_VOLU"),
+    ("KC_AUDIO_VOL_DOWN", "KC_VOLD"),
+    ("KC_MEDIA_PLAY_PAUSE", "KC_MPLY"),
+    ("KC_MEDIA_NEXT_TRACK", "KC_MNXT"),
+    ("KC_MEDIA_PREV_TRACK", "KC_MPRV"),
+    ("KC_MEDIA_STOP", "KC_MSTP"),
+    ("MS_BTN1", "KC_BTN1"),
+    ("MS_BTN2", "KC_BTN2"),
+    ("MS_BTN3", "KC_BTN3"),
+    ("MS_BTN4", "KC_BTN4"),
+    ("MS_BTN5", "KC_BTN5"),
+    ("XXXXXXX", "KC_NO"),
+];
+
+/// Other names of the modifier functions (`C(KC_A)`, `LCMD(KC_A)`)
+const MODIFIER_FUNCTION_ALIASES: &[(&str, &str)] = &[
+    ("C", "LCTL"),
+    ("S", "LSFT"),
+    ("A", "LALT"),
+    ("LOPT", "LALT"),
+    ("G", "LGUI"),
+    ("LCMD", "LGUI"),
+    ("LWIN", "LGUI"),
+    ("ROPT", "RALT"),
+    ("ALGR", "RALT"),
+    ("RCMD", "RGUI"),
+    ("RWIN", "RGUI"),
+];
+
+/// A key of a QMK layout (`info.json`), in key units
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct QmkKeyRect {
+    pub x: f64,
+    pub y: f64,
+    #[serde(default = "one_unit")]
+    pub w: f64,
+    #[serde(default = "one_unit")]
+    pub h: f64,
+}
+
+fn one_unit() -> f64 {
+    1.0
+}
+
+/// What [`import`] did with the keymap
+#[derive(Debug, Clone, Default)]
+pub struct ImportReport {
+    /// Where each QMK layer went ("Layer1", "Layer1 Fn"), by QMK layer
+    pub layers: Vec<(usize, String)>,
+    /// The model keys each QMK key was placed on, by position in the QMK layers
+    #[allow(dead_code)]
+    pub placements: Vec<Option<String>>,
+    pub changes: Vec<(Layer, KeyChange)>,
+    /// The changes as text (`Layer1: Fn+Q: Unused -> Esc`)
+    pub summary: Vec<String>,
+    pub issues: Vec<ImportIssue>,
+}
+
+/// A key or action of the QMK keymap which couldn't be placed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportIssue {
+    pub qmk_layer: Option<usize>,
+    /// The position of the key in the QMK layers
+    pub key_index: Option<usize>,
+    pub keycode: Option<String>,
+    pub reason: String,
+}
+
+/// `layer 2, key 14 (KC_FOO): unknown keycode`
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = vec![];
+        if let Some(qmk_layer) = self.qmk_layer {
+            parts.push(format!("layer {qmk_layer}"));
+        }
+        if let Some(key_index) = self.key_index {
+            parts.push(format!("key {key_index}"));
+        }
+        let location = parts.join(", ");
+        match &self.keycode {
+            Some(keycode) => write!(f, "{location} ({keycode}): {}", self.reason),
+            None if location.is_empty() => write!(f, "{}", self.reason),
+            None => write!(f, "{location}: {}", self.reason),
+        }
+    }
+}
+
+/// A parsed QMK keycode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QmkKeycode {
+    Transparent,
+    Action(KeyAction),
+    /// `MO(n)`
+    Momentary(usize),
+    /// `TO(n)` / `TG(n)`
+    Switch(usize),
+}
+
+/// Reads the key rects of a layout from a QMK `info.json` (`layouts.<layout>.layout`), the first layout is used if
+/// `layout` is `None`. A bare array of rects and KLE raw JSON (see [`kle::parse`]) are accepted too.
+pub fn parse_geometry(info: &Value, layout: Option<&str>) -> Result<Vec<QmkKeyRect>, String> {
+    let rects = match info {
+        // Keyboard Layout Editor rows
+        Value::Array(rows) if rows.iter().any(Value::is_array) => {
+            return Ok(kle::parse(info)?.iter().map(QmkKeyRect::from).collect());
+        }
+        Value::Array(_) => info,
+        _ => {
+            let layouts = info
+                .get("layouts")
+                .and_then(Value::as_object)
+                .ok_or("the file doesn't have `layouts`")?;
+            let found = match layout {
+                Some(layout) => layouts.get(layout),
+                None => layouts.values().next(),
+            };
+            let found = found.ok_or_else(|| {
+                format!(
+                    "unknown layout, the file has {}",
+                    layouts.keys().cloned().collect::<Vec<_>>().join(", ")
+                )
+            })?;
+            found.get("layout").unwrap_or(found)
+        }
+    };
+    serde_json::from_value(rects.clone()).map_err(|err| format!("invalid layout: {err}"))
+}
+
+/// Imports a QMK / VIA `keymap.json` onto layers 1 - 3 of a model.
+///
+/// QMK keys are placed on the model key with the nearest center (within half a key), using `geometry` in the order
+/// of the keymap. Without geometry the keymap has to have a key per model key, in the order of
+/// [`ModelDefinition::layout_rows`](super::model::ModelDefinition::layout_rows) (which is what [`export`] writes).
+///
+/// A QMK layer which the key on the model's Fn key holds (`MO(n)`) becomes the Fn key set of its layer, the others
+/// fill [`IMPORTED_LAYERS`] in order. `KC_TRNS` takes the key of the layer a Fn layer belongs to, or of QMK layer 0.
+/// Keys which aren't placed keep their binding, and everything which couldn't be placed is listed in the report.
+pub fn import(
+    state: &mut State,
+    keymap: &Value,
+    geometry: &[QmkKeyRect],
+) -> Result<ImportReport, String> {
+    let qmk_layers = keymap
+        .get("layers")
+        .and_then(Value::as_array)
+        .ok_or("the keymap doesn't have `layers`")?
+        .iter()
+        .map(|layer| {
+            layer
+                .as_array()
+                .map(|keycodes| {
+                    keycodes
+                        .iter()
+                        .map(|keycode| keycode.as_str().unwrap_or_default().trim().to_string())
+                        .collect::<Vec<_>>()
+                })
+                .ok_or("a layer isn't an array of keycodes")
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let model = state.model().clone();
+    let rows = model.layout_rows();
+    let keys = rows.iter().flatten().copied().collect::<Vec<_>>();
+    let key_count = qmk_layers.iter().map(Vec::len).max().unwrap_or_default();
+    let placements = if geometry.is_empty() {
+        if key_count != keys.len() {
+            return Err(format!(
+                "without a layout the keymap needs a key per model key ({} keys, the model has {})",
+                key_count,
+                keys.len()
+            ));
+        }
+        keys.iter().map(|&key| Some(key)).collect()
+    } else {
+        match_geometry(&rows, model.layout_units(), geometry)
+    };
+
+    let mut report = ImportReport {
+        placements: placements
+            .iter()
+            .map(|key| key.map(|key| key.key_name.clone()))
+            .collect(),
+        ..Default::default()
+    };
+    for (key_index, placement) in placements.iter().enumerate().take(key_count) {
+        if placement.is_none() {
+            report.issues.push(ImportIssue {
+                qmk_layer: None,
+                key_index: Some(key_index),
+                keycode: None,
+                reason: "no model key at this position".to_string(),
+            });
+        }
+    }
+    let fn_key_index = placements.iter().position(|key| key.is_some_and(is_fn_key));
+    if fn_key_index.is_none() && model.keys().any(is_fn_key) {
+        report.issues.push(ImportIssue {
+            qmk_layer: None,
+            key_index: None,
+            keycode: None,
+            reason: "no QMK key is placed on the Fn key, the Fn layers are left as they are"
+                .to_string(),
+        });
+    }
+
+    // Which layer each QMK layer goes to
+    let keycode_at = |qmk_layer: usize, key_index: usize| {
+        qmk_layers[qmk_layer]
+            .get(key_index)
+            .map(String::as_str)
+            .unwrap_or_default()
+    };
+    let mut targets: Vec<Option<(Layer, bool)>> = vec![None; qmk_layers.len()];
+    // The layer `KC_TRNS` falls through to: a Fn layer's parent, otherwise the default layer
+    let mut below: Vec<Option<usize>> = (0..qmk_layers.len())
+        .map(|qmk_layer| (qmk_layer > 0).then_some(0))
+        .collect();
+    let mut free_layers = IMPORTED_LAYERS.iter().copied();
+    for qmk_layer in 0..qmk_layers.len() {
+        if targets[qmk_layer].is_none() {
+            match free_layers.next() {
+                Some(layer) => targets[qmk_layer] = Some((layer, false)),
+                None => {
+                    report.issues.push(ImportIssue {
+                        qmk_layer: Some(qmk_layer),
+                        key_index: None,
+                        keycode: None,
+                        reason: "only layers 1 - 3 are imported".to_string(),
+                    });
+                    continue;
+                }
+            }
+        }
+        let Some((layer, false)) = targets[qmk_layer] else {
+            continue;
+        };
+        let Some(fn_key_index) = fn_key_index else {
+            continue;
+        };
+        if let Ok(QmkKeycode::Momentary(fn_layer)) =
+            parse_keycode(keycode_at(qmk_layer, fn_key_index))
+        {
+            if fn_layer > qmk_layer && fn_layer < qmk_layers.len() && targets[fn_layer].is_none() {
+                targets[fn_layer] = Some((layer, true));
+                below[fn_layer] = Some(qmk_layer);
+            }
+        }
+    }
+    for (qmk_layer, target) in targets.iter().enumerate() {
+        if let Some((layer, fn_layer)) = target {
+            report.layers.push((
+                qmk_layer,
+                format!("{layer:?}{}", if *fn_layer { " Fn" } else { "" }),
+            ));
+        }
+    }
+
+    for (qmk_layer, target) in targets.iter().enumerate() {
+        let Some((layer, fn_layer)) = *target else {
+            continue;
+        };
+        for (key_index, key) in placements.iter().enumerate().take(key_count) {
+            let Some(key) = key else {
+                continue;
+            };
+            if is_fn_key(key) {
+                continue;
+            }
+            let mut issue = |keycode: &str, reason: String| {
+                report.issues.push(ImportIssue {
+                    qmk_layer: Some(qmk_layer),
+                    key_index: Some(key_index),
+                    keycode: Some(keycode.to_string()),
+                    reason,
+                })
+            };
+
+            let mut keycode = keycode_at(qmk_layer, key_index);
+            let mut parsed = parse_keycode(keycode);
+            let mut source_layer = below[qmk_layer];
+            while let (Ok(QmkKeycode::Transparent), Some(layer)) = (&parsed, source_layer) {
+                keycode = keycode_at(layer, key_index);
+                parsed = parse_keycode(keycode);
+                source_layer = below[layer];
+            }
+            let action = match parsed {
+                Ok(QmkKeycode::Action(action)) => action,
+                Ok(QmkKeycode::Transparent) => KeyAction::Unused,
+                Ok(QmkKeycode::Momentary(target) | QmkKeycode::Switch(target)) => {
+                    let Some((target_layer, false)) = targets.get(target).copied().flatten() else {
+                        issue(
+                            keycode,
+                            format!("QMK layer {target} isn't imported as a layer"),
+                        );
+                        continue;
+                    };
+                    match parsed {
+                        Ok(QmkKeycode::Momentary(_)) => KeyAction::TempSwitchLayer {
+                            layer: target_layer as u16,
+                        },
+                        _ => KeyAction::LayerButton {
+                            layer: target_layer as u16,
+                        },
+                    }
+                }
+                Err(reason) => {
+                    issue(keycode, reason);
+                    continue;
+                }
+            };
+
+            match state.bind_key(
+                layer,
+                fn_layer,
+                &KeyAddress::LogicCode(key.logic_code),
+                action.to_u32(),
+            ) {
+                Ok(changes) => report
+                    .changes
+                    .extend(changes.into_iter().map(|change| (layer, change))),
+                Err(err) => issue(keycode, err.to_string()),
+            }
+        }
+    }
+    report.summary = report
+        .changes
+        .iter()
+        .map(|(layer, change)| format!("{layer:?}: {}", state.describe_change(change)))
+        .collect();
+    Ok(report)
+}
+
+/// Imports a keymap onto the factory default layers of a model, see [`import`]
+pub fn import_onto_factory_default(
+    model_id: u32,
+    keymap: &Value,
+    geometry: &[QmkKeyRect],
+) -> Result<ImportReport, String> {
+    let mut state =
+        State::get_keyboard_state(model_id).ok_or_else(|| format!("unknown model {model_id}"))?;
+    state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+    import(&mut state, keymap, geometry)
+}
+
+/// Places each QMK key on the model key with the nearest center. Both layouts are measured in key units from their
+/// top left key (see [`ModelDefinition::layout_units`](super::model::ModelDefinition::layout_units)).
+fn match_geometry<'a>(
+    rows: &[Vec<&'a Key>],
+    (unit_x, unit_y): (f64, f64),
+    geometry: &[QmkKeyRect],
+) -> Vec<Option<&'a Key>> {
+    let keys = rows.iter().flatten().copied().collect::<Vec<_>>();
+    let left = keys
+        .iter()
+        .map(|key| key.position.left)
+        .min()
+        .unwrap_or_default();
+    let top = keys
+        .iter()
+        .map(|key| key.position.top)
+        .min()
+        .unwrap_or_default();
+    let key_centers = keys
+        .iter()
+        .map(|key| {
+            (
+                (key.position.left - left) as f64 / unit_x
+                    + key.position.width as f64 / unit_x / 2.0,
+                (key.position.top - top) as f64 / unit_y
+                    + key.position.height as f64 / unit_y / 2.0,
+            )
+        })
+        .collect::<Vec<_>>();
+    let x = geometry
+        .iter()
+        .map(|rect| rect.x)
+        .fold(f64::INFINITY, f64::min);
+    let y = geometry
+        .iter()
+        .map(|rect| rect.y)
+        .fold(f64::INFINITY, f64::min);
+    let qmk_centers = geometry
+        .iter()
+        .map(|rect| (rect.x - x + rect.w / 2.0, rect.y - y + rect.h / 2.0));
+
+    let mut pairs = vec![];
+    for (qmk_index, (qmk_x, qmk_y)) in qmk_centers.enumerate() {
+        for (key_index, (key_x, key_y)) in key_centers.iter().enumerate() {
+            let distance = (qmk_x - key_x).hypot(qmk_y - key_y);
+            if distance < 0.5 {
+                pairs.push((distance, qmk_index, key_index));
+            }
+        }
+    }
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut placements = vec![None; geometry.len()];
+    let mut placed_keys = vec![false; keys.len()];
+    for (_, qmk_index, key_index) in pairs {
+        if placements[qmk_index].is_none() && !placed_keys[key_index] {
+            placements[qmk_index] = Some(keys[key_index]);
+            placed_keys[key_index] = true;
+        }
+    }
+    placements
+}
+
+fn parse_keycode(keycode: &str) -> Result<QmkKeycode, String> {
+    let keycode = KEYCODE_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(keycode))
+        .map_or(keycode, |(_, name)| *name);
+    match keycode.to_ascii_uppercase().as_str() {
+        "KC_TRNS" | "KC_TRANSPARENT" | "_______" => return Ok(QmkKeycode::Transparent),
+        "KC_NO" => return Ok(QmkKeycode::Action(KeyAction::Unused)),
+        _ => {}
+    }
+
+    if let Some((function, argument)) = keycode
+        .strip_suffix(')')
+        .and_then(|keycode| keycode.split_once('('))
+    {
+        let function = function.trim().to_ascii_uppercase();
+        let function = MODIFIER_FUNCTION_ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == function)
+            .map_or(function.as_str(), |(_, name)| *name);
+        let layer = || {
+            argument
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid layer `{argument}`"))
+        };
+        return match function {
+            "MO" => Ok(QmkKeycode::Momentary(layer()?)),
+            "TO" | "TG" => Ok(QmkKeycode::Switch(layer()?)),
+            _ => {
+                let (modifier, _, _) = MODIFIERS
+                    .iter()
+                    .find(|(_, _, name)| *name == function)
+                    .ok_or_else(|| format!("QMK function `{function}` can't be imported"))?;
+                match parse_keycode(argument.trim())? {
+                    QmkKeycode::Action(KeyAction::Key {
+                        modifiers,
+                        key_code,
+                    }) => Ok(QmkKeycode::Action(KeyAction::Key {
+                        modifiers: modifiers | *modifier,
+                        key_code,
+                    })),
+                    _ => Err("only keys can have modifiers".to_string()),
+                }
+            }
+        };
+    }
+
+    let keycode = keycode.to_ascii_uppercase();
+    if let Some((modifier, _, _)) = MODIFIERS.iter().find(|(_, name, _)| *name == keycode) {
+        return Ok(QmkKeycode::Action(KeyAction::Key {
+            modifiers: *modifier,
+            key_code: 0,
+        }));
+    }
+    if let Some(key_code) =
+        (0x04..=0xDF).find(|&usage| usage_keycode(usage).as_deref() == Some(&keycode))
+    {
+        return Ok(QmkKeycode::Action(KeyAction::Key {
+            modifiers: DriverValueModifier::NONE,
+            key_code,
+        }));
+    }
+    if let Some((buttons, _)) = MOUSE_BUTTONS.iter().find(|(_, name)| *name == keycode) {
+        return Ok(QmkKeycode::Action(KeyAction::Mouse { buttons: *buttons }));
+    }
+    if let Some((usage, _)) = CONSUMER_KEYCODES.iter().find(|(_, name)| *name == keycode) {
+        return Ok(QmkKeycode::Action(KeyAction::System { usage: *usage }));
+    }
+    Err("unknown or unsupported keycode".to_string())
+}
@@ -0,0 +1,391 @@
+use std::{collections::HashSet, fmt};
+
+use super::{
+    key_action::KeyAction,
+    key_values::{self, DriverValue},
+    model::{ModelDefinition, UserManualItem},
+    presets::{self, Preset},
+    state::{Key, State, StateLayer, MAX_KEY_SET_SIZE},
+    Layer,
+};
+
+/// `UserManual` shortcuts which switch between a layer and the base layer (pressed again on the layer, they go back)
+const LAYER_SHORTCUTS: &[(&str, Layer)] = &[
+    ("change_onboard_layer_1", Layer::Layer1),
+    ("change_onboard_layer_2", Layer::Layer2),
+    ("change_onboard_layer_3", Layer::Layer3),
+    ("change_driver_layer_1", Layer::Driver),
+];
+
+/// `UserManual` key names which aren't a `KeyName` / `Show` of the keymaps
+const SHORTCUT_KEY_ALIASES: &[(&str, &str)] = &[
+    ("Del", "Delete"),
+    ("↑", "Up"),
+    ("↓", "Down"),
+    ("←", "Left"),
+    ("→", "Right"),
+];
+
+/// Keys a layer shouldn't lose, values of a group stand in for each other (either Shift will do)
+const ESSENTIAL_KEYS: &[&[DriverValue]] = &[
+    &[DriverValue::Esc],
+    &[DriverValue::Enter, DriverValue::NumPadEnter],
+    &[DriverValue::Backspace],
+    &[DriverValue::Space],
+    &[DriverValue::Tab],
+    &[DriverValue::LShift, DriverValue::RShift],
+    &[DriverValue::LCtrl, DriverValue::RCtrl],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    /// Uploading the layer would leave the keyboard in a state which is hard to get out of
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutIssue {
+    pub layer: Layer,
+    pub severity: Severity,
+    pub kind: LayoutIssueKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayoutIssueKind {
+    /// No layer switch (bound or `UserManual` shortcut) leads back to the base layer, even through other layers
+    NoWayBack,
+    /// The Fn key set binds the key which triggers a firmware shortcut
+    ShadowedShortcut {
+        shortcut: String,
+        description: String,
+        key_name: String,
+        action: String,
+    },
+    /// Neither key set of the layer has the key (or any key standing in for it)
+    MissingKey(DriverValue),
+}
+
+impl fmt::Display for LayoutIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {:?}: ", self.layer, self.severity)?;
+        match &self.kind {
+            LayoutIssueKind::NoWayBack => write!(f, "there is no way back to the base layer"),
+            LayoutIssueKind::ShadowedShortcut {
+                shortcut,
+                description,
+                key_name,
+                action,
+            } => write!(
+                f,
+                "{shortcut} ({description}) is shadowed by Fn+{key_name} bound to {action}"
+            ),
+            LayoutIssueKind::MissingKey(key) => write!(f, "no key is bound to {key}"),
+        }
+    }
+}
+
+/// A `UserManual` shortcut resolved to the keys of a model
+struct Shortcut<'a> {
+    item: &'a UserManualItem,
+    /// The keys which trigger the shortcut, the last one pressed (`Q` of `Fn+ESC+Q`) or its alternatives (`-` and
+    /// `=` of `Fn+-/=`)
+    triggers: Vec<&'a Key>,
+    /// The layer switched to, see [`LAYER_SHORTCUTS`]
+    layer: Option<Layer>,
+}
+
+/// Checks every layer of the state:
+/// - each layer can get back to the base layer, through `TempSwitchLayer*` / `Layer*Button` bindings or the layer
+///   shortcuts of `UserManual` (`Fn+W`) which aren't shadowed. Models without layer shortcuts in `UserManual` are
+///   skipped, their firmware surely has some but we don't know which keys.
+/// - the Fn key set doesn't shadow a `UserManual` shortcut. A shortcut is shadowed when the Fn key set binds the key
+///   which triggers it (`Q` of `Fn+ESC+Q`) to something other than the factory default profile does (which often
+///   binds Fn+Q to Q), the keys held before it can be bound.
+/// - essential keys (Esc, Enter, ...) the layer's factory default profile binds are still bound somewhere on the
+///   layer, Fn keys included
+///
+/// Layers without a factory default profile are skipped until a key is bound on them.
+pub fn check(state: &State) -> Vec<LayoutIssue> {
+    let model = state.model();
+    let shortcuts = shortcuts(model);
+    let layers = Layer::ALL
+        .into_iter()
+        .filter_map(|layer| Some((layer, state.get_layer(layer)?)))
+        // Layers without a factory profile have nothing to check until keys are bound
+        .filter(|(_, state_layer)| {
+            state_layer.factory_default_model_data.is_some() || !state_layer.is_empty()
+        })
+        .collect::<Vec<_>>();
+
+    let mut issues = vec![];
+    for &(layer, state_layer) in &layers {
+        let factory_fn_key_set = state_layer.factory_key_set(true, model);
+        for shortcut in &shortcuts {
+            for key in &shortcut.triggers {
+                let Some(action) = shadowing_action(state_layer, &factory_fn_key_set, key) else {
+                    continue;
+                };
+                issues.push(LayoutIssue {
+                    layer,
+                    severity: Severity::Warning,
+                    kind: LayoutIssueKind::ShadowedShortcut {
+                        shortcut: shortcut.item.key.clone(),
+                        description: shortcut.item.description(),
+                        key_name: key.key_name.clone(),
+                        action: action.to_string(),
+                    },
+                });
+            }
+        }
+
+        let factory_key_set = state_layer.factory_key_set(false, model);
+        for &group in ESSENTIAL_KEYS {
+            let is_bound = |key_set: &[u32], fn_key_set: &[u32]| {
+                group.iter().any(|&key| {
+                    key_set.contains(&(key as u32)) || fn_key_set.contains(&(key as u32))
+                })
+            };
+            // Only keys the layer used to have, some factory layers (gaming) leave out Esc on purpose and a layer
+            // without a factory profile had none
+            if is_bound(&factory_key_set, &factory_fn_key_set)
+                && !is_bound(&state_layer.key_set, &state_layer.fn_key_set)
+            {
+                issues.push(LayoutIssue {
+                    layer,
+                    severity: Severity::Warning,
+                    kind: LayoutIssueKind::MissingKey(group[0]),
+                });
+            }
+        }
+    }
+
+    if shortcuts.iter().any(|shortcut| shortcut.layer.is_some()) {
+        issues.extend(
+            no_way_back(model, &layers, &shortcuts)
+                .into_iter()
+                .map(|layer| LayoutIssue {
+                    layer,
+                    severity: Severity::Error,
+                    kind: LayoutIssueKind::NoWayBack,
+                }),
+        );
+    }
+    issues.sort_by_key(|issue| (issue.layer as u8, std::cmp::Reverse(issue.severity)));
+    issues
+}
+
+/// Checks a model's factory default profiles (see [`check`]), after applying presets to the keys or Fn keys of some
+/// layers (see [`presets::apply`])
+pub fn check_factory_default(
+    model_id: u32,
+    presets: &[&Preset],
+    layers: &[Layer],
+    fn_layer: bool,
+) -> Result<Vec<LayoutIssue>, String> {
+    let mut state =
+        State::get_keyboard_state(model_id).ok_or_else(|| format!("unknown model {model_id}"))?;
+    state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+    if !presets.is_empty() {
+        for &layer in layers {
+            presets::apply(&mut state, layer, fn_layer, presets).map_err(|err| err.to_string())?;
+        }
+    }
+    Ok(check(&state))
+}
+
+/// The layers which can't get back to the base layer. The layer switches are walked backwards from the base layer.
+fn no_way_back(
+    model: &ModelDefinition,
+    layers: &[(Layer, &StateLayer)],
+    shortcuts: &[Shortcut],
+) -> Vec<Layer> {
+    let mut way_back = HashSet::from([Layer::Base]);
+    loop {
+        let found = layers
+            .iter()
+            .filter(|(layer, _)| !way_back.contains(layer))
+            .filter(|(layer, state_layer)| {
+                switches(model, *layer, state_layer, shortcuts)
+                    .iter()
+                    .any(|target| way_back.contains(target))
+            })
+            .map(|&(layer, _)| layer)
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            break;
+        }
+        way_back.extend(found);
+    }
+    layers
+        .iter()
+        .map(|&(layer, _)| layer)
+        .filter(|layer| !way_back.contains(layer))
+        .collect()
+}
+
+/// The layers a layer can switch to, by its bindings (either key set) and the shortcuts it doesn't shadow
+fn switches(
+    model: &ModelDefinition,
+    layer: Layer,
+    state_layer: &StateLayer,
+    shortcuts: &[Shortcut],
+) -> Vec<Layer> {
+    // Layer buttons and shortcuts go back to the base layer when pressed on their own layer
+    let toggle = |target: Layer| if target == layer { Layer::Base } else { target };
+    let factory_fn_key_set = state_layer.factory_key_set(true, model);
+    let mut targets = state_layer
+        .key_set
+        .iter()
+        .chain(&state_layer.fn_key_set)
+        .filter_map(|&value| match KeyAction::from_u32(value) {
+            KeyAction::TempSwitchLayer { layer } => match layer {
+                1 => Some(Layer::Base),
+                2 => Some(Layer::Layer1),
+                3 => Some(Layer::Layer2),
+                4 => Some(Layer::Layer3),
+                5 => Some(Layer::Driver),
+                _ => None,
+            },
+            KeyAction::LayerButton { layer } => match layer {
+                1 => Some(Layer::Driver),
+                2 => Some(Layer::Layer1),
+                3 => Some(Layer::Layer2),
+                4 => Some(Layer::Layer3),
+                _ => None,
+            }
+            .map(toggle),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    targets.extend(
+        shortcuts
+            .iter()
+            .filter(|shortcut| {
+                shortcut
+                    .triggers
+                    .iter()
+                    .all(|key| shadowing_action(state_layer, &factory_fn_key_set, key).is_none())
+            })
+            .filter_map(|shortcut| shortcut.layer)
+            .map(toggle),
+    );
+    targets
+}
+
+/// The `UserManual` Fn shortcuts of a model. Shortcuts whose keys aren't all on the keymap are kept without triggers,
+/// they can't be shadowed as far as we can tell.
+fn shortcuts(model: &ModelDefinition) -> Vec<Shortcut<'_>> {
+    model
+        .config
+        .user_manual
+        .iter()
+        .flat_map(|section| &section.items)
+        .filter_map(|item| {
+            let keys = item.key.trim();
+            let keys = keys
+                .get(.."Fn+".len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case("Fn+"))
+                .map(|_| &keys["Fn+".len()..])?;
+            let triggers = keys
+                .split('+')
+                .map(|name| find_keys(model, name.trim()))
+                .collect::<Option<Vec<_>>>()
+                .and_then(|keys| keys.last().cloned())
+                .unwrap_or_default();
+            let layer = LAYER_SHORTCUTS
+                .iter()
+                .find(|(lang, _)| *lang == item.desc_lang)
+                .map(|&(_, layer)| layer);
+            Some(Shortcut {
+                item,
+                triggers,
+                layer,
+            })
+        })
+        .collect()
+}
+
+/// The keys a `UserManual` key name stands for (`ESC`, `]/|` is either key)
+fn find_keys<'a>(model: &'a ModelDefinition, name: &str) -> Option<Vec<&'a Key>> {
+    let find = |name: &str| {
+        if name.is_empty() {
+            return None;
+        }
+        let name = SHORTCUT_KEY_ALIASES
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+            .map_or(name, |&(_, key_name)| key_name);
+        model
+            .keys()
+            .filter(|key| key.logic_code >= 0)
+            .filter(|key| {
+                key.key_name.eq_ignore_ascii_case(name)
+                    || key.show.eq_ignore_ascii_case(name)
+                    || key.driver_value_name.eq_ignore_ascii_case(name)
+            })
+            .min_by_key(|key| key.logic_code)
+    };
+    match find(name) {
+        Some(key) => Some(vec![key]),
+        None => name.split('/').map(|name| find(name.trim())).collect(),
+    }
+}
+
+/// The action bound to a key of the Fn key set, unless it's unused or the factory default profile's
+fn shadowing_action(
+    state_layer: &StateLayer,
+    factory_fn_key_set: &[u32],
+    key: &Key,
+) -> Option<KeyAction> {
+    let index = usize::try_from(key.logic_code).ok()?;
+    let value = *state_layer.fn_key_set.get(index)?;
+    (value != key_values::UNUSED_KEY_VALUE && factory_fn_key_set.get(index) != Some(&value))
+        .then(|| KeyAction::from_u32(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::state::KeyAddress;
+
+    /// A state whose Layer2 has no factory default profile, like on 655491218
+    fn state_without_layer2_profile() -> State {
+        let mut state = State::get_keyboard_state(656801822).unwrap();
+        state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+        let layer = state.get_layer_mut(Layer::Layer2).unwrap();
+        layer.factory_default_model_data = None;
+        layer.key_set.fill(key_values::UNUSED_KEY_VALUE);
+        layer.fn_key_set.fill(key_values::UNUSED_KEY_VALUE);
+        state
+    }
+
+    #[test]
+    fn layers_without_a_profile_are_skipped() {
+        let issues = check(&state_without_layer2_profile());
+        assert!(
+            issues.iter().all(|issue| issue.layer != Layer::Layer2),
+            "{issues:?}"
+        );
+    }
+
+    #[test]
+    fn layers_without_a_profile_had_no_essential_keys() {
+        let mut state = state_without_layer2_profile();
+        state
+            .bind_key(
+                Layer::Layer2,
+                false,
+                &KeyAddress::LogicCode(1),
+                DriverValue::A as u32,
+            )
+            .unwrap();
+        let issues = check(&state);
+        assert!(
+            issues
+                .iter()
+                .all(|issue| !matches!(issue.kind, LayoutIssueKind::MissingKey(_))),
+            "{issues:?}"
+        );
+    }
+}
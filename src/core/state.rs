@@ -1,46 +1,44 @@
-use std::{
-    collections::HashMap,
-    fs::{self, File},
-    io::BufReader,
-    path::Path,
-};
+use std::{collections::HashMap, fmt, path::Path, sync::Arc};
 
-use lazy_static::lazy_static;
-use num::FromPrimitive;
-use parking_lot::Mutex;
-use serde::Deserialize;
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
 
 use super::{
-    key_values::{self, DriverValue},
+    key_action::KeyAction,
+    key_selector::KeySelector,
+    key_values::{self, DisableSet, DriverValue, DriverValueType},
+    model::ModelDefinition,
+    profile::{KeySetEntry, Profile},
+    variants::{self, DataVariant, DEFAULT_VARIANT},
     Layer,
 };
 
-lazy_static! {
-    pub static ref STATES_BY_MODEL_ID: Mutex<HashMap<u32, State>> = Mutex::new(HashMap::new());
-}
+/// The largest key buffer used by any supported firmware (logic codes / key set indices must be below this)
+pub const MAX_KEY_SET_SIZE: usize = 128;
+
+/// The key press lighting effects are indexed by short driver value, which is a single byte
+pub const KEY_PRESS_LIGHTING_EFFECT_SIZE: usize = 256;
 
 #[derive(Debug, Clone, Default)]
 pub struct State {
-    firmware_id: u32,
+    /// The (shared) data files of the model
+    model: Arc<ModelDefinition>,
+
     firmware_major_version: u8,
     firmware_minor_version: u8,
+    #[allow(dead_code)]
     firmware_version: u16,
 
-    model_id: u32,
-    model_name: String,
-
     layers: HashMap<Layer, StateLayer>,
     buffer_size_a: u8,
     buffer_size_b: u8,
 
-    keys_by_location_code: HashMap<i32, Key>,
-    keys_by_logic_code: HashMap<i32, Key>,
-    keys_by_driver_value: HashMap<u32, Key>,
-    /// A unique name for every key. Keys with duplicate DriverValue entries will be given seperate names here.
-    keys_by_driver_value_name: HashMap<String, Key>,
+    /// The default profile variant used for each layer
+    selected_profiles: HashMap<Layer, String>,
 }
 
 /// Getters
+#[allow(dead_code)]
 impl State {
     pub fn get_firmware_version(&self) -> u16 {
         (self.firmware_major_version as u16) << 8 | self.firmware_minor_version as u16
@@ -60,49 +58,73 @@ impl State {
     pub fn has_initialized_buffers(&self) -> bool {
         self.get_max_logic_code() > 0
     }
+
+    pub fn model(&self) -> &Arc<ModelDefinition> {
+        &self.model
+    }
+
+    pub fn data_path(&self) -> String {
+        self.model.data_path()
+    }
+
+    pub fn layout_variants(&self) -> &[DataVariant] {
+        &self.model.variants.layouts
+    }
+
+    pub fn profile_variants(&self, layer: Layer) -> Vec<&DataVariant> {
+        self.model.variants.profiles_for_layer(layer).collect()
+    }
+
+    pub fn selected_layout(&self) -> &str {
+        &self.model.layout
+    }
+
+    pub fn selected_profile(&self, layer: Layer) -> &str {
+        self.selected_profiles
+            .get(&layer)
+            .map(|name| name.as_str())
+            .unwrap_or(DEFAULT_VARIANT)
+    }
 }
 
+// The editing API of the GUI, the CLI commands only use part of it
+#[allow(dead_code)]
 impl State {
     pub fn new() -> Self {
         Self::default()
     }
 
-    pub fn load() {
-        //  string modelListPath = Path.Combine(Program.DataBasePath, "device", "modellist.json");
-
-        #[derive(Deserialize)]
-        struct DeviceModel {
-            #[serde(rename = "modelID")]
-            model_id: u32,
-            #[serde(rename = "firmwareID")]
-            firmware_id: String,
-            name: String,
-            #[serde(rename = "LEType")]
-            le_type: String,
-        }
-
-        let model_list_path = "assets/device/models.json";
-        // serde deserialize
-        let models = serde_json::from_reader::<_, Vec<DeviceModel>>(BufReader::new(
-            File::open(model_list_path).unwrap(),
-        ))
-        .unwrap();
+    /// Creates the state for a connected keyboard. Only the files of the given model are loaded (once, and then
+    /// shared with any other keyboard of the same model).
+    pub fn get_keyboard_state(model_id: u32) -> Option<State> {
+        Some(State {
+            model: ModelDefinition::get(model_id)?,
+            ..Default::default()
+        })
+    }
 
-        for model in models {
-            let mut keyboard_state = State::new();
-            keyboard_state.model_id = model.model_id;
-            // parse firmware_id as "0x1234" to u32
-            keyboard_state.firmware_id = u32::from_str_radix(&model.firmware_id, 16).unwrap();
-            keyboard_state.model_name = model.name;
-            keyboard_state.load_keys();
-            STATES_BY_MODEL_ID
-                .lock()
-                .insert(model.model_id, keyboard_state);
+    /// Picks which layout (keymap) matches the physical board, see [`State::layout_variants`]
+    pub fn select_layout(&mut self, name: &str) -> bool {
+        match ModelDefinition::get_with_layout(self.model.model_id, name) {
+            Some(model) => {
+                self.model = model;
+                true
+            }
+            None => false,
         }
     }
 
-    pub fn get_keyboard_state(model_id: u32) -> State {
-        STATES_BY_MODEL_ID.lock().get(&model_id).unwrap().clone()
+    /// Picks which default profile applies to a layer, see [`State::profile_variants`]. The layer is rebuilt from the
+    /// new profile if the buffers have already been initialized.
+    pub fn select_profile(&mut self, layer: Layer, name: &str) -> bool {
+        if self.model.variants.profile(layer, name).is_none() {
+            return false;
+        }
+        self.selected_profiles.insert(layer, name.to_string());
+        if self.has_initialized_buffers() {
+            self.create_layer(layer, None);
+        }
+        true
     }
 
     pub fn initialize_buffers(&mut self, size_a: u8, size_b: u8) {
@@ -111,7 +133,7 @@ impl State {
         self.buffer_size_a = 0;
         self.buffer_size_b = 0;
 
-        if self.firmware_id == 0 || self.model_id == 0 {
+        if self.model.firmware_id == 0 || self.model.model_id == 0 {
             return;
         }
 
@@ -129,37 +151,36 @@ impl State {
         self.create_layer(Layer::Layer3, None);
     }
 
-    pub fn create_layer(
-        &mut self,
-        layer: Layer,
-        model_data: Option<HashMap<String, serde_json::Value>>,
-    ) {
-        self.layers.insert(layer, StateLayer::new());
-        let state = self.layers.get_mut(&layer).unwrap();
-
-        let mut profile_layer_name = "";
-
-        match layer {
-            Layer::Driver => profile_layer_name = "_online_1",
-            Layer::Layer1 => profile_layer_name = "_offline_1",
-            Layer::Layer2 => profile_layer_name = "_offline_2",
-            Layer::Layer3 => profile_layer_name = "_offline_3",
-            _ => {}
+    pub fn create_layer(&mut self, layer: Layer, model_data: Option<Profile>) {
+        let profile_path = match self.selected_profiles.get(&layer) {
+            Some(name) => self
+                .model
+                .variants
+                .profile(layer, name)
+                .map(|variant| variant.path.clone()),
+            None => None,
         }
+        .unwrap_or_else(|| format!("data/profile{}.json", variants::profile_layer_suffix(layer)));
 
-        let profile_path = format!(
-            "assets/device/{}/data/profile{}.json",
-            self.model_id, profile_layer_name
-        );
+        let profile_path = format!("{}/{}", self.model.path(), profile_path);
+
+        self.layers.insert(layer, StateLayer::new());
+        let state = self.layers.get_mut(&layer).unwrap();
 
         if Path::new(&profile_path).exists() {
-            let profile = serde_json::from_reader::<_, HashMap<String, serde_json::Value>>(
-                BufReader::new(File::open(profile_path).unwrap()),
-            )
-            .unwrap();
-            state.factory_default_model_data = Some(profile);
+            match Profile::from_file(&profile_path) {
+                Ok(profile) => state.factory_default_model_data = Some(profile),
+                Err(err) => log::warn!("Failed to load profile `{profile_path}`: {err}"),
+            }
         }
 
+        // The buffers are sized even without model data (no factory profile for the layer), so every logic code
+        // below the key buffer size can be read and edited
+        let size = self.buffer_size_a as usize * self.buffer_size_b as usize;
+        state.key_set = vec![key_values::UNUSED_KEY_VALUE; size];
+        state.fn_key_set = vec![key_values::UNUSED_KEY_VALUE; size];
+        state.key_press_lighting_effect = vec![0xFF; KEY_PRESS_LIGHTING_EFFECT_SIZE];
+
         let model_data = if let Some(model_data) = model_data {
             model_data
         } else {
@@ -170,172 +191,422 @@ impl State {
             }
         };
 
-        let mut key_set = vec![
-            key_values::UNUSED_KEY_VALUE;
-            self.buffer_size_a as usize * self.buffer_size_b as usize
-        ];
-        let mut fn_key_set = vec![
-            key_values::UNUSED_KEY_VALUE;
-            self.buffer_size_a as usize * self.buffer_size_b as usize
-        ];
-
-        state.key_press_lighting_effect = vec![0xFF; 128];
+        if let Some(ref entries) = model_data.key_set {
+            setup_driver_key_set_buffer(&expand_key_set(entries, &self.model), &mut state.key_set);
+        }
+        if let Some(ref entries) = model_data.fn_key_set {
+            setup_driver_key_set_buffer(
+                &expand_key_set(entries, &self.model),
+                &mut state.fn_key_set,
+            );
+        }
 
-        std::mem::drop(state);
+        if model_data.device_le.is_some() {
+            state.has_le_set = true;
+        }
+    }
 
-        self.setup_driver_key_set_buffer(&model_data, "KeySet", &mut key_set);
-        self.setup_driver_key_set_buffer(&model_data, "FnKeySet", &mut fn_key_set);
+    pub fn get_layer(&self, layer: Layer) -> Option<&StateLayer> {
+        self.layers.get(&layer)
+    }
 
-        let state = self.layers.get_mut(&layer).unwrap();
+    pub fn get_layer_mut(&mut self, layer: Layer) -> Option<&mut StateLayer> {
+        self.layers.get_mut(&layer)
+    }
 
-        state.key_set = key_set;
-        state.fn_key_set = fn_key_set;
+    /// The logic codes a selector stands for on this model
+    pub fn expand_selector(&self, selector: KeySelector) -> Vec<i32> {
+        selector.expand(&self.model)
+    }
 
-        if let Some(device_le_data) = model_data.get("DeviceLE") {
-            if let Some(_) = device_le_data.get("LESet") {
-                state.has_le_set = true;
+    /// The logic codes a key address refers to, checked against the key buffer
+    pub fn resolve_keys(&self, address: &KeyAddress) -> Result<Vec<i32>, LayerEditError> {
+        let unknown = || LayerEditError::UnknownKey(address.to_string());
+        let logic_codes = match address {
+            KeyAddress::LogicCode(logic_code) => vec![*logic_code],
+            KeyAddress::LocationCode(location_code) => {
+                vec![
+                    self.model
+                        .get_key_at_location_code(*location_code)
+                        .ok_or_else(unknown)?
+                        .logic_code,
+                ]
+            }
+            KeyAddress::Name(name) => {
+                vec![
+                    self.model
+                        .get_key_by_name(name)
+                        .ok_or_else(unknown)?
+                        .logic_code,
+                ]
+            }
+            KeyAddress::DriverValueName(name) => {
+                vec![
+                    self.model
+                        .get_key_by_driver_value_name(name)
+                        .ok_or_else(unknown)?
+                        .logic_code,
+                ]
             }
+            KeyAddress::Selector(selector) => selector.expand(&self.model),
+        };
+
+        let size = self.key_buffer_size();
+        if let Some(&logic_code) = logic_codes
+            .iter()
+            .find(|&&logic_code| logic_code < 0 || logic_code as usize >= size)
+        {
+            return Err(LayerEditError::OutOfRange { logic_code, size });
         }
+        Ok(logic_codes)
+    }
+
+    /// Gets the values bound to a key (several for a [`KeySelector`])
+    pub fn get_keys(
+        &self,
+        layer: Layer,
+        fn_layer: bool,
+        address: &KeyAddress,
+    ) -> Result<Vec<KeyBinding>, LayerEditError> {
+        let logic_codes = self.resolve_keys(address)?;
+        let state = self
+            .layers
+            .get(&layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
+        let key_set = state.key_set(fn_layer);
+        Ok(logic_codes
+            .into_iter()
+            .map(|logic_code| KeyBinding {
+                logic_code,
+                driver_value: key_set[logic_code as usize],
+            })
+            .collect())
     }
 
-    fn setup_driver_key_set_buffer(
+    /// Binds a value to a key (or every key of a [`KeySelector`]). Returns the keys which changed.
+    pub fn bind_key(
         &mut self,
-        model_data: &HashMap<String, serde_json::Value>,
-        key: &str,
-        driver_key_set_array: &mut Vec<u32>,
-    ) {
-        if let Some(key_set_data) = model_data.get(key) {
-            for key_set_obj in key_set_data.as_array().unwrap() {
-                let key_set = key_set_obj.as_object().unwrap();
-                let index = key_set.get("Index").unwrap().as_i64().unwrap();
-                let mut driver_value =
-                    i32::from_str_radix(key_set.get("DriverValue").unwrap().as_str().unwrap(), 16)
-                        .unwrap();
-                if (index as usize) < driver_key_set_array.len() {
-                    if driver_value == 0xA030001 || driver_value == 0xa010001 {
-                        driver_value += index as i32;
-                    }
-                }
-                driver_key_set_array[index as usize] = driver_value as u32;
-            }
+        layer: Layer,
+        fn_layer: bool,
+        address: &KeyAddress,
+        driver_value: u32,
+    ) -> Result<Vec<KeyChange>, LayerEditError> {
+        if KeySelector::from_driver_value(driver_value).is_some() {
+            return Err(LayerEditError::SelectorValue(driver_value));
         }
+        let logic_codes = self.resolve_keys(address)?;
+        let state = self
+            .layers
+            .get_mut(&layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
+        Ok(state.set_keys(
+            fn_layer,
+            logic_codes
+                .into_iter()
+                .map(|logic_code| (logic_code, driver_value)),
+        ))
     }
 
-    fn get_default_profile_driver_values(&mut self) -> Option<Vec<u32>> {
-        let profile_path = format!("assets/device/{}/data/profile.json", self.model_id);
-        if Path::new(&profile_path).exists() {
-            let model_data = serde_json::from_reader::<_, HashMap<String, serde_json::Value>>(
-                BufReader::new(File::open(profile_path).unwrap()),
-            )
-            .unwrap();
-            let mut result = vec![key_values::UNUSED_KEY_VALUE; i16::MAX as usize];
-            self.setup_driver_key_set_buffer(&model_data, "KeySet", &mut result);
-            return Some(result);
-        }
-        return None;
+    /// Restores a key (or every key of a [`KeySelector`]) to the value of the layer's factory default profile, keys
+    /// which the profile doesn't bind become unused. Returns the keys which changed.
+    pub fn clear_key(
+        &mut self,
+        layer: Layer,
+        fn_layer: bool,
+        address: &KeyAddress,
+    ) -> Result<Vec<KeyChange>, LayerEditError> {
+        let logic_codes = self.resolve_keys(address)?;
+        let state = self
+            .layers
+            .get_mut(&layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
+        let factory_key_set = state.factory_key_set(fn_layer, &self.model);
+        Ok(state.set_keys(
+            fn_layer,
+            logic_codes
+                .into_iter()
+                .map(|logic_code| (logic_code, factory_key_set[logic_code as usize])),
+        ))
     }
 
-    fn load_keys(&mut self) {
-        self.keys_by_location_code.clear();
-        self.keys_by_logic_code.clear();
-        self.keys_by_driver_value.clear();
-        self.keys_by_driver_value_name.clear();
+    /// The number of values in a layer's key buffer, logic codes must be below this
+    pub fn key_buffer_size(&self) -> usize {
+        self.buffer_size_a as usize * self.buffer_size_b as usize
+    }
 
-        let driver_values = self.get_default_profile_driver_values().unwrap();
+    /// Binds a multi key disable entry of `keys.json` ("disable_num") to a layer. Keys are matched by their default
+    /// value, so the set disables the same physical keys on the normal and Fn layers. [`DisableSet::Enable`] restores
+    /// the factory default of every disabled key. Returns the logic codes which changed.
+    pub fn apply_disable_set(
+        &mut self,
+        layer: Layer,
+        fn_layer: bool,
+        disable_set: DisableSet,
+    ) -> Result<Vec<KeyChange>, LayerEditError> {
+        let size = self.key_buffer_size();
+        let mut keys = self
+            .model
+            .keys()
+            .filter(|key| (0..size as i32).contains(&key.logic_code))
+            .map(|key| (key.logic_code, key.driver_value))
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+        let state = self
+            .layers
+            .get_mut(&layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
 
-        let keys_path = format!("assets/device/{}/data/keymap.js", self.model_id);
-        if !Path::new(&keys_path).exists() {
-            return;
+        let disabled = DriverValue::Disabled as u32;
+        if disable_set == DisableSet::Enable {
+            let factory_key_set = state.factory_key_set(fn_layer, &self.model);
+            let key_set = state.key_set(fn_layer);
+            let values = keys
+                .iter()
+                .map(|&(logic_code, _)| (logic_code, factory_key_set[logic_code as usize]))
+                .filter(|&(logic_code, factory_value)| {
+                    key_set[logic_code as usize] == disabled && factory_value != disabled
+                })
+                .collect::<Vec<_>>();
+            return Ok(state.set_keys(fn_layer, values));
         }
 
-        let text = fs::read_to_string(&keys_path).unwrap();
-        if text.replace(' ', "").contains("KeyName:") {
-            log::debug!("TODO: Handle keymap which is declared as JS `{keys_path}`");
-            return;
-        }
+        let logic_codes = keys
+            .iter()
+            .filter(|&&(_, default_value)| disable_set.contains(default_value))
+            .map(|&(logic_code, _)| logic_code)
+            .collect::<Vec<_>>();
+        let changes = state.set_keys(
+            fn_layer,
+            logic_codes.iter().map(|&logic_code| (logic_code, disabled)),
+        );
+        let disabled_by = if fn_layer {
+            &mut state.fn_disabled_by
+        } else {
+            &mut state.disabled_by
+        };
+        disabled_by.extend(
+            logic_codes
+                .into_iter()
+                .map(|logic_code| (logic_code, disable_set)),
+        );
+        Ok(changes)
+    }
 
-        let mut device_keys = serde_json::from_str::<Vec<Key>>(&text).unwrap();
-        for key in device_keys.iter_mut() {
-            if key.logic_code >= 0
-                && driver_values[key.logic_code as usize] != key_values::UNUSED_KEY_VALUE
-            {
-                key.driver_value = driver_values[key.logic_code as usize];
-            } else {
-                if key.logic_code > 0 {
-                    if let Some(all_keys_key) = key_values::KEYS_BY_LOGIC_CODE.get(&key.logic_code)
-                    {
-                        key.driver_value = all_keys_key.driver_value;
-                    } else {
-                        log::debug!("Couldn't find DriverValue for key `{}` logicCode: {} locationCode: {} modelId: {} modelName: {}", key.key_name, key.logic_code, key.location_code, self.model_id, self.model_name);
-                    }
-                } else {
-                    key.driver_value = key_values::UNUSED_KEY_VALUE;
-                }
-            }
+    /// Copies the selected contents of a layer onto another one. Returns the keys of `to` which changed.
+    pub fn copy_layer(
+        &mut self,
+        from: Layer,
+        to: Layer,
+        contents: LayerContents,
+    ) -> Result<Vec<KeyChange>, LayerEditError> {
+        let source = self
+            .layers
+            .get(&from)
+            .ok_or(LayerEditError::NoLayer(from))?
+            .clone();
+        let state = self
+            .layers
+            .get_mut(&to)
+            .ok_or(LayerEditError::NoLayer(to))?;
+        Ok(state.copy_from(&source, contents))
+    }
 
-            self.keys_by_location_code
-                .insert(key.location_code, key.clone());
-            self.keys_by_logic_code.insert(key.logic_code, key.clone());
-            self.keys_by_driver_value
-                .insert(key.driver_value, key.clone());
-
-            for i in 1..i32::MAX {
-                let driver_value_name = format!(
-                    "{}{}",
-                    DriverValue::from_u32(key.driver_value).unwrap(),
-                    if i > 1 {
-                        format!("_{}", i)
-                    } else {
-                        "".to_string()
-                    }
-                );
-                if !self
-                    .keys_by_driver_value_name
-                    .contains_key(&driver_value_name)
-                {
-                    key.driver_value_name = driver_value_name.clone();
-                    self.keys_by_driver_value_name
-                        .insert(driver_value_name, key.clone());
-                    break;
-                }
-            }
+    /// Swaps the selected contents of two layers. Returns the keys which changed on `a` and on `b`.
+    pub fn swap_layers(
+        &mut self,
+        a: Layer,
+        b: Layer,
+        contents: LayerContents,
+    ) -> Result<(Vec<KeyChange>, Vec<KeyChange>), LayerEditError> {
+        let layer_a = self
+            .layers
+            .get(&a)
+            .ok_or(LayerEditError::NoLayer(a))?
+            .clone();
+        let layer_b = self
+            .layers
+            .get(&b)
+            .ok_or(LayerEditError::NoLayer(b))?
+            .clone();
+        let changes_a = self
+            .layers
+            .get_mut(&a)
+            .unwrap()
+            .copy_from(&layer_b, contents);
+        let changes_b = self
+            .layers
+            .get_mut(&b)
+            .unwrap()
+            .copy_from(&layer_a, contents);
+        Ok((changes_a, changes_b))
+    }
+
+    /// Copies the selected contents of the factory default profile of `from` (see
+    /// [`StateLayer::factory_default_model_data`]) onto `to`, pass the same layer twice to reset it. Returns the keys
+    /// of `to` which changed.
+    pub fn copy_factory_default(
+        &mut self,
+        from: Layer,
+        to: Layer,
+        contents: LayerContents,
+    ) -> Result<Vec<KeyChange>, LayerEditError> {
+        let source = self
+            .layers
+            .get(&from)
+            .ok_or(LayerEditError::NoLayer(from))?
+            .factory_default(&self.model);
+        let state = self
+            .layers
+            .get_mut(&to)
+            .ok_or(LayerEditError::NoLayer(to))?;
+        Ok(state.copy_from(&source, contents))
+    }
+
+    /// Restores every key of a layer's key set (or Fn key set), including keys bound to macros, to the factory
+    /// default profile, like [`LayerDataType::KeySet`](super::opcodes::LayerDataType::KeySet) does on the keyboard.
+    /// Returns the keys which changed.
+    pub fn reset_key_set(
+        &mut self,
+        layer: Layer,
+        fn_layer: bool,
+    ) -> Result<Vec<KeyChange>, LayerEditError> {
+        let state = self
+            .layers
+            .get_mut(&layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
+        let factory_key_set = state.factory_key_set(fn_layer, &self.model);
+        Ok(state.set_keys(
+            fn_layer,
+            factory_key_set
+                .into_iter()
+                .enumerate()
+                .map(|(logic_code, driver_value)| (logic_code as i32, driver_value)),
+        ))
+    }
+
+    /// Lists every key of a layer whose binding differs from the layer's factory default profile (see
+    /// [`StateLayer::factory_default_model_data`]), `old` being the profile's value. Keys the profile doesn't bind are
+    /// compared against [`key_values::UNUSED_KEY_VALUE`].
+    pub fn diff_factory_default(&self, layer: Layer) -> Result<Vec<KeyChange>, LayerEditError> {
+        let state = self
+            .layers
+            .get(&layer)
+            .ok_or(LayerEditError::NoLayer(layer))?;
+        let mut changes = vec![];
+        for fn_layer in [false, true] {
+            let factory_key_set = state.factory_key_set(fn_layer, &self.model);
+            changes.extend(
+                factory_key_set
+                    .iter()
+                    .zip(state.key_set(fn_layer))
+                    .enumerate()
+                    .filter(|(_, (old, new))| old != new)
+                    .map(|(logic_code, (&old, &new))| KeyChange {
+                        logic_code: logic_code as i32,
+                        fn_layer,
+                        old,
+                        new,
+                    }),
+            );
         }
+        Ok(changes)
     }
 
-    pub fn get_layer(&self, layer: Layer) -> Option<&StateLayer> {
-        self.layers.get(&layer)
+    /// Formats a change with the key's name and both actions (`Fn+Escape: Escape -> Macro(3)`)
+    pub fn describe_change(&self, change: &KeyChange) -> String {
+        let key_name = match self.model.get_key_by_logic_code(change.logic_code) {
+            Some(key) => key.key_name.clone(),
+            None => format!("logic code {}", change.logic_code),
+        };
+        format!(
+            "{}{key_name}: {} -> {}",
+            if change.fn_layer { "Fn+" } else { "" },
+            KeyAction::from_u32(change.old),
+            KeyAction::from_u32(change.new)
+        )
     }
 
     pub fn get_key_at_location_code(&self, location_code: i32) -> Option<&Key> {
-        self.keys_by_location_code.get(&location_code)
+        self.model.get_key_at_location_code(location_code)
     }
 
     pub fn get_key_by_logic_code(&self, logic_code: i32) -> Option<&Key> {
-        self.keys_by_logic_code.get(&logic_code)
+        self.model.get_key_by_logic_code(logic_code)
+    }
+}
+
+/// Replaces the entries of a `KeySet` / `FnKeySet` which use a [`KeySelector`] with an entry per key of the model.
+/// Later entries still win, so `All` followed by single keys binds everything but those keys.
+pub fn expand_key_set(key_set: &[KeySetEntry], model: &ModelDefinition) -> Vec<KeySetEntry> {
+    let mut result = Vec::with_capacity(key_set.len());
+    for entry in key_set {
+        match entry.key_selector() {
+            Ok(None) => result.push(entry.clone()),
+            Ok(Some(selector)) => result.extend(selector.expand(model).into_iter().map(
+                |logic_code| KeySetEntry {
+                    index: logic_code,
+                    selector: None,
+                    ..entry.clone()
+                },
+            )),
+            Err(err) => log::warn!("Skipping KeySet entry: {err}"),
+        }
     }
+    result
 }
 
-#[derive(Debug, Clone, Default, Deserialize)]
+/// Fills a key buffer (indexed by logic code) from the `KeySet` / `FnKeySet` of a profile, entries with a selector
+/// have to be expanded first (see [`expand_key_set`])
+pub fn setup_driver_key_set_buffer(key_set: &[KeySetEntry], driver_key_set_array: &mut [u32]) {
+    for entry in key_set {
+        let index = entry.index;
+        if let Some(ref selector) = entry.selector {
+            log::warn!("KeySet selector `{selector}` wasn't expanded");
+            continue;
+        }
+        let Some(mut driver_value) = entry.driver_value() else {
+            log::warn!(
+                "Invalid DriverValue `{}` at index {index}",
+                entry.driver_value
+            );
+            continue;
+        };
+        if index < 0 || index as usize >= driver_key_set_array.len() {
+            log::warn!("KeySet index {index} is outside of the key buffer");
+            continue;
+        }
+        if driver_value == 0xA030001 || driver_value == 0xa010001 {
+            driver_value += index as u32;
+        }
+        driver_key_set_array[index as usize] = driver_value;
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Key {
     pub key_name: String,
+    #[serde(default)]
     pub show: String,
     pub logic_code: i32,
     pub location_code: i32,
     pub position: KeyRect,
+    #[serde(default, skip_serializing)]
     pub driver_value: u32,
 
     /// Unique for a given key, even if there are keys with duplicate driver values
+    #[serde(default, skip_serializing)]
     pub driver_value_name: String,
 }
 
 impl Key {
+    #[allow(dead_code)]
     pub fn new() -> Self {
         Self::default()
     }
 }
 
-#[derive(Debug, Clone, Default, Copy, Deserialize)]
+#[derive(Debug, Clone, Default, Copy, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct KeyRect {
     pub left: i32,
@@ -344,19 +615,606 @@ pub struct KeyRect {
     pub height: i32,
 }
 
+/// A key of the model, as given to the layer editing methods of [`State`]
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyAddress {
+    LogicCode(i32),
+    LocationCode(i32),
+    /// The `KeyName` of the keymap ("Escape"), ignoring case
+    Name(String),
+    /// See [`Key::driver_value_name`]
+    DriverValueName(String),
+    /// Several keys at once
+    Selector(KeySelector),
+}
+
+impl fmt::Display for KeyAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyAddress::LogicCode(logic_code) => write!(f, "logic code {logic_code}"),
+            KeyAddress::LocationCode(location_code) => write!(f, "location code {location_code}"),
+            KeyAddress::Name(name) => write!(f, "`{name}`"),
+            KeyAddress::DriverValueName(name) => write!(f, "`{name}`"),
+            KeyAddress::Selector(selector) => write!(f, "{selector}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LayerEditError {
+    /// The model doesn't have the key
+    UnknownKey(String),
+    /// The logic code is outside of the key buffer (or the buffers haven't been initialized)
+    OutOfRange { logic_code: i32, size: usize },
+    /// The layer hasn't been created yet
+    NoLayer(Layer),
+    /// A [`KeySelector`] value ([`DriverValue::All`], ...), which stands for keys rather than being something a key
+    /// can be bound to
+    SelectorValue(u32),
+}
+
+impl fmt::Display for LayerEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LayerEditError::UnknownKey(key) => write!(f, "the model doesn't have the key {key}"),
+            LayerEditError::OutOfRange { logic_code, size } => write!(
+                f,
+                "logic code {logic_code} is outside of the key buffer (size {size})"
+            ),
+            LayerEditError::NoLayer(layer) => write!(f, "layer {layer:?} hasn't been created"),
+            LayerEditError::SelectorValue(driver_value) => write!(
+                f,
+                "0x{driver_value:08X} selects keys, it can't be bound to a key"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayerEditError {}
+
+/// The value bound to a key of a layer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub logic_code: i32,
+    pub driver_value: u32,
+}
+
+/// A key of a layer which was rebound
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChange {
+    pub logic_code: i32,
+    pub fn_layer: bool,
+    pub old: u32,
+    pub new: u32,
+}
+
+bitflags! {
+    /// The parts of a layer copied by [`State::copy_layer`], [`State::swap_layers`] and
+    /// [`State::copy_factory_default`]
+    pub struct LayerContents: u8 {
+        /// The key set, except keys bound to macros
+        const KEY_SET = 0x01;
+        /// The Fn key set, except keys bound to macros
+        const FN_KEY_SET = 0x02;
+        /// Keys bound to macros (on either layer) of both key sets
+        const MACROS = 0x04;
+        /// The key press lighting effects
+        const LIGHTING = 0x08;
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct StateLayer {
-    pub factory_default_model_data: Option<HashMap<String, serde_json::Value>>,
+    pub factory_default_model_data: Option<Profile>,
     pub key_set: Vec<u32>,
     pub fn_key_set: Vec<u32>,
     pub key_press_lighting_effect: Vec<u8>,
 
     /// Only used for "driver" layer? (17 01) see [`OpCodes::DriverLayerSetConfig`]
     pub has_le_set: bool,
+
+    /// The multi key disable which disabled a key, by logic code (see [`State::apply_disable_set`])
+    pub disabled_by: HashMap<i32, DisableSet>,
+    pub fn_disabled_by: HashMap<i32, DisableSet>,
 }
 
 impl StateLayer {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// The multi key disable which disabled a key, if it's still disabled
+    pub fn disabled_by(&self, fn_layer: bool, logic_code: i32) -> Option<DisableSet> {
+        let (key_set, disabled_by) = if fn_layer {
+            (&self.fn_key_set, &self.fn_disabled_by)
+        } else {
+            (&self.key_set, &self.disabled_by)
+        };
+        let disable_set = *disabled_by.get(&logic_code)?;
+        let value = *key_set.get(logic_code as usize)?;
+        (value == DriverValue::Disabled as u32).then_some(disable_set)
+    }
+
+    /// The catalog entry a disabled key came from ("禁用数字键"), see [`StateLayer::disabled_by`]
+    #[allow(dead_code)]
+    pub fn disabled_by_key(&self, fn_layer: bool, logic_code: i32) -> Option<key_values::Key> {
+        let disable_set = self.disabled_by(fn_layer, logic_code)?;
+        key_values::CATALOG
+            .find_by_disable_set(disable_set)
+            .cloned()
+    }
+
+    /// The key set of the factory default profile, indexed by logic code. `model` is the layer's model, which
+    /// [`KeySelector`] entries are expanded with.
+    pub fn factory_key_set(&self, fn_layer: bool, model: &ModelDefinition) -> Vec<u32> {
+        let size = self.key_set.len();
+        let mut result = vec![key_values::UNUSED_KEY_VALUE; size];
+        let entries = self
+            .factory_default_model_data
+            .as_ref()
+            .and_then(|profile| {
+                if fn_layer {
+                    profile.fn_key_set.as_ref()
+                } else {
+                    profile.key_set.as_ref()
+                }
+            });
+        if let Some(entries) = entries {
+            setup_driver_key_set_buffer(&expand_key_set(entries, model), &mut result);
+        }
+        result
+    }
+
+    /// Whether no key of either key set is bound (a layer without a factory profile, for instance)
+    pub fn is_empty(&self) -> bool {
+        self.key_set
+            .iter()
+            .chain(&self.fn_key_set)
+            .all(|&value| value == key_values::UNUSED_KEY_VALUE)
+    }
+
+    pub fn key_set(&self, fn_layer: bool) -> &[u32] {
+        if fn_layer {
+            &self.fn_key_set
+        } else {
+            &self.key_set
+        }
+    }
+
+    /// Sets the values of keys by logic code (which must be in the buffer), returns the ones which changed. Keys
+    /// which are rebound lose their [`StateLayer::disabled_by`].
+    fn set_keys(
+        &mut self,
+        fn_layer: bool,
+        values: impl IntoIterator<Item = (i32, u32)>,
+    ) -> Vec<KeyChange> {
+        let (key_set, disabled_by) = if fn_layer {
+            (&mut self.fn_key_set, &mut self.fn_disabled_by)
+        } else {
+            (&mut self.key_set, &mut self.disabled_by)
+        };
+        let mut changes = vec![];
+        for (logic_code, driver_value) in values {
+            let value = &mut key_set[logic_code as usize];
+            if *value == driver_value {
+                continue;
+            }
+            disabled_by.remove(&logic_code);
+            changes.push(KeyChange {
+                logic_code,
+                fn_layer,
+                old: *value,
+                new: driver_value,
+            });
+            *value = driver_value;
+        }
+        changes
+    }
+
+    /// A layer with the contents of the factory default profile (key press lighting effects are cleared, like in
+    /// [`State::create_layer`])
+    fn factory_default(&self, model: &ModelDefinition) -> StateLayer {
+        let has_le_set = self
+            .factory_default_model_data
+            .as_ref()
+            .is_some_and(|profile| profile.device_le.is_some());
+        StateLayer {
+            factory_default_model_data: self.factory_default_model_data.clone(),
+            key_set: self.factory_key_set(false, model),
+            fn_key_set: self.factory_key_set(true, model),
+            key_press_lighting_effect: vec![0xFF; KEY_PRESS_LIGHTING_EFFECT_SIZE],
+            has_le_set,
+            ..Default::default()
+        }
+    }
+
+    /// Copies the selected contents of another layer, returns the keys which changed. Disabled keys keep the multi
+    /// key disable they came from.
+    fn copy_from(&mut self, source: &StateLayer, contents: LayerContents) -> Vec<KeyChange> {
+        let is_macro = |driver_value: u32| {
+            KeyAction::from_u32(driver_value).value_type() == DriverValueType::Macro
+        };
+
+        let mut changes = vec![];
+        for (fn_layer, flag) in [
+            (false, LayerContents::KEY_SET),
+            (true, LayerContents::FN_KEY_SET),
+        ] {
+            let values = source
+                .key_set(fn_layer)
+                .iter()
+                .zip(self.key_set(fn_layer))
+                .enumerate()
+                .filter(|&(_, (&value, &current))| {
+                    if is_macro(value) || is_macro(current) {
+                        contents.contains(LayerContents::MACROS)
+                    } else {
+                        contents.contains(flag)
+                    }
+                })
+                .map(|(logic_code, (&value, _))| (logic_code as i32, value))
+                .collect::<Vec<_>>();
+
+            changes.extend(self.set_keys(fn_layer, values.iter().copied()));
+            let disabled_by = if fn_layer {
+                &mut self.fn_disabled_by
+            } else {
+                &mut self.disabled_by
+            };
+            for (logic_code, _) in values {
+                match source.disabled_by(fn_layer, logic_code) {
+                    Some(disable_set) => disabled_by.insert(logic_code, disable_set),
+                    None => disabled_by.remove(&logic_code),
+                };
+            }
+        }
+
+        if contents.contains(LayerContents::LIGHTING) {
+            self.key_press_lighting_effect = source.key_press_lighting_effect.clone();
+            self.has_le_set = source.has_le_set;
+        }
+        changes
+    }
+
+    /// Gets the key press lighting effect of a key (0xFF = none)
+    #[allow(dead_code)]
+    pub fn get_key_press_lighting_effect(&self, driver_value: u32) -> Option<u8> {
+        let index = key_values::short_driver_value(driver_value)? as usize;
+        self.key_press_lighting_effect.get(index).copied()
+    }
+
+    /// Sets the key press lighting effect of a key. Returns false for values without a short driver value.
+    #[allow(dead_code)]
+    pub fn set_key_press_lighting_effect(&mut self, driver_value: u32, effect: u8) -> bool {
+        let Some(index) = key_values::short_driver_value(driver_value) else {
+            return false;
+        };
+        match self.key_press_lighting_effect.get_mut(index as usize) {
+            Some(value) => {
+                *value = effect;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(model_id: u32) -> State {
+        let mut state = State::get_keyboard_state(model_id).unwrap();
+        state.initialize_buffers(1, MAX_KEY_SET_SIZE as u8);
+        state
+    }
+
+    #[test]
+    fn selector_values_cannot_be_bound() {
+        let mut state = state(656801822);
+        for driver_value in [
+            DriverValue::All as u32,
+            DriverValue::AllExceptModifiers as u32,
+            0xFE010002,
+        ] {
+            let result =
+                state.bind_key(Layer::Base, false, &KeyAddress::LogicCode(1), driver_value);
+            assert!(
+                matches!(result, Err(LayerEditError::SelectorValue(value)) if value == driver_value)
+            );
+        }
+    }
+
+    #[test]
+    fn factory_key_set_expands_selectors() {
+        let mut state = state(656801822);
+        let model = state.model.clone();
+        let layer = state.layers.get_mut(&Layer::Base).unwrap();
+        let profile = layer.factory_default_model_data.as_mut().unwrap();
+        profile.key_set = Some(vec![KeySetEntry {
+            selector: Some("All".to_string()),
+            driver_value: "0x02000400".to_string(),
+            ..Default::default()
+        }]);
+
+        let key_set = layer.factory_key_set(false, &model);
+        let logic_codes = KeySelector::All.expand(&model);
+        assert!(!logic_codes.is_empty());
+        for (logic_code, &value) in key_set.iter().enumerate() {
+            let expected = match logic_codes.contains(&(logic_code as i32)) {
+                true => 0x02000400,
+                false => key_values::UNUSED_KEY_VALUE,
+            };
+            assert_eq!(value, expected, "logic code {logic_code}");
+        }
+    }
+
+    #[test]
+    fn gbk_profiles_are_loaded() {
+        // 656801803's `profile_offline_1.json` and `profile_offline_2.json` have GBK encoded names
+        let state = state(656801803);
+        for layer in [Layer::Layer1, Layer::Layer2] {
+            let layer = state.get_layer(layer).unwrap();
+            assert!(layer.factory_default_model_data.is_some());
+        }
+    }
+
+    #[test]
+    fn layers_without_a_profile_can_be_edited() {
+        // 655491218 has no `profile_offline_2.json`
+        let mut state = state(655491218);
+        let layer = state.get_layer(Layer::Layer2).unwrap();
+        assert!(layer.factory_default_model_data.is_none());
+        assert_eq!(layer.key_set.len(), state.key_buffer_size());
+        assert_eq!(layer.fn_key_set.len(), state.key_buffer_size());
+
+        let address = KeyAddress::LogicCode(state.key_buffer_size() as i32 - 1);
+        let value = DriverValue::A as u32;
+        for fn_layer in [false, true] {
+            assert_eq!(
+                state
+                    .bind_key(Layer::Layer2, fn_layer, &address, value)
+                    .unwrap()
+                    .len(),
+                1
+            );
+            let keys = state.get_keys(Layer::Layer2, fn_layer, &address).unwrap();
+            assert_eq!(keys[0].driver_value, value);
+            assert_eq!(
+                state
+                    .clear_key(Layer::Layer2, fn_layer, &address)
+                    .unwrap()
+                    .len(),
+                1
+            );
+            let keys = state.get_keys(Layer::Layer2, fn_layer, &address).unwrap();
+            assert_eq!(keys[0].driver_value, key_values::UNUSED_KEY_VALUE);
+        }
+    }
+
+    #[test]
+    fn reset_key_set_restores_macro_keys_of_one_key_set() {
+        let mut state = state(656801822);
+        let address = KeyAddress::LogicCode(1);
+        let macro_value = KeyAction::Macro { index: 3 }.to_u32();
+        let factory_value = state.get_layer(Layer::Base).unwrap().key_set[1];
+        for fn_layer in [false, true] {
+            state
+                .bind_key(Layer::Base, fn_layer, &address, macro_value)
+                .unwrap();
+        }
+
+        let changes = state.reset_key_set(Layer::Base, false).unwrap();
+        assert_eq!(
+            changes,
+            [KeyChange {
+                logic_code: 1,
+                fn_layer: false,
+                old: macro_value,
+                new: factory_value,
+            }]
+        );
+        let layer = state.get_layer(Layer::Base).unwrap();
+        assert_eq!(layer.fn_key_set[1], macro_value);
+    }
+
+    #[test]
+    fn disable_sets_disable_and_enable_keys() {
+        let mut state = state(656801822);
+        let q = state
+            .model()
+            .get_key_by_driver_value(DriverValue::Q as u32)
+            .unwrap()
+            .logic_code;
+        let d1 = state
+            .model()
+            .get_key_by_driver_value(DriverValue::D1 as u32)
+            .unwrap()
+            .logic_code;
+
+        let changes = state
+            .apply_disable_set(Layer::Base, false, DisableSet::Letter)
+            .unwrap();
+        assert_eq!(changes.len(), 26);
+        let layer = state.get_layer(Layer::Base).unwrap();
+        assert_eq!(layer.key_set[q as usize], DriverValue::Disabled as u32);
+        assert_eq!(layer.key_set[d1 as usize], DriverValue::D1 as u32);
+        assert_eq!(layer.disabled_by(false, q), Some(DisableSet::Letter));
+        assert_eq!(layer.disabled_by(true, q), None);
+        // The key which applies the set comes from keys.json, which is read on first use
+        let key = layer.disabled_by_key(false, q).unwrap();
+        assert_eq!(key.disable_set, Some(DisableSet::Letter));
+        assert!(layer.disabled_by_key(false, d1).is_none());
+
+        let changes = state
+            .apply_disable_set(Layer::Base, false, DisableSet::Enable)
+            .unwrap();
+        assert_eq!(changes.len(), 26);
+        let layer = state.get_layer(Layer::Base).unwrap();
+        assert_eq!(layer.key_set[q as usize], DriverValue::Q as u32);
+        assert_eq!(layer.disabled_by(false, q), None);
+    }
+
+    #[test]
+    fn copy_and_swap_layers() {
+        let mut state = state(656801822);
+        let macro_value = KeyAction::Macro { index: 1 }.to_u32();
+        let a = DriverValue::A as u32;
+        let value = |state: &State, layer: Layer, fn_layer: bool, logic_code: usize| {
+            state.get_layer(layer).unwrap().key_set(fn_layer)[logic_code]
+        };
+        state
+            .bind_key(Layer::Base, false, &KeyAddress::LogicCode(1), a)
+            .unwrap();
+        state
+            .bind_key(Layer::Base, false, &KeyAddress::LogicCode(2), macro_value)
+            .unwrap();
+        let layer1_key_2 = value(&state, Layer::Layer1, false, 2);
+        let layer1_fn_key_1 = value(&state, Layer::Layer1, true, 1);
+
+        // Keys bound to macros are only copied with MACROS
+        state
+            .copy_layer(Layer::Base, Layer::Layer1, LayerContents::KEY_SET)
+            .unwrap();
+        assert_eq!(value(&state, Layer::Layer1, false, 1), a);
+        assert_eq!(value(&state, Layer::Layer1, false, 2), layer1_key_2);
+        assert_eq!(value(&state, Layer::Layer1, true, 1), layer1_fn_key_1);
+        state
+            .copy_layer(Layer::Base, Layer::Layer1, LayerContents::MACROS)
+            .unwrap();
+        assert_eq!(value(&state, Layer::Layer1, false, 2), macro_value);
+
+        let base_key_3 = value(&state, Layer::Base, false, 3);
+        state
+            .bind_key(Layer::Layer2, false, &KeyAddress::LogicCode(3), a)
+            .unwrap();
+        let (changes_a, changes_b) = state
+            .swap_layers(Layer::Base, Layer::Layer2, LayerContents::all())
+            .unwrap();
+        assert!(!changes_a.is_empty() && !changes_b.is_empty());
+        assert_eq!(value(&state, Layer::Base, false, 3), a);
+        assert_eq!(value(&state, Layer::Layer2, false, 3), base_key_3);
+        assert_eq!(value(&state, Layer::Layer2, false, 2), macro_value);
+    }
+
+    #[test]
+    fn copy_factory_default_resets_a_layer() {
+        let mut state = state(656801822);
+        let factory = state.get_layer(Layer::Base).unwrap().clone();
+        let macro_value = KeyAction::Macro { index: 1 }.to_u32();
+        state
+            .bind_key(
+                Layer::Base,
+                false,
+                &KeyAddress::LogicCode(1),
+                DriverValue::A as u32,
+            )
+            .unwrap();
+        state
+            .bind_key(Layer::Base, true, &KeyAddress::LogicCode(2), macro_value)
+            .unwrap();
+
+        let changes = state
+            .copy_factory_default(Layer::Base, Layer::Base, LayerContents::KEY_SET)
+            .unwrap();
+        assert_eq!(changes.len(), 1);
+        let layer = state.get_layer(Layer::Base).unwrap();
+        assert_eq!(layer.key_set, factory.key_set);
+        assert_eq!(layer.fn_key_set[2], macro_value);
+
+        state
+            .copy_factory_default(Layer::Base, Layer::Base, LayerContents::all())
+            .unwrap();
+        let layer = state.get_layer(Layer::Base).unwrap();
+        assert_eq!(layer.fn_key_set, factory.fn_key_set);
+    }
+
+    #[test]
+    fn diff_factory_default_lists_rebound_keys() {
+        let mut state = state(656801822);
+        assert_eq!(state.diff_factory_default(Layer::Base).unwrap(), []);
+
+        let factory = state.get_layer(Layer::Base).unwrap().clone();
+        let a = DriverValue::A as u32;
+        state
+            .bind_key(Layer::Base, false, &KeyAddress::LogicCode(1), a)
+            .unwrap();
+        state
+            .bind_key(Layer::Base, true, &KeyAddress::LogicCode(5), a)
+            .unwrap();
+        assert_eq!(
+            state.diff_factory_default(Layer::Base).unwrap(),
+            [
+                KeyChange {
+                    logic_code: 1,
+                    fn_layer: false,
+                    old: factory.key_set[1],
+                    new: a,
+                },
+                KeyChange {
+                    logic_code: 5,
+                    fn_layer: true,
+                    old: factory.fn_key_set[5],
+                    new: a,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn keys_are_resolved_by_address() {
+        let mut state = state(656801822);
+        let esc = state
+            .model()
+            .get_key_by_driver_value(DriverValue::Esc as u32)
+            .unwrap()
+            .clone();
+        for address in [
+            KeyAddress::LogicCode(esc.logic_code),
+            KeyAddress::LocationCode(esc.location_code),
+            KeyAddress::DriverValueName("Esc".to_string()),
+        ] {
+            assert_eq!(state.resolve_keys(&address), Ok(vec![esc.logic_code]));
+        }
+        assert!(matches!(
+            state.resolve_keys(&KeyAddress::Name("nope".to_string())),
+            Err(LayerEditError::UnknownKey(_))
+        ));
+        assert_eq!(
+            state.resolve_keys(&KeyAddress::LogicCode(MAX_KEY_SET_SIZE as i32)),
+            Err(LayerEditError::OutOfRange {
+                logic_code: MAX_KEY_SET_SIZE as i32,
+                size: MAX_KEY_SET_SIZE,
+            })
+        );
+
+        // A selector binds every key it stands for, and only reports the ones which changed
+        let all = KeyAddress::Selector(KeySelector::All);
+        let logic_codes = state.resolve_keys(&all).unwrap();
+        let disabled = DriverValue::Disabled as u32;
+        let changes = state.bind_key(Layer::Base, false, &all, disabled).unwrap();
+        assert_eq!(changes.len(), logic_codes.len());
+        assert_eq!(
+            state.bind_key(Layer::Base, false, &all, disabled).unwrap(),
+            []
+        );
+        let keys = state.get_keys(Layer::Base, false, &all).unwrap();
+        assert!(keys.iter().all(|key| key.driver_value == disabled));
+        assert_eq!(
+            state.clear_key(Layer::Base, false, &all).unwrap().len(),
+            changes.len()
+        );
+    }
+
+    #[test]
+    fn key_press_lighting_is_indexed_by_short_value() {
+        let mut state = state(656801822);
+        let layer = state.get_layer_mut(Layer::Base).unwrap();
+        assert!(layer.set_key_press_lighting_effect(DriverValue::Esc as u32, 3));
+        assert_eq!(layer.key_press_lighting_effect[0x29], 3);
+        assert!(layer.set_key_press_lighting_effect(DriverValue::LCtrl as u32, 4));
+        assert_eq!(layer.key_press_lighting_effect[0xE0], 4);
+        assert!(!layer.set_key_press_lighting_effect(0x02000601, 5));
+    }
 }
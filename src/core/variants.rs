@@ -0,0 +1,245 @@
+use std::{fs, path::Path};
+
+use super::Layer;
+
+/// The name given to the main data file of a kind (`keymap.json`, `profile_offline_1.json`, etc)
+pub const DEFAULT_VARIANT: &str = "default";
+
+/// An alternative data file found in a model's folder.
+///
+/// Many models ship more than one layout (`keymap1.json`, `keymap - 副本.json`, ...) or default profile
+/// (`profiletemp.json`, "- 副本" copies, ...) and it's up to the user to pick the one which matches their physical
+/// board. Some models also ship whole alternative data folders next to `data`
+/// (`data - 副本/`, or the folder of a sibling model such as `656801853/656801854/data/`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataVariant {
+    /// The name used to select the variant ("default", "1", "副本", "副本 1", "656801854", etc)
+    pub name: String,
+    /// The path of the file in the model's folder ("data/keymap1.json", "data - 副本/profile.json")
+    pub path: String,
+    /// The layer a profile variant belongs to. `None` for layouts and for profiles which could apply to any layer
+    pub layer: Option<Layer>,
+}
+
+/// The main keymap / profiles along with all of their alternatives for a model
+#[derive(Debug, Clone, Default)]
+pub struct DataVariants {
+    pub layouts: Vec<DataVariant>,
+    pub profiles: Vec<DataVariant>,
+}
+
+impl DataVariants {
+    /// Finds the data files of the model folder `model_path` (`assets/device/<model id>`)
+    pub fn discover(model_path: impl AsRef<Path>) -> Self {
+        let model_path = model_path.as_ref();
+        let mut result = Self::default();
+        result.discover_folder(model_path, "data", "");
+
+        let Ok(entries) = fs::read_dir(model_path) else {
+            return result;
+        };
+        let mut folders = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        folders.sort();
+        for folder in folders {
+            if folder == "data" {
+                continue;
+            }
+            if let Some(suffix) = folder.strip_prefix("data") {
+                // A copy of the data folder ("data - 副本")
+                result.discover_folder(model_path, &folder, &variant_name(suffix));
+            } else if model_path.join(&folder).join("data").is_dir() {
+                // The folder of another model with the same board
+                result.discover_folder(model_path, &format!("{folder}/data"), &folder);
+            }
+        }
+        result
+    }
+
+    /// Adds the data files of `model_path/folder`, their variant names are prefixed with `prefix` (if any)
+    fn discover_folder(&mut self, model_path: &Path, folder: &str, prefix: &str) {
+        let Ok(entries) = fs::read_dir(model_path.join(folder)) else {
+            return;
+        };
+        let mut file_names = entries
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect::<Vec<_>>();
+        file_names.sort();
+
+        let prefixed = |name: String| match (prefix, name.as_str()) {
+            ("", _) => name,
+            (_, DEFAULT_VARIANT) => prefix.to_string(),
+            _ => format!("{prefix} {name}"),
+        };
+        for file_name in file_names {
+            let Some(stem) = file_name.strip_suffix(".json") else {
+                continue;
+            };
+            let path = format!("{folder}/{file_name}");
+
+            if NOT_VARIANTS.iter().any(|name| stem.starts_with(name)) {
+                continue;
+            }
+            if let Some(suffix) = stem.strip_prefix("keymap") {
+                self.layouts.push(DataVariant {
+                    name: prefixed(variant_name(suffix)),
+                    path,
+                    layer: None,
+                });
+            } else if let Some(suffix) = stem.strip_prefix("profile") {
+                let (layer, name) = profile_layer_and_name(suffix);
+                self.profiles.push(DataVariant {
+                    name: prefixed(name),
+                    path,
+                    layer,
+                });
+            }
+        }
+    }
+
+    pub fn layout(&self, name: &str) -> Option<&DataVariant> {
+        self.layouts.iter().find(|variant| variant.name == name)
+    }
+
+    /// Finds a profile which can be used for the given layer (either one made for the layer or a layer-less one)
+    pub fn profile(&self, layer: Layer, name: &str) -> Option<&DataVariant> {
+        self.profiles
+            .iter()
+            .filter(|variant| variant.name == name)
+            .find(|variant| variant.layer == Some(layer))
+            .or_else(|| {
+                self.profiles
+                    .iter()
+                    .find(|variant| variant.name == name && variant.layer.is_none())
+            })
+    }
+
+    /// All of the profile names which can be selected for the given layer
+    pub fn profiles_for_layer(&self, layer: Layer) -> impl Iterator<Item = &DataVariant> {
+        self.profiles
+            .iter()
+            .filter(move |variant| variant.layer.is_none() || variant.layer == Some(layer))
+    }
+}
+
+/// Data files which look like variants but can't stand in for a keymap / profile:
+/// - `keymapshow.json` is the picture of the device shown next to its lighting settings (light bars, mouse pads)
+/// - `profile_offline_std.json` only lists the lighting effects of the vendor software (`DriverLE`), it has no key sets
+const NOT_VARIANTS: &[&str] = &["keymapshow", "profile_offline_std"];
+
+/// Gets the file name suffix used by the main profile of a layer (`profile{suffix}.json`)
+pub fn profile_layer_suffix(layer: Layer) -> &'static str {
+    match layer {
+        Layer::Driver => "_online_1",
+        Layer::Layer1 => "_offline_1",
+        Layer::Layer2 => "_offline_2",
+        Layer::Layer3 => "_offline_3",
+        _ => "",
+    }
+}
+
+/// Turns what's left of a file name after the "keymap" / "profile_offline_1" part into a variant name
+/// ("" => "default", "1" => "1", " - 副本" => "副本", " (2)" => "(2)")
+fn variant_name(suffix: &str) -> String {
+    let name = suffix.trim_start_matches([' ', '-', '_']);
+    if name.is_empty() {
+        DEFAULT_VARIANT.to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn profile_layer_and_name(suffix: &str) -> (Option<Layer>, String) {
+    for layer in [Layer::Driver, Layer::Layer1, Layer::Layer2, Layer::Layer3] {
+        if let Some(rest) = suffix.strip_prefix(profile_layer_suffix(layer)) {
+            return (Some(layer), variant_name(rest));
+        }
+    }
+
+    if suffix.is_empty() {
+        return (Some(Layer::Base), DEFAULT_VARIANT.to_string());
+    }
+    (None, variant_name(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_names_are_turned_into_variants() {
+        assert_eq!(variant_name(""), DEFAULT_VARIANT);
+        assert_eq!(variant_name(" - 副本"), "副本");
+        assert_eq!(
+            profile_layer_and_name("_offline_2 (2)"),
+            (Some(Layer::Layer2), "(2)".to_string())
+        );
+        assert_eq!(
+            profile_layer_and_name(""),
+            (Some(Layer::Base), DEFAULT_VARIANT.to_string())
+        );
+        assert_eq!(profile_layer_and_name("temp"), (None, "temp".to_string()));
+    }
+
+    #[test]
+    fn copies_of_the_data_folder_are_variants() {
+        let variants = DataVariants::discover("assets/device/656801822");
+        assert_eq!(
+            variants.layout(DEFAULT_VARIANT).unwrap().path,
+            "data/keymap.json"
+        );
+        assert_eq!(
+            variants.layout("副本").unwrap().path,
+            "data - 副本/keymap.json"
+        );
+        assert_eq!(
+            variants.profile(Layer::Layer1, "副本").unwrap().path,
+            "data - 副本/profile_offline_1.json"
+        );
+        assert_eq!(
+            variants.profile(Layer::Base, "副本").unwrap().path,
+            "data - 副本/profile.json"
+        );
+    }
+
+    #[test]
+    fn files_which_cant_be_selected_are_skipped() {
+        // Every model folder has a `profile_offline_std.json` without key sets
+        let variants = DataVariants::discover("assets/device/656801822");
+        assert!(variants
+            .profiles
+            .iter()
+            .all(|variant| !variant.path.contains("profile_offline_std")));
+        assert!(variants.profile(Layer::Base, "std").is_none());
+
+        // 655491218 has a `keymapshow.json` next to its `keymap.json`
+        let variants = DataVariants::discover("assets/device/655491218");
+        assert!(variants.layout("show").is_none());
+        assert_eq!(
+            variants
+                .layouts
+                .iter()
+                .map(|variant| variant.path.as_str())
+                .collect::<Vec<_>>(),
+            ["data/keymap.json"]
+        );
+    }
+
+    #[test]
+    fn nested_model_folders_are_variants() {
+        let variants = DataVariants::discover("assets/device/656801853");
+        assert_eq!(
+            variants.layout("656801854").unwrap().path,
+            "656801854/data/keymap.json"
+        );
+        assert!(variants
+            .profiles
+            .iter()
+            .any(|variant| variant.path.starts_with("656801854/data/")));
+    }
+}
@@ -0,0 +1,420 @@
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::js_value::JsValue;
+
+/// Found in the key list (`0.js`), the last of the keys.json groups
+const KEYS_MARKER: &str = "mode-layer-temp-switch";
+/// Found in every language table (`index.js`)
+const LANGS_MARKER: &str = "macro_cate_already_exist";
+
+const KEYS_SOURCES: &[&str] = &["0.formatted.js", "0.js", "0.json"];
+const LANGS_SOURCES: &[&str] = &["index.formatted.js", "index.js"];
+
+#[derive(Debug)]
+pub enum UpdateError {
+    /// No `CMSEngine/driver` folder was found in the given path
+    NoDriverFolders(PathBuf),
+    Io(PathBuf, io::Error),
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpdateError::NoDriverFolders(path) => write!(
+                f,
+                "no `CMSEngine/driver` folders found in `{}`",
+                path.display()
+            ),
+            UpdateError::Io(path, err) => write!(f, "`{}`: {}", path.display(), err),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+/// What was imported by [`update_data`]
+#[derive(Debug, Default)]
+pub struct UpdateSummary {
+    pub driver_folders: Vec<PathBuf>,
+    /// The file the keys were taken from and the number of groups in it
+    pub keys: Option<(PathBuf, usize)>,
+    /// The names of the language tables which were written
+    pub languages: Vec<String>,
+    /// The number of files written into `device`
+    pub device_files: usize,
+    /// Device files which couldn't be parsed and were copied as they are (GBK encoded, not JSON, etc)
+    pub copied_as_is: Vec<PathBuf>,
+    /// Data which couldn't be found in any of the driver folders
+    pub warnings: Vec<String>,
+}
+
+/// Imports the data files from one or more unpacked vendor drivers into `assets_path`.
+///
+/// `source` is either a driver folder ("GK6XPlus Driver") or a folder containing several of them. The key list and
+/// language tables are extracted from the first driver which has them, the `device` trees of every driver are merged
+/// (later drivers win). Everything which parses is rewritten as plain JSON with tab indentation, which takes care of
+/// BOMs, JS declared keymaps and the mix of formatting styles the vendor uses.
+pub fn update_data(
+    source: impl AsRef<Path>,
+    assets_path: impl AsRef<Path>,
+) -> Result<UpdateSummary, UpdateError> {
+    let source = source.as_ref();
+    let assets_path = assets_path.as_ref();
+
+    let mut summary = UpdateSummary {
+        driver_folders: find_driver_folders(source),
+        ..Default::default()
+    };
+    if summary.driver_folders.is_empty() {
+        return Err(UpdateError::NoDriverFolders(source.to_path_buf()));
+    }
+
+    for driver_path in summary.driver_folders.clone() {
+        if summary.keys.is_none() {
+            if let Some((file, keys)) = find_source(&driver_path, KEYS_SOURCES, extract_keys) {
+                let group_count = keys.as_array().map_or(0, |groups| groups.len());
+                write_json(&assets_path.join("keys.json"), &keys)?;
+                summary.keys = Some((file, group_count));
+            }
+        }
+
+        if summary.languages.is_empty() {
+            if let Some((_, languages)) = find_source(&driver_path, LANGS_SOURCES, |text| {
+                Some(extract_languages(text)).filter(|languages| !languages.is_empty())
+            }) {
+                let langs_path = assets_path.join("i18n").join("langs");
+                for (name, table) in languages {
+                    write_json(&langs_path.join(format!("{}.json", name)), &table)?;
+                    summary.languages.push(name);
+                }
+            }
+        }
+
+        let device_path = driver_path.join("device");
+        if device_path.is_dir() {
+            copy_device_tree(&device_path, &assets_path.join("device"), &mut summary)?;
+        }
+    }
+
+    if summary.keys.is_none() {
+        summary
+            .warnings
+            .push(format!("Couldn't find the key list (\"{KEYS_MARKER}\")"));
+    }
+    if summary.languages.is_empty() {
+        summary.warnings.push(format!(
+            "Couldn't find the language tables (\"{LANGS_MARKER}\")"
+        ));
+    }
+    Ok(summary)
+}
+
+/// Finds every `CMSEngine/driver` folder in (or at) the given path, sorted by path
+pub fn find_driver_folders(path: impl AsRef<Path>) -> Vec<PathBuf> {
+    let path = path.as_ref();
+    let driver_path = |path: &Path| {
+        [
+            path.join("CMSEngine").join("driver"),
+            path.join("driver"),
+            path.to_path_buf(),
+        ]
+        .into_iter()
+        .find(|candidate| candidate.join("device").is_dir() || candidate.join("index.js").is_file())
+    };
+
+    if let Some(found) = driver_path(path) {
+        return vec![found];
+    }
+
+    let mut result = fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| driver_path(&path))
+        .collect::<Vec<_>>();
+    result.sort();
+    result
+}
+
+/// Extracts the array of key groups (what becomes `keys.json`) from the driver's JS
+pub fn extract_keys(text: &str) -> Option<JsValue> {
+    text.match_indices(KEYS_MARKER).find_map(|(marker, _)| {
+        find_enclosing_literal(text, marker, b'[', |value| {
+            value.as_array().is_some_and(|groups| {
+                groups.iter().any(|group| {
+                    group.get("keytype").and_then(JsValue::as_str) == Some(KEYS_MARKER)
+                })
+            })
+        })
+    })
+}
+
+/// Extracts every language table from the driver's JS, along with the name of the language ("en", "zh", ...)
+pub fn extract_languages(text: &str) -> Vec<(String, JsValue)> {
+    let mut result: Vec<(String, JsValue)> = Vec::new();
+    let mut last_end = 0;
+    for (marker, _) in text.match_indices(LANGS_MARKER) {
+        if marker < last_end {
+            continue;
+        }
+        // The marker lives in a category ("macro") of the language's root object
+        let Some(start) = find_enclosing_start(text, marker, b'{', |value| match value {
+            JsValue::Object(entries) => entries
+                .iter()
+                .any(|(_, category)| category.get(LANGS_MARKER).is_some()),
+            _ => false,
+        }) else {
+            continue;
+        };
+        let (table, end) = JsValue::parse_at(text, start).unwrap();
+        last_end = end;
+
+        let mut name = language_name(text, start, &table);
+        if result.iter().any(|(existing, _)| *existing == name) {
+            name = format!("{}{}", name, result.len() + 1);
+        }
+        result.push((name, table));
+    }
+    result
+}
+
+/// The key the language table is assigned to (`en: {...}`), otherwise a guess based on its contents
+fn language_name(text: &str, start: usize, table: &JsValue) -> String {
+    let before = text[..start].trim_end();
+    if let Some(before) = before.strip_suffix(':') {
+        let before = before.trim_end();
+        let key = before
+            .strip_suffix(['"', '\''])
+            .map(|quoted| &quoted[quoted.rfind(['"', '\'']).map_or(0, |i| i + 1)..])
+            .unwrap_or_else(|| {
+                let start = before
+                    .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .map_or(0, |i| i + 1);
+                &before[start..]
+            });
+        let is_locale = (2..=5).contains(&key.len())
+            && key.starts_with(|c: char| c.is_ascii_lowercase())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphabetic() || c == '-' || c == '_');
+        if is_locale {
+            return key.to_string();
+        }
+    }
+
+    // Webpack modules are usually just `e.exports = {...}`, tell the two shipped languages apart by their text
+    if contains_han(table) {
+        "zh".to_string()
+    } else {
+        "en".to_string()
+    }
+}
+
+fn contains_han(value: &JsValue) -> bool {
+    match value {
+        JsValue::String(text) => text.chars().any(|c| ('\u{4e00}'..='\u{9fff}').contains(&c)),
+        JsValue::Array(values) => values.iter().any(contains_han),
+        JsValue::Object(entries) => entries.iter().any(|(_, value)| contains_han(value)),
+        _ => false,
+    }
+}
+
+fn find_enclosing_literal(
+    text: &str,
+    position: usize,
+    open: u8,
+    predicate: impl Fn(&JsValue) -> bool,
+) -> Option<JsValue> {
+    let start = find_enclosing_start(text, position, open, predicate)?;
+    JsValue::parse_at(text, start).ok().map(|(value, _)| value)
+}
+
+/// Finds the closest literal starting with `open` which contains `position` and satisfies the predicate. Only the
+/// brackets which are still open at `position` are parsed, see [`open_brackets`].
+fn find_enclosing_start(
+    text: &str,
+    position: usize,
+    open: u8,
+    predicate: impl Fn(&JsValue) -> bool,
+) -> Option<usize> {
+    let bytes = text.as_bytes();
+    open_brackets(text, position)
+        .into_iter()
+        .rev()
+        .filter(|&start| bytes[start] == open)
+        .find(|&start| match JsValue::parse_at(text, start) {
+            Ok((value, end)) => end > position && predicate(&value),
+            Err(_) => false,
+        })
+}
+
+/// The positions of the brackets (`[`, `{`, `(`) which are open at `position`, outermost first. The text before
+/// `position` is scanned once, skipping strings, comments and regular expressions.
+fn open_brackets(text: &str, position: usize) -> Vec<usize> {
+    let bytes = &text.as_bytes()[..position];
+    let mut open = vec![];
+    // The last byte of code which isn't whitespace, a `/` after a value is a division rather than a regular expression
+    let mut previous = b'(';
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match b {
+            b'/' if bytes.get(i + 1) == Some(&b'/') => {
+                i = bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |offset| i + offset);
+                continue;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = text[i + 2..position]
+                    .find("*/")
+                    .map_or(bytes.len(), |end| i + 2 + end + 2);
+                continue;
+            }
+            b'[' | b'{' | b'(' => open.push(i),
+            b']' | b'}' | b')' => {
+                let opening = match b {
+                    b']' => b'[',
+                    b'}' => b'{',
+                    _ => b'(',
+                };
+                if let Some(index) = open.iter().rposition(|&start| bytes[start] == opening) {
+                    open.truncate(index);
+                }
+            }
+            b'"' | b'\'' | b'`' => i = skip_quoted(bytes, i, b),
+            b'/' if !(previous.is_ascii_alphanumeric() || b"_$)]}\"'`".contains(&previous)) => {
+                i = skip_quoted(bytes, i, b'/');
+            }
+            _ => {}
+        }
+        if !b.is_ascii_whitespace() {
+            previous = bytes.get(i).copied().unwrap_or(b);
+        }
+        i += 1;
+    }
+    open
+}
+
+/// The position of the quote ending the string (or regular expression) which starts at `start`
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8) -> usize {
+    let mut in_class = false;
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'[' if quote == b'/' => in_class = true,
+            b']' if quote == b'/' => in_class = false,
+            b if b == quote && !in_class => return i,
+            _ => {}
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
+fn find_source<T>(
+    driver_path: &Path,
+    file_names: &[&str],
+    extract: impl Fn(&str) -> Option<T>,
+) -> Option<(PathBuf, T)> {
+    file_names.iter().find_map(|file_name| {
+        let path = driver_path.join(file_name);
+        let text = fs::read(&path).ok()?;
+        let value = extract(&String::from_utf8_lossy(&text))?;
+        Some((path, value))
+    })
+}
+
+fn copy_device_tree(
+    source: &Path,
+    destination: &Path,
+    summary: &mut UpdateSummary,
+) -> Result<(), UpdateError> {
+    let io_error = |path: &Path| {
+        let path = path.to_path_buf();
+        move |err| UpdateError::Io(path, err)
+    };
+
+    let mut entries = fs::read_dir(source)
+        .map_err(io_error(source))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    entries.sort();
+
+    for path in entries {
+        let target = destination.join(path.file_name().unwrap());
+        if path.is_dir() {
+            copy_device_tree(&path, &target, summary)?;
+            continue;
+        }
+
+        let bytes = fs::read(&path).map_err(io_error(&path))?;
+        let parsed = if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|text| JsValue::parse(text).ok())
+        } else {
+            None
+        };
+        match parsed {
+            Some(value) => write_json(&target, &value)?,
+            None => {
+                if path
+                    .extension()
+                    .is_some_and(|extension| extension == "json")
+                {
+                    summary.copied_as_is.push(path.clone());
+                }
+                fs::create_dir_all(destination).map_err(io_error(destination))?;
+                fs::write(&target, bytes).map_err(io_error(&target))?;
+            }
+        }
+        summary.device_files += 1;
+    }
+    Ok(())
+}
+
+fn write_json(path: &Path, value: &JsValue) -> Result<(), UpdateError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|err| UpdateError::Io(parent.to_path_buf(), err))?;
+    }
+    fs::write(path, value.to_json_pretty() + "\n")
+        .map_err(|err| UpdateError::Io(path.to_path_buf(), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn brackets_in_strings_comments_and_regexes_are_skipped() {
+        let text = concat!(
+            r#"var a="[{",b=/[}\]]/g,c=x/2/1;/* } */ // ]"#,
+            "\n",
+            r#"e.exports=[{title:'}',keytype:"mode-layer-temp-switch",keys:[]}]"#,
+        );
+        let marker = text.find(KEYS_MARKER).unwrap();
+        let starts = open_brackets(text, marker);
+        assert_eq!(
+            starts
+                .iter()
+                .map(|&start| &text[start..=start])
+                .collect::<Vec<_>>(),
+            ["[", "{"]
+        );
+
+        let keys = extract_keys(text).unwrap();
+        assert_eq!(keys.as_array().unwrap().len(), 1);
+    }
+}
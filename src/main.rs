@@ -1,8 +1,10 @@
 #[macro_use]
 extern crate num_derive;
 
+mod commands;
 mod core;
 
 fn main() {
-    println!("Hello, world!");
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    std::process::exit(commands::run(&args));
 }
@@ -0,0 +1,57 @@
+use std::process::Command;
+
+/// Presets compose (the later one wins), keys the board doesn't have are skipped
+#[test]
+fn apply_composed_presets() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args([
+            "apply-preset",
+            "655491085",
+            "dvorak+wasd-arrows+caps-as-ctrl",
+        ])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    for line in [
+        "Q: Q -> Quotes",
+        ";: Semicolon -> S",
+        "W: W -> Up",
+        "S: S -> Down",
+        "Caps Lock: CapsLock -> LCtrl",
+        "Skipped: dvorak: W (overridden by a later preset)",
+        "Skipped: wasd-arrows: Up (the model doesn't have the key)",
+    ] {
+        assert!(lines.contains(&line), "missing `{line}` in:\n{stdout}");
+    }
+    assert!(!lines.contains(&"W: W -> Comma"));
+}
+
+/// The numpad preset on the Fn keys of a layer
+#[test]
+fn apply_preset_to_fn_keys() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args([
+            "apply-preset",
+            "655491085",
+            "numpad",
+            "--layer",
+            "Layer2",
+            "--fn",
+        ])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout
+        .lines()
+        .any(|line| line == "Fn+U: PrintScreen -> NumPad4"));
+    assert!(stdout.lines().any(|line| line == "Fn+M: Delete -> NumPad0"));
+}
@@ -0,0 +1,64 @@
+use std::process::Command;
+
+fn check_layout(args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .arg("check-layout")
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+/// The factory default profiles are fine
+#[test]
+fn factory_default_layers_pass() {
+    assert_eq!(check_layout(&["655491085", "--strict"]), (0, String::new()));
+}
+
+/// Remapping W / P / ; on the Fn keys shadows shortcuts, the other layer shortcuts still lead back
+#[test]
+fn shadowed_shortcuts_are_warnings() {
+    let (code, stdout) = check_layout(&[
+        "655491085",
+        "--preset",
+        "wasd-arrows+numpad",
+        "--layer",
+        "Layer1",
+        "--fn",
+    ]);
+    assert_eq!(code, 0);
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        [
+            "Layer1 Warning: Fn+W (Switch to onboard layer 1 or standard layer) is shadowed by Fn+W bound to Up",
+            "Layer1 Warning: Fn+P (Brightness -) is shadowed by Fn+P bound to NumPadAsterisk",
+            "Layer1 Warning: Fn+; (Speed -) is shadowed by Fn+; bound to NumPadSubtract",
+        ]
+    );
+}
+
+/// Dvorak on every Fn layer takes all of the layer shortcuts, only the base layer is left
+#[test]
+fn layers_without_a_way_back_are_errors() {
+    let (code, stdout) = check_layout(&["655491085", "--preset", "dvorak", "--fn"]);
+    assert_eq!(code, 1);
+    let errors = stdout
+        .lines()
+        .filter(|line| line.contains(" Error: "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            "Layer1 Error: there is no way back to the base layer",
+            "Layer2 Error: there is no way back to the base layer",
+            "Layer3 Error: there is no way back to the base layer",
+            "Driver Error: there is no way back to the base layer",
+        ]
+    );
+    assert!(stdout.lines().any(|line| line
+        == "Base Warning: Fn+ESC+Q (Clear all layers configurations) is shadowed by Fn+Q bound to Quotes"));
+}
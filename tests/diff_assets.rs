@@ -0,0 +1,52 @@
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+fn diff_assets(old: &str, new: &str) -> Vec<Value> {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["diff-assets", old, new, "--json"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+/// Every kind of change between the two small trees in `tests/fixtures/asset_diff`
+#[test]
+fn diff_assets_reports_fixture_changes() {
+    let changes = diff_assets(
+        "tests/fixtures/asset_diff/old",
+        "tests/fixtures/asset_diff/new",
+    );
+    let expected = [
+        json!({ "kind": "model_info_changed", "model_id": 655360001, "field": "firmwareID", "old": "0x00000001", "new": "0x00000003" }),
+        json!({ "kind": "config_changed", "model_id": 655360001, "field": "IsFnKeySet", "old": true, "new": false }),
+        json!({ "kind": "key_changed", "model_id": 655360001, "file": "keymap.json", "location_code": 2, "key_name": "F1", "field": "LogicCode", "old": 63, "new": 65 }),
+        json!({ "kind": "key_removed", "model_id": 655360001, "file": "keymap.json", "location_code": 3, "key_name": "F2" }),
+        json!({ "kind": "key_added", "model_id": 655360001, "file": "keymap.json", "location_code": 4, "key_name": "F3" }),
+        json!({ "kind": "key_set_changed", "model_id": 655360001, "file": "profile.json", "key_set": "KeySet", "index": 47, "old": "0x02002900", "new": "0x02003900" }),
+        json!({ "kind": "file_added", "model_id": 655360001, "file": "profile_offline_1.json" }),
+        json!({ "kind": "model_removed", "model_id": 655360002, "name": "Old Board" }),
+        json!({ "kind": "model_added", "model_id": 655360003, "name": "New Board" }),
+        json!({ "kind": "group_added", "group": "media", "title": "多媒体" }),
+        json!({ "kind": "group_removed", "group": "mouse", "title": "鼠标" }),
+        json!({ "kind": "catalog_key_changed", "group": "primary", "driver_value": "0x02002900", "name": "Esc", "field": "IsCombFunc", "old": true, "new": false }),
+        json!({ "kind": "catalog_key_removed", "group": "primary", "driver_value": "0x02003A00", "name": "F1" }),
+        json!({ "kind": "catalog_key_added", "group": "primary", "driver_value": "0x02003C00", "name": "F3" }),
+    ];
+    for change in &expected {
+        assert!(changes.contains(change), "missing {change}");
+    }
+    // Plus the position of F1. The BOM, JS keymap and "0x02003a00" vs "0x02003A00" aren't changes.
+    assert_eq!(changes.len(), expected.len() + 1);
+}
+
+#[test]
+fn diff_assets_of_same_tree_is_empty() {
+    assert!(diff_assets("assets", "assets").is_empty());
+}
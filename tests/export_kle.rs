@@ -0,0 +1,27 @@
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+/// Layer1 of a 61 key board: the metadata and five rows, with the bound actions as legends
+#[test]
+fn export_kle_of_factory_layer() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["export-kle", "655491085", "--layer", "Layer1"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let kle: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let rows = kle.as_array().unwrap();
+    assert_eq!(rows.len(), 6);
+    assert_eq!(rows[0], json!({ "name": "GK61 RGB - Layer1" }));
+    assert_eq!(rows[1][0], "Esc");
+    assert_eq!(rows[1][13], json!({ "w": 2.0 }));
+    // Layer1 has arrows on the bottom right, the key's own label is on the front
+    assert_eq!(rows[4][11], "Up\n\n\n\n/");
+    assert_eq!(rows[5][15], "Fn");
+}
@@ -0,0 +1,51 @@
+use std::process::Command;
+
+use serde_json::Value;
+
+/// The factory keymap of a 61 key board: two QMK layers (keys, Fn keys) per onboard layer, in row order
+#[test]
+fn export_qmk_of_factory_default() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["export-qmk", "655491085"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let keymap: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let layers = keymap["layers"].as_array().unwrap();
+    assert_eq!(layers.len(), 8);
+    assert!(layers
+        .iter()
+        .all(|layer| layer.as_array().unwrap().len() == 61));
+
+    let base = layers[0].as_array().unwrap();
+    assert_eq!(base[0], "KC_ESC");
+    assert_eq!(base[14], "KC_TAB");
+    // The Fn key is last (bottom right) and holds the base layer's Fn layer
+    assert_eq!(base[60], "MO(1)");
+    // Layer1 has arrows on the bottom right and Layer3 on the right shift
+    let layer1 = layers[2].as_array().unwrap();
+    assert_eq!(layer1[51], "KC_UP");
+    assert_eq!(layer1[52], "MO(6)");
+}
+
+/// Profiles which can't be read (656801803 has GBK encoded names in layers 1 and 2) don't stop the export
+#[test]
+fn export_qmk_with_unreadable_profiles() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["export-qmk", "656801803"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let keymap: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(keymap["layers"].as_array().unwrap().len(), 8);
+}
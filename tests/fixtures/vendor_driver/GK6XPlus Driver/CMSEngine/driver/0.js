@@ -0,0 +1 @@
+webpackJsonp([0],{"+1Ab":function(e,t){e.exports=[{title:"基本功能",keytype:"primary",lang:"kb_primary",icon:"el-icon-kb-keyboard",pid:"0",pname:"singlebutton",keys:[{linekeys:[{LocationCode:0,LogicCode:47,Name:"Esc",LangTitle:"key_esc",DriverValue:"0x02002900",IsCombFunc:!0},{LocationCode:-1,LogicCode:-1,Name:"禁用",LangTitle:"key_disable",Icon:"el-icon-kb-disable",DriverValue:"0x02000000",Class:"el-button--danger"}]}]},{title:"层切换",keytype:"mode-layer-temp-switch",lang:"kb_layer",icon:"el-icon-kb-layer",pid:"0",pname:"singlebutton",keys:[{linekeys:[{LocationCode:-1,LogicCode:-1,Name:'Layer 1',LangTitle:"key_layer1",DriverValue:"0x0A070001",IsCombFunc:!1}]}]}]},"2xYz":function(e,t,n){var r=n("+1Ab");/* [not, data] */e.exports={keys:r,count:r.length}}});
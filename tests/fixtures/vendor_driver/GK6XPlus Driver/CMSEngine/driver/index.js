@@ -0,0 +1 @@
+webpackJsonp([1],{"3Kq1":function(e,t){e.exports={common:{set:"设置",cancel:"取消"},macro:{macro_cate_already_exist:"已经存在该宏分类!"},keys:{key_esc:"Esc",'"key_lctrl + ↑"':"Ctrl+↑"}}},"NHnr":function(e,t,n){"use strict";var o=new i.a({locale:"en",messages:{zh:n("3Kq1"),en:{common:{set:"Set",cancel:"Cancel"},macro:{macro_cate_already_exist:"Macro category already exist!"},keys:{key_esc:"Esc",'"key_lctrl + ↑"':"Ctrl+↑"}}}})}});
@@ -0,0 +1,30 @@
+use std::process::Command;
+
+use serde_json::{json, Value};
+
+/// Positions, sizes and labels of a small KLE file, as vendor style keymap keys
+#[test]
+fn import_kle_as_keymap() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["import-kle", "tests/fixtures/kle/layout.json"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let keys: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let position = |left: i32, top: i32, width: i32| json!({ "Left": left, "Top": top, "Width": width, "Height": 50 });
+    assert_eq!(
+        keys,
+        json!([
+            { "KeyName": "Esc", "Show": "Esc", "LogicCode": -1, "LocationCode": 0, "Position": position(0, 0, 50) },
+            { "KeyName": "F1", "Show": "F1", "LogicCode": -1, "LocationCode": 1, "Position": position(81, 0, 50) },
+            { "KeyName": "Tab", "Show": "Tab", "LogicCode": -1, "LocationCode": 2, "Position": position(0, 54, 77) },
+            { "KeyName": "Q", "Show": "Q", "LogicCode": -1, "LocationCode": 3, "Position": position(81, 68, 50) },
+            { "KeyName": "Shift", "Show": "Shift", "LogicCode": -1, "LocationCode": 4, "Position": position(14, 122, 104) },
+        ])
+    );
+}
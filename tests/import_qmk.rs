@@ -0,0 +1,56 @@
+use std::process::Command;
+
+fn import_qmk(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .arg("import-qmk")
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr),
+    )
+}
+
+/// A 60% QMK keymap placed by geometry onto a 61 key board, the Fn key's `MO(1)` makes layer 1 the Fn layer
+#[test]
+fn import_qmk_by_geometry() {
+    let (success, output) = import_qmk(&[
+        "655491085",
+        "tests/fixtures/qmk/keymap.json",
+        "--layout",
+        "tests/fixtures/qmk/info.json",
+    ]);
+    assert!(success, "{output}");
+    let lines = output.lines().collect::<Vec<_>>();
+    for expected in [
+        "QMK layer 0 -> Layer1",
+        "QMK layer 1 -> Layer1 Fn",
+        "QMK layer 2 -> Layer2",
+        "QMK layer 3 -> Layer3",
+        "Layer1: Caps Lock: CapsLock -> LCtrl",
+        "Layer1: Right Alt: Left -> Layer2.Switch",
+        "Layer1: Fn+Q: Unused -> LCtrl+LShift+Esc",
+        "Layer1: Fn+A: A -> Left",
+        "Layer1: Fn+R: Unused -> Media.PlayPause",
+        "Layer2: Space: Space -> Layer3.Temp",
+        "Layer3: 1: F1 -> D1",
+        "Not placed: key 61: no model key at this position",
+        "Not placed: layer 1, key 17 (RGB_TOG): unknown or unsupported keycode",
+    ] {
+        assert!(
+            lines.contains(&expected),
+            "missing `{expected}` in\n{output}"
+        );
+    }
+}
+
+/// Without a layout the keymap has to have the model's key count
+#[test]
+fn import_qmk_without_layout_needs_model_order() {
+    let (success, output) = import_qmk(&["655491085", "tests/fixtures/qmk/keymap.json"]);
+    assert!(!success);
+    assert!(output.contains("62 keys, the model has 61"), "{output}");
+}
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+/// The committed lint report must match the assets, so changes to the vendor data show up in review
+#[test]
+fn lint_report_is_up_to_date() {
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["lint-assets", "--check"])
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,79 @@
+use std::{fs, path::Path, process::Command};
+
+use serde_json::{json, Value};
+
+fn read_json(path: impl AsRef<Path>) -> Value {
+    let path = path.as_ref();
+    let text = fs::read_to_string(path).unwrap_or_else(|err| panic!("{}: {err}", path.display()));
+    serde_json::from_str(&text).unwrap_or_else(|err| panic!("{}: {err}", path.display()))
+}
+
+/// Imports the small driver in `tests/fixtures/vendor_driver` and checks everything came out as plain JSON
+#[test]
+fn update_data_imports_fixture_driver() {
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("update_data");
+    let _ = fs::remove_dir_all(&out);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_gk6xui"))
+        .args(["update_data", "tests/fixtures/vendor_driver", "--out"])
+        .arg(&out)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let keys = read_json(out.join("keys.json"));
+    let groups = keys.as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[1]["keytype"], "mode-layer-temp-switch");
+    assert_eq!(
+        groups[0]["keys"][0]["linekeys"][0],
+        json!({
+            "LocationCode": 0,
+            "LogicCode": 47,
+            "Name": "Esc",
+            "LangTitle": "key_esc",
+            "DriverValue": "0x02002900",
+            "IsCombFunc": true
+        })
+    );
+    assert_eq!(groups[1]["keys"][0]["linekeys"][0]["IsCombFunc"], false);
+    // Key order is kept as the vendor wrote it
+    let text = fs::read_to_string(out.join("keys.json")).unwrap();
+    assert!(text.starts_with("[\n\t{\n\t\t\"title\": \"基本功能\",\n\t\t\"keytype\""));
+
+    let en = read_json(out.join("i18n/langs/en.json"));
+    assert_eq!(
+        en["macro"]["macro_cate_already_exist"],
+        "Macro category already exist!"
+    );
+    assert_eq!(en["keys"]["\"key_lctrl + ↑\""], "Ctrl+↑");
+    let zh = read_json(out.join("i18n/langs/zh.json"));
+    assert_eq!(zh["common"]["set"], "设置");
+
+    let device = out.join("device");
+    assert_eq!(
+        read_json(device.join("models.json"))[0]["modelID"],
+        655360001
+    );
+    assert_eq!(
+        read_json(device.join("655360001/config.json")),
+        json!({ "DeviceType": 0, "IsFnKeySet": true })
+    );
+    let keymap = read_json(device.join("655360001/data/keymap.json"));
+    assert_eq!(keymap[1]["KeyName"], "F1");
+    assert_eq!(keymap[1]["Position"]["Left"], 100);
+    assert_eq!(
+        read_json(device.join("655360001/data/profile.json"))["KeySet"][0]["DriverValue"],
+        "0x02002900"
+    );
+    // Not JSON, copied as it is
+    assert_eq!(
+        fs::read(device.join("655360001/data/le/breath.le.json")).unwrap(),
+        b"LE\x01\x02not json"
+    );
+}